@@ -19,6 +19,9 @@ pub mod lib {
     pub const SK_RESOURCES: &str = "skresources";
     pub const SK_UNICODE_CORE: &str = "skunicode_core";
     pub const SK_UNICODE_ICU: &str = "skunicode_icu";
+    pub const SK_UNICODE_CLIENT_ICU: &str = "skunicode_client_icu";
+    pub const SK_UNICODE_ICU4X: &str = "skunicode_icu4x";
+    pub const SK_UNICODE_LIBGRAPHEME: &str = "skunicode_libgrapheme";
 }
 
 /// The configuration of the resulting binaries.
@@ -60,16 +63,25 @@ impl BinariesConfiguration {
         let mut binding_libraries = Vec::new();
         let binding_files = vec!["bindings.rs".into()];
         let mut additional_files = Vec::new();
-        let feature_ids = features.ids();
+        let feature_ids = features.ids(&target);
 
         if features.text_layout {
-            if target.is_windows() {
-                additional_files.push(ICUDTL_DAT.into());
-            }
             ninja_built_libraries.push(lib::SK_PARAGRAPH.into());
             ninja_built_libraries.push(lib::SK_SHAPER.into());
             ninja_built_libraries.push(lib::SK_UNICODE_CORE.into());
+        }
+        if features.unicode_icu() {
+            if target.is_windows() {
+                additional_files.push(ICUDTL_DAT.into());
+            }
             ninja_built_libraries.push(lib::SK_UNICODE_ICU.into());
+            ninja_built_libraries.push(lib::SK_UNICODE_CLIENT_ICU.into());
+        }
+        if features.unicode_icu4x {
+            ninja_built_libraries.push(lib::SK_UNICODE_ICU4X.into());
+        }
+        if features.unicode_libgrapheme {
+            ninja_built_libraries.push(lib::SK_UNICODE_LIBGRAPHEME.into());
         }
         if features.svg {
             ninja_built_libraries.push(lib::SVG.into());
@@ -72,6 +72,11 @@ impl Target {
         self.system == "windows"
     }
 
+    /// Linux, but not Android.
+    pub fn is_linux(&self) -> bool {
+        self.system == "linux" && !matches!(self.abi.as_deref(), Some("android" | "androideabi"))
+    }
+
     pub fn builds_with_msvc(&self) -> bool {
         self.abi.as_deref() == Some("msvc")
     }
@@ -1,5 +1,7 @@
 use std::collections::HashSet;
 
+use crate::build_support::cargo;
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Features {
     /// Build with OpenGL support?
@@ -26,9 +28,39 @@ pub struct Features {
     /// Features related to text layout. Modules skshaper and skparagraph.
     pub text_layout: bool,
 
+    /// Use ICU4X instead of ICU as the Unicode implementation for text layout.
+    pub unicode_icu4x: bool,
+
+    /// Use libgrapheme instead of ICU as the Unicode implementation for text layout.
+    pub unicode_libgrapheme: bool,
+
     /// Support for rendering SVG.
     pub svg: bool,
 
+    /// Support the encoding of bitmap data to the PNG image format.
+    pub png_encode: bool,
+
+    /// Support the decoding of the PNG image format to bitmap data.
+    pub png_decode: bool,
+
+    /// Support the encoding of bitmap data to the JPEG image format.
+    pub jpeg_encode: bool,
+
+    /// Support the decoding of the JPEG image format to bitmap data.
+    pub jpeg_decode: bool,
+
+    /// Support the decoding of the GIF image format to bitmap data.
+    pub gif: bool,
+
+    /// Support the decoding of the BMP image format to bitmap data.
+    pub bmp: bool,
+
+    /// Support the decoding of the ICO image format to bitmap data.
+    pub ico: bool,
+
+    /// Support the decoding of the WBMP image format to bitmap data.
+    pub wbmp: bool,
+
     /// Support the encoding of bitmap data to the WEBP image format.
     pub webp_encode: bool,
 
@@ -49,6 +81,15 @@ pub struct Features {
 
     /// Build with FreeType WOFF2 support.
     pub freetype_woff2: bool,
+
+    /// Use fontconfig for the system font manager on Linux.
+    pub fontconfig: bool,
+
+    /// Build the font manager that loads fonts from a directory.
+    pub fontmgr_directory: bool,
+
+    /// Build the font manager that loads fonts from memory.
+    pub fontmgr_data: bool,
 }
 
 impl Default for Features {
@@ -63,7 +104,17 @@ impl Default for Features {
             metal: cfg!(feature = "metal"),
             d3d: cfg!(feature = "d3d"),
             text_layout: cfg!(feature = "textlayout"),
+            unicode_icu4x: cfg!(feature = "unicode-icu4x"),
+            unicode_libgrapheme: cfg!(feature = "unicode-libgrapheme"),
             svg: cfg!(feature = "svg"),
+            png_encode: cfg!(feature = "png-encode"),
+            png_decode: cfg!(feature = "png-decode"),
+            jpeg_encode: cfg!(feature = "jpeg-encode"),
+            jpeg_decode: cfg!(feature = "jpeg-decode"),
+            gif: cfg!(feature = "gif"),
+            bmp: cfg!(feature = "bmp"),
+            ico: cfg!(feature = "ico"),
+            wbmp: cfg!(feature = "wbmp"),
             webp_encode: cfg!(feature = "webp-encode"),
             webp_decode: cfg!(feature = "webp-decode"),
             embed_freetype: cfg!(feature = "embed-freetype"),
@@ -71,6 +122,9 @@ impl Default for Features {
             dng: false,
             particles: false,
             freetype_woff2: cfg!(feature = "freetype-woff2"),
+            fontconfig: cfg!(feature = "fontconfig"),
+            fontmgr_directory: cfg!(feature = "fontmgr-directory"),
+            fontmgr_data: cfg!(feature = "fontmgr-data"),
         }
     }
 }
@@ -80,8 +134,32 @@ impl Features {
         self.gl || self.vulkan || self.metal || self.d3d
     }
 
-    /// Feature Ids used to look up prebuilt binaries.
-    pub fn ids(&self) -> HashSet<&str> {
+    /// Is ICU used as the Unicode implementation? ICU is the default if text layout is enabled and
+    /// no other implementation is selected.
+    pub fn unicode_icu(&self) -> bool {
+        self.text_layout && !self.unicode_icu4x && !self.unicode_libgrapheme
+    }
+
+    /// Are any of the custom FreeType based font managers enabled?
+    pub fn custom_font_mgr(&self) -> bool {
+        self.fontmgr_directory || self.fontmgr_data
+    }
+
+    /// Are all the codecs enabled that were part of every build before they could be selected
+    /// individually?
+    pub fn standard_codecs(&self) -> bool {
+        self.png_encode
+            && self.png_decode
+            && self.jpeg_encode
+            && self.jpeg_decode
+            && self.gif
+            && self.bmp
+            && self.ico
+            && self.wbmp
+    }
+
+    /// Feature Ids used to look up prebuilt binaries for `target`.
+    pub fn ids(&self, target: &cargo::Target) -> HashSet<&str> {
         let mut feature_ids = Vec::new();
 
         if self.gl {
@@ -108,9 +186,49 @@ impl Features {
         if self.text_layout {
             feature_ids.push(feature_id::TEXTLAYOUT);
         }
+        if self.unicode_icu4x {
+            feature_ids.push(feature_id::UNICODE_ICU4X);
+        }
+        if self.unicode_libgrapheme {
+            feature_ids.push(feature_id::UNICODE_LIBGRAPHEME);
+        }
         if self.svg {
             feature_ids.push(feature_id::SVG);
         }
+        // The standard codecs are not part of the ids if they are all enabled, so that the keys of
+        // the default binaries stay the same.
+        if !self.standard_codecs() {
+            let codec_ids_start = feature_ids.len();
+            if self.png_encode {
+                feature_ids.push(feature_id::PNGE);
+            }
+            if self.png_decode {
+                feature_ids.push(feature_id::PNGD);
+            }
+            if self.jpeg_encode {
+                feature_ids.push(feature_id::JPEGE);
+            }
+            if self.jpeg_decode {
+                feature_ids.push(feature_id::JPEGD);
+            }
+            if self.gif {
+                feature_ids.push(feature_id::GIF);
+            }
+            if self.bmp {
+                feature_ids.push(feature_id::BMP);
+            }
+            if self.ico {
+                feature_ids.push(feature_id::ICO);
+            }
+            if self.wbmp {
+                feature_ids.push(feature_id::WBMP);
+            }
+            // Explicitly mark builds without any of the standard codecs, otherwise they would
+            // share the key with the default binaries.
+            if feature_ids.len() == codec_ids_start {
+                feature_ids.push(feature_id::NO_CODECS);
+            }
+        }
         if self.webp_encode {
             feature_ids.push(feature_id::WEBPE);
         }
@@ -120,6 +238,17 @@ impl Features {
         if self.embed_freetype {
             feature_ids.push(feature_id::EMBED_FREETYPE);
         }
+        // fontconfig is enabled by default, so only its absence is part of the ids. It is used
+        // on Linux only, on other targets its absence does not change the binaries.
+        if !self.fontconfig && target.is_linux() {
+            feature_ids.push(feature_id::NO_FONTCONFIG);
+        }
+        if self.fontmgr_directory {
+            feature_ids.push(feature_id::FONTMGR_DIRECTORY);
+        }
+        if self.fontmgr_data {
+            feature_ids.push(feature_id::FONTMGR_DATA);
+        }
 
         feature_ids.into_iter().collect()
     }
@@ -132,11 +261,25 @@ mod feature_id {
     pub const METAL: &str = "metal";
     pub const D3D: &str = "d3d";
     pub const TEXTLAYOUT: &str = "textlayout";
+    pub const UNICODE_ICU4X: &str = "icu4x";
+    pub const UNICODE_LIBGRAPHEME: &str = "libgrapheme";
     pub const SVG: &str = "svg";
+    pub const PNGE: &str = "pnge";
+    pub const PNGD: &str = "pngd";
+    pub const JPEGE: &str = "jpege";
+    pub const JPEGD: &str = "jpegd";
+    pub const GIF: &str = "gif";
+    pub const BMP: &str = "bmp";
+    pub const ICO: &str = "ico";
+    pub const WBMP: &str = "wbmp";
+    pub const NO_CODECS: &str = "nocodecs";
     pub const WEBPE: &str = "webpe";
     pub const WEBPD: &str = "webpd";
     pub const EGL: &str = "egl";
     pub const X11: &str = "x11";
     pub const WAYLAND: &str = "wayland";
     pub const EMBED_FREETYPE: &str = "freetype";
+    pub const NO_FONTCONFIG: &str = "nofontconfig";
+    pub const FONTMGR_DIRECTORY: &str = "fontmgrdir";
+    pub const FONTMGR_DATA: &str = "fontmgrdata";
 }
@@ -19,12 +19,11 @@ impl PlatformDetails for Emscripten {
             .arg("target_cpu", quote("wasm"));
 
         // The custom embedded font manager is enabled by default on WASM, but depends
-        // on the undefined symbol `SK_EMBEDDED_FONTS`. Enable the custom empty font
-        // manager instead so typeface creation still works.
+        // on the undefined symbol `SK_EMBEDDED_FONTS`. It's only enabled with the feature
+        // `fontmgr-data`, so enable the custom empty font manager to make sure typeface creation
+        // still works.
         // See https://github.com/rust-skia/rust-skia/issues/648
-        builder
-            .arg("skia_enable_fontmgr_custom_embedded", no())
-            .arg("skia_enable_fontmgr_custom_empty", yes());
+        builder.arg("skia_enable_fontmgr_custom_empty", yes());
     }
 
     fn bindgen_args(&self, _target: &cargo::Target, builder: &mut BindgenArgsBuilder) {
@@ -28,7 +28,11 @@ pub fn gn_args(config: &BuildConfiguration, builder: &mut GnArgsBuilder) {
 }
 
 pub fn link_libraries(features: &Features) -> Vec<String> {
-    let mut libs = vec!["stdc++", "fontconfig", "freetype"];
+    let mut libs = vec!["stdc++", "freetype"];
+
+    if features.fontconfig {
+        libs.push("fontconfig");
+    }
 
     if features.gl {
         if features.egl {
@@ -46,7 +50,9 @@ pub fn link_libraries(features: &Features) -> Vec<String> {
     }
 
     if skia::env::use_system_libraries() {
-        libs.push("png16");
+        if features.png_encode || features.png_decode {
+            libs.push("png16");
+        }
         libs.push("z");
         libs.push("icudata");
         libs.push("icui18n");
@@ -62,7 +68,9 @@ pub fn link_libraries(features: &Features) -> Vec<String> {
         }
     }
 
-    if skia::env::use_system_libraries() || cfg!(feature = "use-system-jpeg-turbo") {
+    if (features.jpeg_encode || features.jpeg_decode)
+        && (skia::env::use_system_libraries() || cfg!(feature = "use-system-jpeg-turbo"))
+    {
         libs.push("jpeg");
     }
 
@@ -130,6 +130,21 @@ impl FinalBuildConfiguration {
                 .arg("skia_use_egl", yes_if(features.egl))
                 .arg("skia_use_x11", yes_if(features.x11))
                 .arg("skia_use_system_libpng", yes_if(use_system_libraries))
+                .arg("skia_use_libpng_encode", yes_if(features.png_encode))
+                .arg("skia_use_libpng_decode", yes_if(features.png_decode))
+                .arg(
+                    "skia_use_libjpeg_turbo_encode",
+                    yes_if(features.jpeg_encode),
+                )
+                .arg(
+                    "skia_use_libjpeg_turbo_decode",
+                    yes_if(features.jpeg_decode),
+                )
+                // GIF decoding is implemented with wuffs.
+                .arg("skia_use_wuffs", yes_if(features.gif))
+                .arg("skia_enable_bmp_decode", yes_if(features.bmp))
+                .arg("skia_enable_ico_decode", yes_if(features.ico))
+                .arg("skia_enable_wbmp_decode", yes_if(features.wbmp))
                 .arg("skia_use_libwebp_encode", yes_if(features.webp_encode))
                 .arg("skia_use_libwebp_decode", yes_if(features.webp_decode))
                 .arg("skia_use_system_zlib", yes_if(use_system_libraries))
@@ -164,10 +179,20 @@ impl FinalBuildConfiguration {
             }
 
             if features.text_layout {
+                if features.unicode_icu4x && features.unicode_libgrapheme {
+                    panic!("The features `unicode-icu4x` and `unicode-libgrapheme` can not be combined, select one Unicode implementation only");
+                }
+                let use_icu = features.unicode_icu();
                 builder
                     .arg("skia_enable_skshaper", yes())
-                    .arg("skia_use_icu", yes())
-                    .arg("skia_use_system_icu", yes_if(use_system_libraries))
+                    .arg("skia_use_icu", yes_if(use_icu))
+                    .arg(
+                        "skia_use_system_icu",
+                        yes_if(use_icu && use_system_libraries),
+                    )
+                    .arg("skia_use_client_icu", yes_if(use_icu))
+                    .arg("skia_use_icu4x", yes_if(features.unicode_icu4x))
+                    .arg("skia_use_libgrapheme", yes_if(features.unicode_libgrapheme))
                     .arg("skia_use_harfbuzz", yes())
                     .arg("skia_pdf_subset_harfbuzz", yes())
                     .arg("skia_use_system_harfbuzz", yes_if(use_system_libraries))
@@ -185,10 +210,13 @@ impl FinalBuildConfiguration {
                 builder.arg("skia_use_system_libwebp", yes_if(use_system_libraries));
             }
 
-            let use_freetype = platform::uses_freetype(build);
+            // The custom font managers are based on FreeType, so it's embedded on platforms
+            // that do not use FreeType otherwise.
+            let platform_freetype = platform::uses_freetype(build);
+            let use_freetype = platform_freetype || features.custom_font_mgr();
             builder.arg("skia_use_freetype", yes_if(use_freetype));
             if use_freetype {
-                if features.embed_freetype {
+                if features.embed_freetype || !platform_freetype {
                     builder.arg("skia_use_system_freetype2", no());
                 } else {
                     // third_party/freetype2/BUILD.gn hard-codes /usr/include/freetype2
@@ -205,6 +233,22 @@ impl FinalBuildConfiguration {
                 }
             }
 
+            builder
+                .arg(
+                    "skia_enable_fontmgr_custom_directory",
+                    yes_if(features.fontmgr_directory),
+                )
+                .arg(
+                    "skia_enable_fontmgr_custom_embedded",
+                    yes_if(features.fontmgr_data),
+                );
+
+            if !features.fontconfig {
+                builder
+                    .arg("skia_use_fontconfig", no())
+                    .arg("skia_enable_fontmgr_fontconfig", no());
+            }
+
             // target specific gn args.
             let target = &build.target;
 
@@ -58,6 +58,12 @@ impl Configuration {
             if features.svg {
                 sources.push("src/svg.cpp".into());
             }
+            if features.png_encode {
+                sources.push("src/png-encode.cpp".into());
+            }
+            if features.jpeg_encode {
+                sources.push("src/jpeg-encode.cpp".into());
+            }
             if features.webp_encode {
                 sources.push("src/webp-encode.cpp".into());
             }
@@ -852,13 +858,24 @@ pub(crate) mod definitions {
                 "obj/modules/skshaper/skshaper.ninja".into(),
                 "obj/modules/skparagraph/skparagraph.ninja".into(),
                 "obj/modules/skunicode/skunicode_core.ninja".into(),
+            ]);
+        }
+        if features.unicode_icu() {
+            files.extend(vec![
                 "obj/modules/skunicode/skunicode_icu.ninja".into(),
+                "obj/modules/skunicode/skunicode_client_icu.ninja".into(),
             ]);
             // shaper.cpp includes SkLoadICU.h
             if !use_system_libraries {
                 files.push("obj/third_party/icu/icu.ninja".into())
             }
         }
+        if features.unicode_icu4x {
+            files.push("obj/modules/skunicode/skunicode_icu4x.ninja".into());
+        }
+        if features.unicode_libgrapheme {
+            files.push("obj/modules/skunicode/skunicode_libgrapheme.ninja".into());
+        }
         if features.svg {
             files.push("obj/modules/svg/svg.ninja".into());
         }
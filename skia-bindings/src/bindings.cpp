@@ -2,6 +2,7 @@
 #include <tuple>
 #include <vector>
 #include <memory>
+#include <atomic>
 
 #include "bindings.h"
 // codec/
@@ -11,13 +12,24 @@
 #include "include/codec/SkEncodedImageFormat.h"
 #include "include/codec/SkPixmapUtils.h"
 
+#if defined(SK_CODEC_DECODES_BMP)
 #include "include/codec/SkBmpDecoder.h"
+#endif
+#if defined(SK_CODEC_DECODES_GIF)
 #include "include/codec/SkGifDecoder.h"
+#endif
+#if defined(SK_CODEC_DECODES_ICO)
 #include "include/codec/SkIcoDecoder.h"
+#endif
+#if defined(SK_CODEC_DECODES_JPEG)
 #include "include/codec/SkJpegDecoder.h"
+#endif
+#if defined(SK_CODEC_DECODES_PNG)
 #include "include/codec/SkPngDecoder.h"
+#endif
+#if defined(SK_CODEC_DECODES_WBMP)
 #include "include/codec/SkWbmpDecoder.h"
-
+#endif
 #if defined(SK_CODEC_DECODES_WEBP)
 #include "include/codec/SkWebpDecoder.h"
 #endif
@@ -43,6 +55,7 @@
 #include "include/core/SkFontMetrics.h"
 #include "include/core/SkFontMgr.h"
 #include "include/core/SkGraphics.h"
+#include "include/core/SkOpenTypeSVGDecoder.h"
 #include "include/core/SkImage.h"
 #include "include/core/SkImageFilter.h"
 #include "include/core/SkImageGenerator.h"
@@ -93,16 +106,20 @@
 #include "include/effects/SkOverdrawColorFilter.h"
 
 #include "include/effects/SkRuntimeEffect.h"
+#include "include/sksl/SkSLDebugTrace.h"
+#include "include/sksl/SkSLVersion.h"
+// The private SkSL compiler is used by C_SkRuntimeEffect_Validate(), because SkRuntimeEffect does
+// not report the warnings of programs that compile.
+#include "src/sksl/SkSLCompiler.h"
+#include "src/sksl/SkSLProgramKind.h"
+#include "src/sksl/SkSLProgramSettings.h"
+#include "src/sksl/ir/SkSLProgram.h"
 
 #include "include/effects/SkPerlinNoiseShader.h"
 #include "include/effects/SkShaderMaskFilter.h"
 #include "include/effects/SkTableMaskFilter.h"
 #include "include/effects/SkTrimPathEffect.h"
 
-// encode/
-#include "include/encode/SkPngEncoder.h"
-#include "include/encode/SkJpegEncoder.h"
-
 // pathops/
 #include "include/pathops/SkPathOps.h"
 
@@ -231,30 +248,54 @@ extern "C" SkImage* C_SkCodecs_DeferredImage(SkCodec* codec, const SkAlphaType*
 // codec/*Decoder.h
 //
 
+#if defined(SK_CODEC_DECODES_BMP)
+
 extern "C" void C_SkBmpDecoder_Decoder(SkCodecs::Decoder* uninitialized) {
     new (uninitialized) SkCodecs::Decoder(SkBmpDecoder::Decoder());
 }
 
+#endif
+
+#if defined(SK_CODEC_DECODES_GIF)
+
 extern "C" void C_SkGifDecoder_Decoder(SkCodecs::Decoder* uninitialized) {
     new (uninitialized) SkCodecs::Decoder(SkGifDecoder::Decoder());
 }
 
+#endif
+
+#if defined(SK_CODEC_DECODES_ICO)
+
 extern "C" void C_SkIcoDecoder_Decoder(SkCodecs::Decoder* uninitialized) {
     new (uninitialized) SkCodecs::Decoder(SkIcoDecoder::Decoder());
 }
 
+#endif
+
+#if defined(SK_CODEC_DECODES_JPEG)
+
 extern "C" void C_SkJpegDecoder_Decoder(SkCodecs::Decoder* uninitialized) {
     new (uninitialized) SkCodecs::Decoder(SkJpegDecoder::Decoder());
 }
 
+#endif
+
+#if defined(SK_CODEC_DECODES_PNG)
+
 extern "C" void C_SkPngDecoder_Decoder(SkCodecs::Decoder* uninitialized) {
     new (uninitialized) SkCodecs::Decoder(SkPngDecoder::Decoder());
 }
 
+#endif
+
+#if defined(SK_CODEC_DECODES_WBMP)
+
 extern "C" void C_SkWbmpDecoder_Decoder(SkCodecs::Decoder* uninitialized) {
     new (uninitialized) SkCodecs::Decoder(SkWbmpDecoder::Decoder());
 }
 
+#endif
+
 #if defined(SK_CODEC_DECODES_WEBP)
 
 extern "C" void C_SkWebpDecoder_Decoder(SkCodecs::Decoder* uninitialized) {
@@ -1388,6 +1429,39 @@ extern "C" bool C_SkTypeface_LocalizedStrings_next(SkTypeface::LocalizedStrings*
     return false;
 }
 
+// The HarfBuzz based font subsetter of the PDF backend.
+#if defined(SK_PDF_USE_HARFBUZZ_SUBSET)
+#include "src/pdf/SkPDFGlyphUse.h"
+#include "src/pdf/SkPDFSubsetFont.h"
+
+// Checks the embedding permissions in `OS/2.fsType`. Restricted license embedding (0x0002), no
+// subsetting (0x0100) and bitmap embedding only (0x0200) forbid creating an outline subset.
+// Fonts without an OS/2 table have no restrictions.
+static bool SkTypeface_subsettingAllowed(const SkTypeface& typeface) {
+    uint8_t fsType[2];
+    if (typeface.getTableData(SkSetFourByteTag('O', 'S', '/', '2'), 8, sizeof(fsType), fsType) != sizeof(fsType)) {
+        return true;
+    }
+    return ((fsType[0] << 8 | fsType[1]) & 0x0302) == 0;
+}
+
+extern "C" SkData* C_SkTypeface_subset(const SkTypeface* self, const SkGlyphID* glyphs, size_t count) {
+    auto glyphCount = self->countGlyphs();
+    if (glyphCount <= 1 || !SkTypeface_subsettingAllowed(*self)) {
+        return nullptr;
+    }
+    // Glyph 0 (.notdef) is always included.
+    SkPDFGlyphUse glyphUse(1, SkToU16(glyphCount - 1));
+    for (size_t i = 0; i < count; ++i) {
+        if (glyphs[i] != 0 && glyphs[i] < glyphCount) {
+            glyphUse.set(glyphs[i]);
+        }
+    }
+    return SkPDFSubsetFont(*self, glyphUse).release();
+}
+
+#endif
+
 //
 // core/SkFlattenable.h
 //
@@ -1619,6 +1693,250 @@ extern "C" SkFontMgr* C_SkFontMgr_RefEmpty() {
     return SkFontMgr::RefEmpty().release();
 }
 
+#if defined(SK_FONTMGR_FREETYPE_DIRECTORY_AVAILABLE)
+
+extern "C" SkFontMgr* C_SkFontMgr_NewCustomDirectory(const char* dir) {
+    return SkFontMgr_New_Custom_Directory(dir).release();
+}
+
+#endif
+
+#if defined(SK_FONTMGR_FREETYPE_EMBEDDED_AVAILABLE)
+#include "include/ports/SkFontMgr_data.h"
+
+extern "C" SkFontMgr* C_SkFontMgr_NewCustomData(SkData** datas, size_t count) {
+    std::vector<sk_sp<SkData>> v;
+    for (size_t i = 0; i < count; ++i) {
+        v.push_back(sk_ref_sp(datas[i]));
+    }
+    return SkFontMgr_New_Custom_Data(SkSpan(v)).release();
+}
+
+#endif
+
+#if defined(SK_FONTMGR_FONTCONFIG_AVAILABLE)
+
+extern "C" SkFontMgr* C_SkFontMgr_NewFontConfig() {
+    return SkFontMgr_New_FontConfig(nullptr).release();
+}
+
+#endif
+
+//
+// SkFontMgr <-> RustFontMgr interop
+//
+
+namespace FontMgrImpl {
+    extern "C" typedef int (*CountFamilies)(TraitObject);
+    extern "C" typedef void (*GetFamilyName)(TraitObject, int index, SkString* familyName);
+    extern "C" typedef SkFontStyleSet* (*CreateStyleSet)(TraitObject, int index);
+    extern "C" typedef SkFontStyleSet* (*MatchFamily)(TraitObject, const char familyName[]);
+    extern "C" typedef SkTypeface* (*MatchFamilyStyle)(TraitObject, const char familyName[], const SkFontStyle*);
+    extern "C" typedef SkTypeface* (*MatchFamilyStyleCharacter)(
+        TraitObject, const char familyName[], const SkFontStyle*, const char* bcp47[], int bcp47Count, SkUnichar character);
+    extern "C" typedef SkTypeface* (*MakeFromData)(TraitObject, SkData* data, const SkFontArguments* args);
+    extern "C" typedef SkTypeface* (*LegacyMakeTypeface)(TraitObject, const char familyName[], const SkFontStyle*);
+    extern "C" typedef void (*Drop)(TraitObject);
+}
+
+class RustFontMgr: public SkFontMgr {
+
+public:
+    struct Param {
+        TraitObject trait;
+        ::FontMgrImpl::CountFamilies countFamilies;
+        ::FontMgrImpl::GetFamilyName getFamilyName;
+        ::FontMgrImpl::CreateStyleSet createStyleSet;
+        ::FontMgrImpl::MatchFamily matchFamily;
+        ::FontMgrImpl::MatchFamilyStyle matchFamilyStyle;
+        ::FontMgrImpl::MatchFamilyStyleCharacter matchFamilyStyleCharacter;
+        ::FontMgrImpl::MakeFromData makeFromData;
+        ::FontMgrImpl::LegacyMakeTypeface legacyMakeTypeface;
+        ::FontMgrImpl::Drop drop;
+    };
+
+    explicit RustFontMgr(const Param& param)
+    :_param(param){
+    }
+
+    ~RustFontMgr() override {
+        _param.drop(_param.trait);
+    }
+
+protected:
+    int onCountFamilies() const override {
+        return _param.countFamilies(_param.trait);
+    }
+
+    void onGetFamilyName(int index, SkString* familyName) const override {
+        _param.getFamilyName(_param.trait, index, familyName);
+    }
+
+    sk_sp<SkFontStyleSet> onCreateStyleSet(int index) const override {
+        auto styleSet = sp(_param.createStyleSet(_param.trait, index));
+        return styleSet ? styleSet : SkFontStyleSet::CreateEmpty();
+    }
+
+    sk_sp<SkFontStyleSet> onMatchFamily(const char familyName[]) const override {
+        auto styleSet = sp(_param.matchFamily(_param.trait, familyName));
+        return styleSet ? styleSet : SkFontStyleSet::CreateEmpty();
+    }
+
+    sk_sp<SkTypeface> onMatchFamilyStyle(const char familyName[], const SkFontStyle& style) const override {
+        return sp(_param.matchFamilyStyle(_param.trait, familyName, &style));
+    }
+
+    sk_sp<SkTypeface> onMatchFamilyStyleCharacter(
+        const char familyName[], const SkFontStyle& style,
+        const char* bcp47[], int bcp47Count,
+        SkUnichar character) const override {
+        return sp(_param.matchFamilyStyleCharacter(_param.trait, familyName, &style, bcp47, bcp47Count, character));
+    }
+
+    sk_sp<SkTypeface> onMakeFromData(sk_sp<SkData> data, int ttcIndex) const override {
+        return this->makeFromDataArgs(std::move(data), SkFontArguments().setCollectionIndex(ttcIndex));
+    }
+
+    sk_sp<SkTypeface> onMakeFromStreamIndex(std::unique_ptr<SkStreamAsset> stream, int ttcIndex) const override {
+        return this->onMakeFromStreamArgs(std::move(stream), SkFontArguments().setCollectionIndex(ttcIndex));
+    }
+
+    sk_sp<SkTypeface> onMakeFromStreamArgs(std::unique_ptr<SkStreamAsset> stream, const SkFontArguments& args) const override {
+        return this->makeFromDataArgs(SkData::MakeFromStream(stream.get(), stream->getLength()), args);
+    }
+
+    sk_sp<SkTypeface> onMakeFromFile(const char path[], int ttcIndex) const override {
+        auto data = SkData::MakeFromFileName(path);
+        return data ? this->onMakeFromData(std::move(data), ttcIndex) : nullptr;
+    }
+
+    sk_sp<SkTypeface> onLegacyMakeTypeface(const char familyName[], SkFontStyle style) const override {
+        return sp(_param.legacyMakeTypeface(_param.trait, familyName, &style));
+    }
+
+private:
+    sk_sp<SkTypeface> makeFromDataArgs(sk_sp<SkData> data, const SkFontArguments& args) const {
+        return sp(_param.makeFromData(_param.trait, data.release(), &args));
+    }
+
+    Param _param;
+};
+
+extern "C" SkFontMgr* C_RustFontMgr_new(const RustFontMgr::Param* param) {
+    return new RustFontMgr(*param);
+}
+
+//
+// SkOpenTypeSVGDecoder <-> RustOpenTypeSVGDecoder interop
+//
+
+namespace OpenTypeSVGDecoderImpl {
+    extern "C" typedef size_t (*ApproximateSize)(TraitObject);
+    extern "C" typedef bool (*Render)(
+        TraitObject, SkCanvas* canvas, int upem, SkGlyphID glyphId, SkColor foregroundColor,
+        const SkColor* palette, size_t paletteCount);
+    extern "C" typedef void (*Drop)(TraitObject);
+}
+
+class RustOpenTypeSVGDecoder: public SkOpenTypeSVGDecoder {
+
+public:
+    struct Param {
+        TraitObject trait;
+        ::OpenTypeSVGDecoderImpl::ApproximateSize approximateSize;
+        ::OpenTypeSVGDecoderImpl::Render render;
+        ::OpenTypeSVGDecoderImpl::Drop drop;
+    };
+
+    explicit RustOpenTypeSVGDecoder(const Param& param)
+    :_param(param){
+    }
+
+    ~RustOpenTypeSVGDecoder() override {
+        _param.drop(_param.trait);
+    }
+
+    size_t approximateSize() override {
+        return _param.approximateSize(_param.trait);
+    }
+
+    bool render(SkCanvas& canvas, int upem, SkGlyphID glyphId,
+                SkColor foregroundColor, SkSpan<SkColor> palette) override {
+        return _param.render(_param.trait, &canvas, upem, glyphId, foregroundColor, palette.data(), palette.size());
+    }
+
+private:
+    Param _param;
+};
+
+namespace OpenTypeSVGDecoderImpl {
+    /// Creates the decoder of a SVG document, returns false if the document is not supported.
+    extern "C" typedef bool (*Make)(const uint8_t* svg, size_t length, RustOpenTypeSVGDecoder::Param* param);
+
+    static std::atomic<Make> make;
+
+    static std::unique_ptr<SkOpenTypeSVGDecoder> makeDecoder(const uint8_t* svg, size_t length) {
+        RustOpenTypeSVGDecoder::Param param;
+        auto makeFn = make.load();
+        if (!makeFn || !makeFn(svg, length, &param)) {
+            return nullptr;
+        }
+        return std::make_unique<RustOpenTypeSVGDecoder>(param);
+    }
+}
+
+extern "C" void C_SkGraphics_SetOpenTypeSVGDecoderFactory_Rust(OpenTypeSVGDecoderImpl::Make make) {
+    OpenTypeSVGDecoderImpl::make.store(make);
+    SkGraphics::SetOpenTypeSVGDecoderFactory(make ? OpenTypeSVGDecoderImpl::makeDecoder : nullptr);
+}
+
+extern "C" bool C_SkGraphics_OpenTypeSVGDecoderFactory_make(const uint8_t* svg, size_t length) {
+    auto factory = SkGraphics::GetOpenTypeSVGDecoderFactory();
+    return factory && factory(svg, length) != nullptr;
+}
+
+/// A style set that is built from a list of typefaces.
+class TypefacesFontStyleSet: public SkFontStyleSet {
+
+public:
+    explicit TypefacesFontStyleSet(std::vector<sk_sp<SkTypeface>> typefaces)
+    :_typefaces(std::move(typefaces)){
+    }
+
+    int count() override {
+        return static_cast<int>(_typefaces.size());
+    }
+
+    void getStyle(int index, SkFontStyle* style, SkString* name) override {
+        auto& typeface = _typefaces[index];
+        if (style) {
+            *style = typeface->fontStyle();
+        }
+        if (name) {
+            name->reset();
+        }
+    }
+
+    sk_sp<SkTypeface> createTypeface(int index) override {
+        return _typefaces[index];
+    }
+
+    sk_sp<SkTypeface> matchStyle(const SkFontStyle& pattern) override {
+        return this->matchStyleCSS3(pattern);
+    }
+
+private:
+    std::vector<sk_sp<SkTypeface>> _typefaces;
+};
+
+extern "C" SkFontStyleSet* C_SkFontStyleSet_MakeFromTypefaces(SkTypeface** typefaces, size_t count) {
+    std::vector<sk_sp<SkTypeface>> v;
+    for (size_t i = 0; i < count; ++i) {
+        v.push_back(sk_ref_sp(typefaces[i]));
+    }
+    return new TypefacesFontStyleSet(std::move(v));
+}
+
 //
 // core/SkFontParameters.h
 //
@@ -2639,6 +2957,57 @@ bool C_SkRuntimeEffect_allowBlender(const SkRuntimeEffect* self) {
     return self->allowBlender();
 }
 
+SkShader* C_SkRuntimeEffect_MakeTraced(SkShader* shader, const SkIPoint* traceCoord, SkSL::DebugTrace** debugTrace) {
+    auto traced = SkRuntimeEffect::MakeTraced(sp(shader), *traceCoord);
+    *debugTrace = traced.debugTrace.release();
+    return traced.shader.release();
+}
+
+void C_SkSL_DebugTrace_writeTrace(const SkSL::DebugTrace* self, SkWStream* w) {
+    self->writeTrace(w);
+}
+
+void C_SkSL_DebugTrace_dump(const SkSL::DebugTrace* self, SkWStream* o) {
+    self->dump(o);
+}
+
+}
+
+// Compiles `sksl` to a runtime effect of `kind` (0: shader, 1: color filter, 2: blender) and
+// returns the errors and warnings of the compiler in `diagnostics`.
+extern "C" bool C_SkRuntimeEffect_Validate(int kind, const SkString* sksl, SkString* diagnostics) {
+    SkSL::ProgramKind programKind;
+    switch (kind) {
+        case 0: programKind = SkSL::ProgramKind::kRuntimeShader; break;
+        case 1: programKind = SkSL::ProgramKind::kRuntimeColorFilter; break;
+        default: programKind = SkSL::ProgramKind::kRuntimeBlender; break;
+    }
+    // The settings SkRuntimeEffect::MakeFromSource() uses for the default options.
+    SkSL::ProgramSettings settings;
+    settings.fInlineThreshold = 0;
+    settings.fForceNoInline = false;
+    settings.fOptimize = true;
+    settings.fMaxVersionAllowed = SkSL::Version::k100;
+    settings.fUseMemoryPool = false;
+    SkSL::Compiler compiler;
+    auto program = compiler.convertProgram(programKind, std::string(sksl->c_str(), sksl->size()), settings);
+    *diagnostics = SkString(compiler.errorText());
+    if (!program) {
+        return false;
+    }
+
+    // SkRuntimeEffect applies additional checks to the compiled program.
+    SkRuntimeEffect::Result result;
+    switch (kind) {
+        case 0: result = SkRuntimeEffect::MakeForShader(*sksl); break;
+        case 1: result = SkRuntimeEffect::MakeForColorFilter(*sksl); break;
+        default: result = SkRuntimeEffect::MakeForBlender(*sksl); break;
+    }
+    if (!result.effect) {
+        diagnostics->append(result.errorText);
+        return false;
+    }
+    return true;
 }
 
 //
@@ -2781,6 +3150,41 @@ SkImageFilter *C_SkImageFilters_Picture(SkPicture *pic, const SkRect *targetRect
     return SkImageFilters::Picture(sp(pic), *targetRect).release();
 }
 
+SkImageFilter *C_SkImageFilters_RuntimeShader(
+    const SkRuntimeEffect *effect, const SkData *uniforms,
+    const SkRuntimeEffect::ChildPtr *children, size_t childCount,
+    SkScalar maxSampleRadius,
+    const size_t *childIndices, SkImageFilter *const inputs[], size_t inputCount) {
+    std::vector<sk_sp<SkImageFilter>> filters;
+    for (size_t i = 0; i < inputCount; ++i) {
+        filters.push_back(sp(inputs[i]));
+    }
+    if (uniforms->size() < effect->uniformSize()) {
+        return nullptr;
+    }
+    SkRuntimeShaderBuilder builder(sk_ref_sp(const_cast<SkRuntimeEffect*>(effect)));
+    auto uniformData = static_cast<const uint8_t*>(uniforms->data());
+    for (const auto& uniform : effect->uniforms()) {
+        builder.uniform(uniform.name).set(uniformData + uniform.offset, static_cast<int>(uniform.sizeInBytes()));
+    }
+    auto declarations = effect->children();
+    for (size_t i = 0; i < childCount; ++i) {
+        if (auto shader = children[i].shader()) {
+            builder.child(declarations[i].name) = sk_ref_sp(shader);
+        } else if (auto colorFilter = children[i].colorFilter()) {
+            builder.child(declarations[i].name) = sk_ref_sp(colorFilter);
+        } else if (auto blender = children[i].blender()) {
+            builder.child(declarations[i].name) = sk_ref_sp(blender);
+        }
+    }
+    std::vector<std::string_view> names;
+    for (size_t i = 0; i < inputCount; ++i) {
+        names.push_back(declarations[childIndices[i]].name);
+    }
+    return SkImageFilters::RuntimeShader(
+        builder, maxSampleRadius, names.data(), filters.data(), static_cast<int>(inputCount)).release();
+}
+
 SkImageFilter *C_SkImageFilters_Shader(SkShader *shader,
                                        SkImageFilters::Dither dither,
                                        const SkRect *cropRect)
@@ -2858,62 +3262,6 @@ C_SkImageFilters_SpotLitSpecular(const SkPoint3 &location,
 
 }
 
-//
-// encode/
-//
-
-extern "C" {
-
-bool C_SkPngEncoder_Encode(SkWStream* stream, const SkPixmap* pixmap,
-    SkDataTable* comments, SkPngEncoder::FilterFlag filterFlags, int zLibLevel) {
-
-    auto options = SkPngEncoder::Options();
-    options.fComments = sp(comments);
-    options.fFilterFlags = filterFlags;
-    options.fZLibLevel = zLibLevel;
-
-    return SkPngEncoder::Encode(stream, *pixmap, options);
-}
-
-SkData* C_SkPngEncoder_EncodeImage(GrDirectContext* ctx, const SkImage* img,
-    SkDataTable* comments, SkPngEncoder::FilterFlag filterFlags, int zLibLevel) {
-
-    auto options = SkPngEncoder::Options();
-    options.fComments = sp(comments);
-    options.fFilterFlags = filterFlags;
-    options.fZLibLevel = zLibLevel;
-
-    return SkPngEncoder::Encode(ctx, img, options).release();
-}
-
-bool C_SkJpegEncoder_Encode(SkWStream* stream, const SkPixmap* pixmap, 
-    int quality,
-    SkJpegEncoder::Downsample downsample, 
-    SkJpegEncoder::AlphaOption alphaOption, 
-    const SkData* xmpMetadata) {
-    auto options = SkJpegEncoder::Options();
-    options.fQuality = quality;
-    options.fDownsample = downsample;
-    options.fAlphaOption = alphaOption;
-    options.xmpMetadata = xmpMetadata;
-    return SkJpegEncoder::Encode(stream, *pixmap, options);
-}
-
-SkData* C_SkJpegEncoder_EncodeImage(GrDirectContext* ctx, const SkImage* img, 
-    int quality,
-    SkJpegEncoder::Downsample downsample, 
-    SkJpegEncoder::AlphaOption alphaOption, 
-    const SkData* xmpMetadata) {
-    auto options = SkJpegEncoder::Options();
-    options.fQuality = quality;
-    options.fDownsample = downsample;
-    options.fAlphaOption = alphaOption;
-    options.xmpMetadata = xmpMetadata;
-    return SkJpegEncoder::Encode(ctx, img, options).release();
-}
-
-}
-
 //
 // docs/SkPDFDocument.h
 //
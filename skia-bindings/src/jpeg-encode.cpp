@@ -0,0 +1,34 @@
+#include "bindings.h"
+
+#include "include/core/SkData.h"
+#include "include/encode/SkJpegEncoder.h"
+
+extern "C" {
+
+bool C_SkJpegEncoder_Encode(SkWStream* stream, const SkPixmap* pixmap, 
+    int quality,
+    SkJpegEncoder::Downsample downsample, 
+    SkJpegEncoder::AlphaOption alphaOption, 
+    const SkData* xmpMetadata) {
+    auto options = SkJpegEncoder::Options();
+    options.fQuality = quality;
+    options.fDownsample = downsample;
+    options.fAlphaOption = alphaOption;
+    options.xmpMetadata = xmpMetadata;
+    return SkJpegEncoder::Encode(stream, *pixmap, options);
+}
+
+SkData* C_SkJpegEncoder_EncodeImage(GrDirectContext* ctx, const SkImage* img, 
+    int quality,
+    SkJpegEncoder::Downsample downsample, 
+    SkJpegEncoder::AlphaOption alphaOption, 
+    const SkData* xmpMetadata) {
+    auto options = SkJpegEncoder::Options();
+    options.fQuality = quality;
+    options.fDownsample = downsample;
+    options.fAlphaOption = alphaOption;
+    options.xmpMetadata = xmpMetadata;
+    return SkJpegEncoder::Encode(ctx, img, options).release();
+}
+
+}
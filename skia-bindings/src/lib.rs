@@ -23,7 +23,10 @@ pub use defaults::*;
 
 mod impls;
 
-#[cfg(feature = "textlayout")]
+#[cfg(all(
+    feature = "textlayout",
+    not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme"))
+))]
 pub mod icu;
 
 #[allow(unused_imports)]
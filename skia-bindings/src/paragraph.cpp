@@ -12,9 +12,12 @@
 #include "modules/skparagraph/include/TextShadow.h"
 #include "modules/skparagraph/include/TextStyle.h"
 #include "modules/skparagraph/include/TypefaceFontProvider.h"
+#include "unicode.h"
 
 // m84: needs definition of SkFontData
 #include "src/core/SkFontDescriptor.h"
+// Needed to find out if a builder has a SkUnicode before it is built.
+#include "modules/skparagraph/src/ParagraphBuilderImpl.h"
 
 #include <optional>
 
@@ -436,11 +439,109 @@ extern "C" {
         return self->Reset();
     }
 
-    ParagraphBuilder* C_ParagraphBuilder_make(const ParagraphStyle* style, const FontCollection* fontCollection) {
-        return ParagraphBuilder::make(*style, spFromConst(fontCollection)).release();
+    ParagraphBuilder* C_ParagraphBuilder_make(const ParagraphStyle* style, const FontCollection* fontCollection, SkUnicode* unicode) {
+        auto u = unicode ? sp(unicode) : makeUnicode();
+        if (!u) {
+            return nullptr;
+        }
+        return ParagraphBuilder::make(*style, spFromConst(fontCollection), std::move(u)).release();
+    }
+
+    void C_ParagraphBuilder_setUnicode(ParagraphBuilder* self, SkUnicode* unicode) {
+        self->setUnicode(sp(unicode));
     }
 }
 
+// Client supplied Unicode data.
+#if defined(SK_UNICODE_CLIENT_IMPLEMENTATION)
+
+namespace {
+    std::vector<SkUnicode::Position> positions(const size_t* positions, size_t count) {
+        return std::vector<SkUnicode::Position>(positions, positions + count);
+    }
+
+    std::vector<SkUnicode::LineBreakBefore> lineBreaks(const size_t* positions, const bool* hard, size_t count) {
+        std::vector<SkUnicode::LineBreakBefore> breaks;
+        breaks.reserve(count);
+        for (size_t i = 0; i < count; ++i) {
+            breaks.emplace_back(
+                positions[i],
+                hard[i] ? SkUnicode::LineBreakType::kHardLineBreak : SkUnicode::LineBreakType::kSoftLineBreak);
+        }
+        return breaks;
+    }
+}
+
+extern "C" {
+    // Without a SkUnicode, the builder creates a client SkUnicode from the data set with
+    // `SetWords*`, `SetGraphemeBreaks*` and `SetLineBreaks*` when the paragraph is built.
+    ParagraphBuilder* C_ParagraphBuilder_makeWithoutUnicode(const ParagraphStyle* style, const FontCollection* fontCollection) {
+        return ParagraphBuilder::make(*style, spFromConst(fontCollection), nullptr).release();
+    }
+
+    void C_ParagraphBuilder_SetWordsUtf8(ParagraphBuilder* self, const size_t* words, size_t count) {
+        self->SetWordsUtf8(positions(words, count));
+    }
+
+    void C_ParagraphBuilder_SetWordsUtf16(ParagraphBuilder* self, const size_t* words, size_t count) {
+        self->SetWordsUtf16(positions(words, count));
+    }
+
+    void C_ParagraphBuilder_SetGraphemeBreaksUtf8(ParagraphBuilder* self, const size_t* graphemes, size_t count) {
+        self->SetGraphemeBreaksUtf8(positions(graphemes, count));
+    }
+
+    void C_ParagraphBuilder_SetGraphemeBreaksUtf16(ParagraphBuilder* self, const size_t* graphemes, size_t count) {
+        self->SetGraphemeBreaksUtf16(positions(graphemes, count));
+    }
+
+    void C_ParagraphBuilder_SetLineBreaksUtf8(ParagraphBuilder* self, const size_t* breaks, const bool* hard, size_t count) {
+        self->SetLineBreaksUtf8(lineBreaks(breaks, hard, count));
+    }
+
+    void C_ParagraphBuilder_SetLineBreaksUtf16(ParagraphBuilder* self, const size_t* breaks, const bool* hard, size_t count) {
+        self->SetLineBreaksUtf16(lineBreaks(breaks, hard, count));
+    }
+
+    // Building a paragraph without a SkUnicode and without the client data aborts.
+    bool C_ParagraphBuilder_canBuild(const ParagraphBuilder* self) {
+        struct UnicodeAccess : ParagraphBuilderImpl {
+            static const sk_sp<SkUnicode>& unicode(const ParagraphBuilderImpl* builder) {
+                return builder->*(&UnicodeAccess::fUnicode);
+            }
+        };
+        auto builder = static_cast<const ParagraphBuilderImpl*>(self);
+        if (UnicodeAccess::unicode(builder)) {
+            return true;
+        }
+        auto [words, graphemeBreaks, lineBreaks] = self->getClientICUData();
+        return const_cast<ParagraphBuilder*>(self)->getText().empty()
+            || (!graphemeBreaks.empty() && !lineBreaks.empty());
+    }
+
+    void C_ParagraphBuilder_getClientICUData(
+        const ParagraphBuilder* self,
+        VecSink<size_t>* words,
+        VecSink<size_t>* graphemeBreaks,
+        VecSink<size_t>* lineBreaks,
+        VecSink<bool>* hardLineBreaks) {
+        auto [w, g, l] = self->getClientICUData();
+        std::vector<size_t> breaksVec;
+        // `std::vector<bool>` does not provide contiguous storage.
+        auto hardVec = std::make_unique<bool[]>(l.size());
+        for (size_t i = 0; i < l.size(); ++i) {
+            breaksVec.push_back(l[i].pos);
+            hardVec[i] = l[i].breakType == SkUnicode::LineBreakType::kHardLineBreak;
+        }
+        words->set(w);
+        graphemeBreaks->set(g);
+        lineBreaks->set(breaksVec);
+        hardLineBreaks->set(hardVec.get(), l.size());
+    }
+}
+
+#endif
+
 //
 // TextStyle.h
 //
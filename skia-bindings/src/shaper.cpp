@@ -4,12 +4,15 @@
 #include "modules/skshaper/include/SkShaper.h"
 #include "modules/skshaper/include/SkShaper_harfbuzz.h"
 #include "modules/skshaper/include/SkShaper_skunicode.h"
-#include "modules/skunicode/include/SkUnicode_icu.h"
+#include "unicode.h"
+
+#include <vector>
+
 #ifdef SK_SHAPER_CORETEXT_AVAILABLE
 #include "modules/skshaper/include/SkShaper_coretext.h"
 #endif
 
-#if defined(_WIN32)
+#if defined(_WIN32) && defined(SK_UNICODE_ICU_IMPLEMENTATION)
 #include "third_party/icu/SkLoadICU.h"
 #endif
 
@@ -53,10 +56,69 @@ extern "C" SkShaper::FontRunIterator* C_SkShaper_MakeFontMgrRunIterator(const ch
     return SkShaper::MakeFontMgrRunIterator(utf8, utf8Bytes, *font, sk_sp<SkFontMgr>(fallback)).release();
 }
 
+// A FontRunIterator created by SkShaper::MakeFontMgrRunIterator() that owns the requested
+// family name the wrapped iterator refers to.
+class RequestFontMgrRunIterator final : public SkShaper::FontRunIterator {
+public:
+    RequestFontMgrRunIterator(const char* utf8, size_t utf8Bytes, const SkFont& font, sk_sp<SkFontMgr> fallback,
+                              const char* requestName, size_t requestNameLen, SkFontStyle requestStyle)
+        : fRequestName(requestName ? SkString(requestName, requestNameLen) : SkString())
+        , fIterator(SkShaper::MakeFontMgrRunIterator(
+              utf8, utf8Bytes, font, std::move(fallback),
+              requestName ? fRequestName.c_str() : nullptr, requestStyle, nullptr)) {}
+
+    void consume() override { fIterator->consume(); }
+    size_t endOfCurrentRun() const override { return fIterator->endOfCurrentRun(); }
+    bool atEnd() const override { return fIterator->atEnd(); }
+    const SkFont& currentFont() const override { return fIterator->currentFont(); }
+
+private:
+    // Declared before `fIterator`, so that it is initialized first.
+    SkString fRequestName;
+    std::unique_ptr<SkShaper::FontRunIterator> fIterator;
+};
+
+extern "C" SkShaper::FontRunIterator* C_SkShaper_MakeFontMgrRunIterator2(
+    const char* utf8, size_t utf8Bytes, const SkFont* font, SkFontMgr* fallback,
+    const char* requestName, size_t requestNameLen, const SkFontStyle* requestStyle) {
+    return new RequestFontMgrRunIterator(
+        utf8, utf8Bytes, *font, sk_sp<SkFontMgr>(fallback), requestName, requestNameLen, *requestStyle);
+}
+
 extern "C" SkShaper::FontRunIterator* C_SkShaper_TrivialFontRunIterator_new(const SkFont& font, size_t utf8Bytes) {
     return new SkShaper::TrivialFontRunIterator(font, utf8Bytes);
 }
 
+// A FontRunIterator over runs that were resolved in advance.
+class FontRunsIterator final : public SkShaper::FontRunIterator {
+public:
+    FontRunsIterator(const size_t* ends, const SkFont* fonts, size_t count)
+        : fEnds(ends, ends + count), fFonts(fonts, fonts + count), fCurrent(0) {}
+
+    void consume() override {
+        SkASSERT(fCurrent < fEnds.size());
+        ++fCurrent;
+    }
+    size_t endOfCurrentRun() const override {
+        return fCurrent ? fEnds[fCurrent - 1] : 0;
+    }
+    bool atEnd() const override {
+        return fCurrent == fEnds.size();
+    }
+    const SkFont& currentFont() const override {
+        return fFonts[fCurrent ? fCurrent - 1 : 0];
+    }
+
+private:
+    std::vector<size_t> fEnds;
+    std::vector<SkFont> fFonts;
+    size_t fCurrent;
+};
+
+extern "C" SkShaper::FontRunIterator* C_SkShaper_FontRunsIterator_new(const size_t* ends, const SkFont* fonts, size_t count) {
+    return new FontRunsIterator(ends, fonts, count);
+}
+
 extern "C" uint8_t C_SkShaper_BiDiRunIterator_currentLevel(const SkShaper::BiDiRunIterator* self) {
     return self->currentLevel();
 }
@@ -73,10 +135,53 @@ extern "C" SkFourByteTag C_SkShaper_ScriptRunIterator_currentScript(const SkShap
     return self->currentScript();
 }
 
+// A ScriptRunIterator that forwards to another one and records the end and the script of every
+// run the shaper consumes.
+class RecordingScriptRunIterator final : public SkShaper::ScriptRunIterator {
+public:
+    explicit RecordingScriptRunIterator(SkShaper::ScriptRunIterator* iterator)
+        : fIterator(iterator) {}
+
+    void consume() override {
+        fIterator->consume();
+        fEnds.push_back(fIterator->endOfCurrentRun());
+        fScripts.push_back(fIterator->currentScript());
+    }
+    size_t endOfCurrentRun() const override {
+        return fIterator->endOfCurrentRun();
+    }
+    bool atEnd() const override {
+        return fIterator->atEnd();
+    }
+    SkFourByteTag currentScript() const override {
+        return fIterator->currentScript();
+    }
+
+    std::vector<size_t> fEnds;
+    std::vector<SkFourByteTag> fScripts;
+
+private:
+    SkShaper::ScriptRunIterator* fIterator;
+};
+
+extern "C" SkShaper::ScriptRunIterator* C_SkShaper_RecordingScriptRunIterator_new(SkShaper::ScriptRunIterator* iterator) {
+    return new RecordingScriptRunIterator(iterator);
+}
+
+extern "C" void C_SkShaper_RecordingScriptRunIterator_getRuns(SkShaper::ScriptRunIterator* self, VecSink<size_t>* ends, VecSink<SkFourByteTag>* scripts) {
+    auto recording = static_cast<RecordingScriptRunIterator*>(self);
+    ends->set(recording->fEnds);
+    scripts->set(recording->fScripts);
+}
+
 extern "C" SkShaper::ScriptRunIterator* C_SkShaper_MakeScriptRunIterator(const char* utf8, size_t utf8Bytes, SkFourByteTag script) {
     return SkShaper::MakeScriptRunIterator(utf8, utf8Bytes, script).release();
 }
 
+extern "C" SkShaper::ScriptRunIterator* C_SkShaper_MakeSkUnicodeHbScriptRunIterator(const char* utf8, size_t utf8Bytes, SkFourByteTag script) {
+    return SkShaper::MakeSkUnicodeHbScriptRunIterator(utf8, utf8Bytes, script).release();
+}
+
 extern "C" SkShaper::ScriptRunIterator* C_SkShaper_MakeHbIcuScriptRunIterator(const char* utf8, size_t utf8Bytes) {
     return SkShaper::MakeHbIcuScriptRunIterator(utf8, utf8Bytes).release();
 }
@@ -219,7 +324,7 @@ extern "C" SkShaper::ScriptRunIterator* C_SkShapers_Primitive_TrivialScriptRunIt
 // SkShapers::HB
 
 extern "C" SkShaper* C_SkShapers_HB_ShaperDrivenWrapper(SkFontMgr* fontMgr) {
-    auto unicode = SkUnicodes::ICU::Make();
+    auto unicode = makeUnicode();
     if (!unicode) {
         return nullptr;
     }
@@ -227,7 +332,7 @@ extern "C" SkShaper* C_SkShapers_HB_ShaperDrivenWrapper(SkFontMgr* fontMgr) {
 }
 
 extern "C" SkShaper* C_SkShapers_HB_ShapeThenWrap(SkFontMgr* fontMgr) {
-    auto unicode = SkUnicodes::ICU::Make();
+    auto unicode = makeUnicode();
     if (!unicode) {
         return nullptr;
     }
@@ -235,7 +340,7 @@ extern "C" SkShaper* C_SkShapers_HB_ShapeThenWrap(SkFontMgr* fontMgr) {
 }
 
 extern "C" SkShaper* C_SkShapers_HB_ShapeDontWrapOrReorder(SkFontMgr* fontMgr) {
-    auto unicode = SkUnicodes::ICU::Make();
+    auto unicode = makeUnicode();
     if (!unicode) {
         return nullptr;
     }
@@ -245,9 +350,49 @@ extern "C" SkShaper* C_SkShapers_HB_ShapeDontWrapOrReorder(SkFontMgr* fontMgr) {
 // SkShapers::unicode
 
 extern "C" SkShaper::BiDiRunIterator* C_SkShapers_unicode_BidiRunIterator(const char* utf8, size_t utf8Bytes, uint8_t bidiLevel) {
-    auto unicode = SkUnicodes::ICU::Make();
+    auto unicode = makeUnicode();
     if (!unicode) {
         return nullptr;
     }
     return SkShapers::unicode::BidiRunIterator(std::move(unicode), utf8, utf8Bytes, bidiLevel).release();
 }
+
+// SkUnicode
+
+extern "C" SkUnicode* C_SkUnicode_Make() {
+    return makeUnicode().release();
+}
+
+extern "C" bool C_SkUnicode_getGraphemeBreaks(SkUnicode* self, const char* utf8, int utf8Units, VecSink<size_t>* breaks) {
+    skia_private::TArray<SkUnicode::CodeUnitFlags, true> flags;
+    // `utf8` is only modified if tabs are replaced.
+    if (!self->computeCodeUnitFlags(const_cast<char*>(utf8), utf8Units, false, &flags)) {
+        return false;
+    }
+    std::vector<size_t> result;
+    for (int i = 0; i < flags.size(); ++i) {
+        if (SkUnicode::hasGraphemeStartFlag(flags[i])) {
+            result.push_back(i);
+        }
+    }
+    breaks->set(result);
+    return true;
+}
+
+#if defined(SK_UNICODE_ICU_IMPLEMENTATION)
+extern "C" SkUnicode* C_SkUnicodes_ICU_Make() {
+    return SkUnicodes::ICU::Make().release();
+}
+#endif
+
+#if defined(SK_UNICODE_ICU4X_IMPLEMENTATION)
+extern "C" SkUnicode* C_SkUnicodes_ICU4X_Make() {
+    return SkUnicodes::ICU4X::Make().release();
+}
+#endif
+
+#if defined(SK_UNICODE_LIBGRAPHEME_IMPLEMENTATION)
+extern "C" SkUnicode* C_SkUnicodes_Libgrapheme_Make() {
+    return SkUnicodes::Libgrapheme::Make().release();
+}
+#endif
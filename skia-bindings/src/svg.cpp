@@ -1,13 +1,21 @@
 #include "bindings.h"
 
 #include "include/core/SkCanvas.h"
+#include "include/core/SkGraphics.h"
 #include "include/svg/SkSVGCanvas.h"
 
 #include "modules/svg/include/SkSVGDOM.h"
 #include "modules/svg/include/SkSVGNode.h"
+#include "modules/svg/include/SkSVGOpenTypeSVGDecoder.h"
+#include "modules/svg/include/SkSVGRenderContext.h"
+#include "modules/svg/include/SkSVGSVG.h"
 #include "modules/skresources/include/SkResources.h"
 
+#include "include/core/SkPathUtils.h"
+#include "include/core/SkRRect.h"
 #include "include/core/SkStream.h"
+#include "include/pathops/SkPathOps.h"
+#include "include/utils/SkNoDrawCanvas.h"
 
 
 typedef SkData* (*loadSkData)(const char resource_path[], const char resource_name[], void* context);
@@ -75,3 +83,117 @@ extern "C" bool C_SkSVGDOM_unique(const SkSVGDOM* self) {
 extern "C" void C_SkSVGDOM_setContainerSize(SkSVGDOM* self, const SkSize& size){
     self->setContainerSize(size);
 }
+
+extern "C" void C_SkSVGDOM_setRootSize(SkSVGDOM* self, const SkSize* size) {
+    if (auto* root = self->getRoot()) {
+        root->setWidth(SkSVGLength(size->width()));
+        root->setHeight(SkSVGLength(size->height()));
+    }
+}
+
+extern "C" bool C_SkSVGDOM_getViewBox(const SkSVGDOM* self, SkRect* viewBox) {
+    const auto* root = self->getRoot();
+    if (!root || !root->getViewBox().has_value()) {
+        return false;
+    }
+    *viewBox = *root->getViewBox();
+    return true;
+}
+
+extern "C" void C_SkSVGDOM_intrinsicSize(const SkSVGDOM* self, SkSize* size) {
+    const auto* root = self->getRoot();
+    *size = root ? root->intrinsicSize(SkSVGLengthContext(self->containerSize())) : SkSize::MakeEmpty();
+}
+
+// A canvas that collects the area covered by the drawn shapes: the fill area of filled shapes and
+// the outline of stroked shapes, in device space. Clips, text, images and colors are ignored.
+class CoverageCanvas final : public SkNoDrawCanvas {
+public:
+    CoverageCanvas(int width, int height) : SkNoDrawCanvas(width, height) {}
+
+    SkPath coverage() {
+        SkPath result;
+        if (fBuilder.resolve(&result)) {
+            return result;
+        }
+        // Path ops can fail on degenerate geometry. The combined paths then differ from the union
+        // only where shapes of opposite directions overlap.
+        return fPaths;
+    }
+
+protected:
+    void onDrawPath(const SkPath& path, const SkPaint& paint) override {
+        this->add(path, paint);
+    }
+
+    void onDrawRect(const SkRect& rect, const SkPaint& paint) override {
+        this->add(SkPath::Rect(rect), paint);
+    }
+
+    void onDrawRRect(const SkRRect& rrect, const SkPaint& paint) override {
+        this->add(SkPath::RRect(rrect), paint);
+    }
+
+    void onDrawOval(const SkRect& oval, const SkPaint& paint) override {
+        this->add(SkPath::Oval(oval), paint);
+    }
+
+    void onDrawPoints(PointMode mode, size_t count, const SkPoint pts[], const SkPaint& paint) override {
+        SkPath path;
+        switch (mode) {
+            case kPoints_PointMode:
+                return;
+            case kLines_PointMode:
+                for (size_t i = 0; i + 1 < count; i += 2) {
+                    path.moveTo(pts[i]);
+                    path.lineTo(pts[i + 1]);
+                }
+                break;
+            case kPolygon_PointMode:
+                path.addPoly(pts, SkToInt(count), false);
+                break;
+        }
+        SkPaint stroke(paint);
+        stroke.setStyle(SkPaint::kStroke_Style);
+        this->add(path, stroke);
+    }
+
+private:
+    void add(const SkPath& path, const SkPaint& paint) {
+        SkPath fill;
+        // Hairlines do not cover an area.
+        if (!skpathutils::FillPathWithPaint(path, paint, &fill)) {
+            return;
+        }
+        fill.transform(this->getTotalMatrix());
+        fBuilder.add(fill, kUnion_SkPathOp);
+        SkPath winding;
+        if (AsWinding(fill, &winding)) {
+            fPaths.addPath(winding);
+        }
+    }
+
+    SkOpBuilder fBuilder;
+    SkPath fPaths;
+};
+
+// Renders the document with the root element sized to `viewport` and returns the area covered by
+// the fills and strokes of its shapes. Shapes with `fill="none"` contribute only their strokes.
+extern "C" void C_SkSVGDOM_asPath(const SkSVGDOM* self, const SkSize* viewport, SkPath* path) {
+    const auto* root = self->getRoot();
+    if (!root) {
+        *path = SkPath();
+        return;
+    }
+    CoverageCanvas canvas(SkScalarCeilToInt(viewport->width()), SkScalarCeilToInt(viewport->height()));
+    SkSVGIDMapper mapper;
+    SkSVGLengthContext lctx(*viewport);
+    SkSVGPresentationContext pctx;
+    SkSVGRenderContext ctx(&canvas, SkFontMgr::RefEmpty(), nullptr, mapper, lctx, pctx, {nullptr, nullptr}, nullptr);
+    root->render(ctx);
+    *path = canvas.coverage();
+}
+
+extern "C" void C_SkGraphics_SetOpenTypeSVGDecoderFactory_SVG() {
+    SkGraphics::SetOpenTypeSVGDecoderFactory(SkSVGOpenTypeSVGDecoder::Make);
+}
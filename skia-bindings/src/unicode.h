@@ -0,0 +1,29 @@
+#ifndef SKIA_BINDINGS_UNICODE_H
+#define SKIA_BINDINGS_UNICODE_H
+
+#include "modules/skunicode/include/SkUnicode.h"
+
+#if defined(SK_UNICODE_ICU_IMPLEMENTATION)
+#include "modules/skunicode/include/SkUnicode_icu.h"
+#endif
+#if defined(SK_UNICODE_ICU4X_IMPLEMENTATION)
+#include "modules/skunicode/include/SkUnicode_icu4x.h"
+#endif
+#if defined(SK_UNICODE_LIBGRAPHEME_IMPLEMENTATION)
+#include "modules/skunicode/include/SkUnicode_libgrapheme.h"
+#endif
+
+/// Creates the `SkUnicode` implementation selected by the cargo features.
+inline sk_sp<SkUnicode> makeUnicode() {
+#if defined(SK_UNICODE_ICU_IMPLEMENTATION)
+    return SkUnicodes::ICU::Make();
+#elif defined(SK_UNICODE_ICU4X_IMPLEMENTATION)
+    return SkUnicodes::ICU4X::Make();
+#elif defined(SK_UNICODE_LIBGRAPHEME_IMPLEMENTATION)
+    return SkUnicodes::Libgrapheme::Make();
+#else
+    return nullptr;
+#endif
+}
+
+#endif
@@ -0,0 +1,134 @@
+//! Derive macros for `skia-safe`.
+//!
+//! This crate is not meant to be used directly, the macros are re-exported by `skia-safe`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Expr, Fields, LitStr, Type,
+};
+
+/// Implements `skia_safe::runtime_effect::Uniforms` for a struct with named fields.
+///
+/// Each field is one SkSL uniform of the same name, in the order of declaration. The type of a
+/// field must implement `skia_safe::runtime_effect::UniformValue`.
+///
+/// Field attributes:
+///
+/// - `#[uniform(name = "...")]` sets the name of the uniform in SkSL.
+/// - `#[uniform(array)]` marks a field of type `[T; N]` as a uniform array of `N` elements of
+///   type `T`. Without it, `[f32; 2]` to `[f32; 4]` and `[i32; 2]` to `[i32; 4]` are vectors.
+#[proc_macro_derive(Uniforms, attributes(uniform))]
+pub fn derive_uniforms(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    uniforms(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn uniforms(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.ident.span(),
+                    "Uniforms can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "Uniforms can only be derived for structs",
+            ))
+        }
+    };
+
+    let krate = quote!(::skia_safe::runtime_effect);
+    let mut declarations = Vec::new();
+    let mut writes = Vec::new();
+
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let attributes = FieldAttributes::parse(field)?;
+        let name = attributes
+            .name
+            .unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+
+        if attributes.array {
+            let (element, len) = array_type(&field.ty)?;
+            declarations.push(quote! {
+                #krate::UniformField {
+                    name: #name,
+                    ty: <#element as #krate::UniformValue>::TYPE,
+                    count: #len,
+                }
+            });
+            writes.push(quote! {
+                #krate::UniformValue::write(self.#ident.as_slice(), data);
+            });
+        } else {
+            let ty = &field.ty;
+            declarations.push(quote! {
+                #krate::UniformField {
+                    name: #name,
+                    ty: <#ty as #krate::UniformValue>::TYPE,
+                    count: 1,
+                }
+            });
+            writes.push(quote! {
+                #krate::UniformValue::write(&self.#ident, data);
+            });
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #krate::Uniforms for #ident #ty_generics #where_clause {
+            const FIELDS: &'static [#krate::UniformField] = &[#(#declarations),*];
+
+            fn write(&self, data: &mut ::std::vec::Vec<u8>) {
+                #(#writes)*
+            }
+        }
+    })
+}
+
+#[derive(Default)]
+struct FieldAttributes {
+    name: Option<LitStr>,
+    array: bool,
+}
+
+impl FieldAttributes {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut attributes = Self::default();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("uniform")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    attributes.name = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("array") {
+                    attributes.array = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `name = \"...\"` or `array`"))
+                }
+            })?;
+        }
+        Ok(attributes)
+    }
+}
+
+fn array_type(ty: &Type) -> syn::Result<(&Type, &Expr)> {
+    match ty {
+        Type::Array(array) => Ok((&array.elem, &array.len)),
+        _ => Err(Error::new(
+            ty.span(),
+            "#[uniform(array)] requires a field of type [T; N]",
+        )),
+    }
+}
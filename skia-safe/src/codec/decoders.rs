@@ -1,3 +1,4 @@
+#[cfg(feature = "bmp")]
 pub mod bmp_decoder {
     use std::{io, result};
 
@@ -12,6 +13,7 @@ pub mod bmp_decoder {
     }
 }
 
+#[cfg(feature = "gif")]
 pub mod gif_decoder {
     use std::{io, result};
 
@@ -26,6 +28,7 @@ pub mod gif_decoder {
     }
 }
 
+#[cfg(feature = "ico")]
 pub mod ico_decoder {
     use std::{io, result};
 
@@ -40,6 +43,7 @@ pub mod ico_decoder {
     }
 }
 
+#[cfg(feature = "jpeg-decode")]
 pub mod jpeg_decoder {
     use std::{io, result};
 
@@ -54,6 +58,7 @@ pub mod jpeg_decoder {
     }
 }
 
+#[cfg(feature = "png-decode")]
 pub mod png_decoder {
     use std::{io, result};
 
@@ -68,6 +73,7 @@ pub mod png_decoder {
     }
 }
 
+#[cfg(feature = "wbmp")]
 pub mod wbmp_decoder {
     use std::{io, result};
 
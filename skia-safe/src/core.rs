@@ -63,6 +63,7 @@ pub mod rrect;
 mod rsxform;
 pub mod sampling_options;
 mod scalar_;
+mod sdf;
 pub mod shader;
 mod size;
 pub mod stroke_rec;
@@ -147,6 +148,7 @@ pub use sampling_options::{
     CubicResampler, FilterMode, FilterOptions, MipmapMode, SamplingMode, SamplingOptions,
 };
 pub use scalar_::*;
+pub use sdf::SdfKind;
 pub use shader::Shader;
 pub use size::*;
 pub use stroke_rec::StrokeRec;
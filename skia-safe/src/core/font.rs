@@ -3,8 +3,8 @@ use std::{fmt, ptr};
 use skia_bindings::{self as sb, SkFont, SkFont_PrivFlags};
 
 use crate::{
-    interop::VecSink, prelude::*, scalar, EncodedText, FontHinting, FontMetrics, GlyphId, Paint,
-    Path, Point, Rect, Typeface, Unichar,
+    core::sdf, interop::VecSink, prelude::*, scalar, Bitmap, EncodedText, FontHinting, FontMetrics,
+    GlyphId, IPoint, IRect, Matrix, Paint, Path, Point, Rect, RoundOut, SdfKind, Typeface, Unichar,
 };
 
 pub use skia_bindings::SkFont_Edging as Edging;
@@ -406,6 +406,32 @@ impl Font {
         unsafe { self.native().getPath(glyph_id, path.native_mut()) }.if_true_some(path)
     }
 
+    /// Returns the signed distance field of the glyph's outline and the location of its top left
+    /// corner relative to the glyph's origin.
+    ///
+    /// The field covers the bounds of the outline extended by `spread` pixels on every side. See
+    /// [`Path::to_sdf()`] for `spread` and [`SdfKind`] for the formats of the returned bitmap.
+    ///
+    /// Returns `None` for glyphs without an outline or if `spread` is not positive.
+    pub fn get_sdf(
+        &self,
+        glyph_id: GlyphId,
+        spread: scalar,
+        kind: SdfKind,
+    ) -> Option<(Bitmap, IPoint)> {
+        let path = self.get_path(glyph_id)?;
+        let bounds: IRect = path.bounds().round_out();
+        if bounds.is_empty() || spread <= 0.0 || !spread.is_finite() {
+            return None;
+        }
+        let pad = spread.ceil() as i32;
+        let origin = IPoint::new(bounds.left - pad, bounds.top - pad);
+        let size = (bounds.width() + 2 * pad, bounds.height() + 2 * pad);
+        let matrix = Matrix::translate((-origin.x as scalar, -origin.y as scalar));
+        let field = sdf::generate(&path.with_transform(&matrix), size, spread, kind)?;
+        Some((field, origin))
+    }
+
     // TODO: getPaths() (needs a function to be passed, but supports a context).
 
     pub fn metrics(&self) -> (scalar, FontMetrics) {
@@ -444,4 +470,24 @@ mod tests {
         font.set_embolden(false);
         assert!(!font.is_embolden());
     }
+
+    #[test]
+    fn glyph_sdf_covers_the_outline_and_the_spread() {
+        let typeface = FontMgr::new()
+            .legacy_make_typeface(None, FontStyle::normal())
+            .unwrap();
+        let font = Font::new(typeface, 32.0);
+        let glyph = font.unichar_to_glyph('O' as Unichar);
+        let bounds: IRect = font.get_path(glyph).unwrap().bounds().round_out();
+        let (field, origin) = font.get_sdf(glyph, 4.0, SdfKind::MultiChannel).unwrap();
+        assert_eq!(origin, IPoint::new(bounds.left - 4, bounds.top - 4));
+        assert_eq!(
+            field.dimensions(),
+            (bounds.width() + 8, bounds.height() + 8).into()
+        );
+        // The corners of the field are more than `spread` pixels outside of the outline.
+        assert_eq!(field.pixmap().pixels::<[u8; 4]>().unwrap()[0][3], 0);
+        assert!(font.get_sdf(glyph, 0.0, SdfKind::SingleChannel).is_none());
+        assert!(font.get_sdf(glyph, -1.0, SdfKind::SingleChannel).is_none());
+    }
 }
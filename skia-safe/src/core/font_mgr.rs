@@ -1,10 +1,11 @@
-use skia_bindings::{self as sb, SkFontMgr, SkFontStyleSet, SkRefCntBase};
+use skia_bindings::{self as sb, SkFontMgr, SkFontStyleSet, SkRefCntBase, SkTypeface};
 use std::{ffi::CString, fmt, mem, os::raw::c_char, ptr};
 
 use crate::{
+    graphics,
     interop::{self, DynamicMemoryWStream},
     prelude::*,
-    FontStyle, Typeface, Unichar,
+    Data, FontArguments, FontStyle, Typeface, Unichar,
 };
 
 pub type FontStyleSet = RCHandle<SkFontStyleSet>;
@@ -80,6 +81,25 @@ impl FontStyleSet {
     pub fn new_empty() -> Self {
         FontStyleSet::from_ptr(unsafe { sb::C_SkFontStyleSet_CreateEmpty() }).unwrap()
     }
+
+    /// Creates a style set that contains the given typefaces. Styles are matched with the CSS3
+    /// font matching rules.
+    pub fn from_typefaces(typefaces: &[Typeface]) -> Self {
+        let mut ptrs: Vec<*mut SkTypeface> = typefaces
+            .iter()
+            .map(|tf| tf.native() as *const _ as *mut _)
+            .collect();
+        FontStyleSet::from_ptr(unsafe {
+            sb::C_SkFontStyleSet_MakeFromTypefaces(ptrs.as_mut_ptr(), ptrs.len())
+        })
+        .unwrap()
+    }
+
+    fn match_style_or_none(&mut self, pattern: FontStyle) -> Option<Typeface> {
+        Typeface::from_ptr(unsafe {
+            sb::C_SkFontStyleSet_matchStyle(self.native_mut(), pattern.native())
+        })
+    }
 }
 
 pub type FontMgr = RCHandle<SkFontMgr>;
@@ -109,11 +129,63 @@ impl FontMgr {
     // Deprecated by Skia, but we continue to support it. This returns a font manager with
     // system fonts for the current platform.
     pub fn new() -> Self {
-        FontMgr::from_ptr(unsafe { sb::C_SkFontMgr_NewSystem() }).unwrap()
+        FontMgr::from_new_ptr(unsafe { sb::C_SkFontMgr_NewSystem() }).unwrap()
     }
 
     pub fn empty() -> Self {
-        FontMgr::from_ptr(unsafe { sb::C_SkFontMgr_RefEmpty() }).unwrap()
+        FontMgr::from_new_ptr(unsafe { sb::C_SkFontMgr_RefEmpty() }).unwrap()
+    }
+
+    /// Creates a font manager that loads all fonts in the directory `path` and its
+    /// subdirectories.
+    ///
+    /// Returns `None` if `path` is not valid UTF-8 or contains a nul character.
+    #[cfg(feature = "fontmgr-directory")]
+    pub fn from_directory(path: impl AsRef<std::path::Path>) -> Option<Self> {
+        let path = CString::new(path.as_ref().to_str()?).ok()?;
+        FontMgr::from_new_ptr(unsafe { sb::C_SkFontMgr_NewCustomDirectory(path.as_ptr()) })
+    }
+
+    /// Creates a font manager that provides the fonts in `font_data` only. This can be used for
+    /// hermetic and reproducible font selection.
+    #[cfg(feature = "fontmgr-data")]
+    pub fn from_font_data(font_data: impl IntoIterator<Item = Data>) -> Self {
+        let font_data: Vec<Data> = font_data.into_iter().collect();
+        let mut ptrs: Vec<*mut sb::SkData> = font_data
+            .iter()
+            .map(|data| data.native() as *const _ as *mut _)
+            .collect();
+        FontMgr::from_new_ptr(unsafe {
+            sb::C_SkFontMgr_NewCustomData(ptrs.as_mut_ptr(), ptrs.len())
+        })
+        .unwrap()
+    }
+
+    /// Creates a font manager that uses fontconfig to find fonts.
+    #[cfg(all(feature = "fontconfig", target_os = "linux"))]
+    pub fn fontconfig() -> Option<Self> {
+        FontMgr::from_new_ptr(unsafe { sb::C_SkFontMgr_NewFontConfig() })
+    }
+
+    /// Creates a font manager that is implemented in Rust.
+    ///
+    /// The returned font manager can be used everywhere a [`FontMgr`] is accepted, for example
+    /// for `svg::Dom`, `Shaper` or the font collection of a paragraph. `font_mgr` is dropped when
+    /// Skia releases its last reference.
+    ///
+    /// Skia may call into `font_mgr` from any thread that uses the font manager, which is why
+    /// [`FontMgrImpl`] requires [`Send`] and [`Sync`].
+    pub fn from_impl(font_mgr: impl FontMgrImpl + 'static) -> Self {
+        let param = rust_font_mgr::new_param(Box::new(font_mgr));
+        FontMgr::from_new_ptr(unsafe { sb::C_RustFontMgr_new(&param) }).unwrap()
+    }
+
+    /// Wraps a font manager that was just created and installs the default OpenType SVG decoder,
+    /// so that the glyphs of its typefaces can be rendered without calling
+    /// [`graphics::init()`].
+    fn from_new_ptr(font_mgr: *mut SkFontMgr) -> Option<Self> {
+        graphics::init_open_type_svg_decoder();
+        FontMgr::from_ptr(font_mgr)
     }
 
     pub fn count_families(&self) -> usize {
@@ -252,9 +324,266 @@ impl FontMgr {
     // TODO: makeFromStream(.., ttcIndex).
 }
 
+/// A font manager implemented in Rust, see [`FontMgr::from_impl()`].
+pub trait FontMgrImpl: Send + Sync {
+    /// The number of font families.
+    fn count_families(&self) -> usize;
+
+    /// The name of the family at `index`, `index` is less than [`Self::count_families()`].
+    fn family_name(&self, index: usize) -> String;
+
+    /// The style set of the family at `index`. By default this matches the family by its name.
+    fn new_style_set(&self, index: usize) -> FontStyleSet {
+        self.match_family(&self.family_name(index))
+    }
+
+    /// Returns the style set for the given family name. An empty `family_name` requests the
+    /// default family. Returns an empty style set if the family is not available.
+    fn match_family(&self, family_name: &str) -> FontStyleSet;
+
+    /// Finds the closest matching typeface to the family name and style. By default this matches
+    /// the style in the style set returned by [`Self::match_family()`].
+    fn match_family_style(&self, family_name: &str, style: FontStyle) -> Option<Typeface> {
+        self.match_family(family_name).match_style_or_none(style)
+    }
+
+    /// Used for font fallback: Finds a typeface that is closest to the family name and style
+    /// that can render `character`. `bcp_47` contains the requested languages, ordered from the
+    /// most to the least preferred one.
+    fn match_family_style_character(
+        &self,
+        family_name: &str,
+        style: FontStyle,
+        bcp_47: &[&str],
+        character: Unichar,
+    ) -> Option<Typeface>;
+
+    /// Creates a typeface from font data. `ttc_index` selects the face in a font collection.
+    fn new_from_data(&self, data: Data, ttc_index: usize) -> Option<Typeface>;
+
+    /// Creates a typeface from font data with the collection index, variation coordinates and
+    /// palette of `arguments`.
+    ///
+    /// Typefaces created from data, streams and files are routed through this function. By
+    /// default this invokes [`Self::new_from_data()`] and applies the variation coordinates and
+    /// the palette with [`Typeface::clone_with_arguments()`].
+    fn new_from_data_with_arguments(
+        &self,
+        data: Data,
+        arguments: &FontArguments,
+    ) -> Option<Typeface> {
+        let typeface = self.new_from_data(data, arguments.collection_index())?;
+        let palette = arguments.palette();
+        if arguments.variation_design_position().coordinates.is_empty()
+            && palette.index == 0
+            && palette.overrides.is_empty()
+        {
+            return Some(typeface);
+        }
+        typeface.clone_with_arguments(arguments)
+    }
+
+    /// Returns a typeface for the family name and style. By default this invokes
+    /// [`Self::match_family_style()`] with the default family if `family_name` is `None`.
+    fn legacy_make_typeface(
+        &self,
+        family_name: Option<&str>,
+        style: FontStyle,
+    ) -> Option<Typeface> {
+        self.match_family_style(family_name.unwrap_or_default(), style)
+    }
+}
+
+mod rust_font_mgr {
+    use std::{
+        ffi::CStr,
+        mem,
+        os::raw::{c_char, c_int},
+        panic, process,
+    };
+
+    use skia_bindings::{
+        RustFontMgr_Param, SkData, SkFontArguments, SkFontStyle, SkFontStyleSet, SkString,
+        SkTypeface, SkUnichar, TraitObject,
+    };
+
+    use super::FontMgrImpl;
+    use crate::{interop, prelude::*, Data, FontArguments, FontStyle};
+
+    pub fn new_param(font_mgr: Box<dyn FontMgrImpl>) -> RustFontMgr_Param {
+        RustFontMgr_Param {
+            trait_: unsafe {
+                mem::transmute::<*mut dyn FontMgrImpl, TraitObject>(Box::into_raw(font_mgr))
+            },
+            countFamilies: Some(count_families),
+            getFamilyName: Some(get_family_name),
+            createStyleSet: Some(create_style_set),
+            matchFamily: Some(match_family),
+            matchFamilyStyle: Some(match_family_style),
+            matchFamilyStyleCharacter: Some(match_family_style_character),
+            makeFromData: Some(make_from_data),
+            legacyMakeTypeface: Some(legacy_make_typeface),
+            drop: Some(drop),
+        }
+    }
+
+    extern "C" fn count_families(to: TraitObject) -> c_int {
+        guard("count_families", || {
+            to_font_mgr(to)
+                .count_families()
+                .try_into()
+                .unwrap_or(c_int::MAX)
+        })
+    }
+
+    extern "C" fn get_family_name(to: TraitObject, index: c_int, family_name: *mut SkString) {
+        guard("get_family_name", || {
+            let font_mgr = to_font_mgr(to);
+            let Some(index) = valid_index(font_mgr, index) else {
+                return;
+            };
+            let name = font_mgr.family_name(index);
+            *interop::String::from_native_ref_mut(unsafe { &mut *family_name }) =
+                interop::String::from_str(name);
+        })
+    }
+
+    /// Returns `null` for an invalid `index`, the caller replaces it with an empty style set.
+    extern "C" fn create_style_set(to: TraitObject, index: c_int) -> *mut SkFontStyleSet {
+        guard("create_style_set", || {
+            let font_mgr = to_font_mgr(to);
+            match valid_index(font_mgr, index) {
+                Some(index) => font_mgr.new_style_set(index).into_ptr(),
+                None => std::ptr::null_mut(),
+            }
+        })
+    }
+
+    extern "C" fn match_family(to: TraitObject, family_name: *const c_char) -> *mut SkFontStyleSet {
+        guard("match_family", || {
+            to_font_mgr(to).match_family(to_str(family_name)).into_ptr()
+        })
+    }
+
+    extern "C" fn match_family_style(
+        to: TraitObject,
+        family_name: *const c_char,
+        style: *const SkFontStyle,
+    ) -> *mut SkTypeface {
+        guard("match_family_style", || {
+            to_font_mgr(to)
+                .match_family_style(to_str(family_name), to_font_style(style))
+                .into_ptr_or_null()
+        })
+    }
+
+    extern "C" fn match_family_style_character(
+        to: TraitObject,
+        family_name: *const c_char,
+        style: *const SkFontStyle,
+        bcp_47: *mut *const c_char,
+        bcp_47_count: c_int,
+        character: SkUnichar,
+    ) -> *mut SkTypeface {
+        guard("match_family_style_character", || {
+            let bcp_47: Vec<&str> = unsafe {
+                safer::from_raw_parts(bcp_47, bcp_47_count.try_into().unwrap_or_default())
+            }
+            .iter()
+            .map(|&language| to_str(language))
+            .collect();
+            to_font_mgr(to)
+                .match_family_style_character(
+                    to_str(family_name),
+                    to_font_style(style),
+                    &bcp_47,
+                    character,
+                )
+                .into_ptr_or_null()
+        })
+    }
+
+    extern "C" fn make_from_data(
+        to: TraitObject,
+        data: *mut SkData,
+        arguments: *const SkFontArguments,
+    ) -> *mut SkTypeface {
+        guard("make_from_data", || {
+            let Some(data) = Data::from_ptr(data) else {
+                return std::ptr::null_mut();
+            };
+            // A negative collection index can not be represented by `FontArguments`.
+            if unsafe { (*arguments).fCollectionIndex } < 0 {
+                return std::ptr::null_mut();
+            }
+            let arguments = FontArguments::from_native_ref(unsafe { &*arguments });
+            to_font_mgr(to)
+                .new_from_data_with_arguments(data, arguments)
+                .into_ptr_or_null()
+        })
+    }
+
+    extern "C" fn legacy_make_typeface(
+        to: TraitObject,
+        family_name: *const c_char,
+        style: *const SkFontStyle,
+    ) -> *mut SkTypeface {
+        guard("legacy_make_typeface", || {
+            let family_name = (!family_name.is_null()).then(|| to_str(family_name));
+            to_font_mgr(to)
+                .legacy_make_typeface(family_name, to_font_style(style))
+                .into_ptr_or_null()
+        })
+    }
+
+    extern "C" fn drop(to: TraitObject) {
+        guard("drop", || {
+            mem::drop(unsafe {
+                Box::from_raw(mem::transmute::<TraitObject, *mut dyn FontMgrImpl>(to))
+            })
+        })
+    }
+
+    /// Aborts if `f` panics, unwinding into Skia is not possible.
+    fn guard<R>(callback: &str, f: impl FnOnce() -> R) -> R {
+        match panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+            Ok(r) => r,
+            Err(_) => {
+                println!("Panic in FFI callback `RustFontMgr::{callback}`");
+                process::abort();
+            }
+        }
+    }
+
+    fn to_font_mgr<'a>(to: TraitObject) -> &'a dyn FontMgrImpl {
+        unsafe { &*mem::transmute::<TraitObject, *const dyn FontMgrImpl>(to) }
+    }
+
+    fn valid_index(font_mgr: &dyn FontMgrImpl, index: c_int) -> Option<usize> {
+        usize::try_from(index)
+            .ok()
+            .filter(|index| *index < font_mgr.count_families())
+    }
+
+    fn to_font_style(style: *const SkFontStyle) -> FontStyle {
+        *FontStyle::from_native_ref(unsafe { &*style })
+    }
+
+    /// Converts a C string to a `&str`, `null` and invalid UTF-8 are converted to an empty
+    /// string.
+    fn to_str<'a>(str: *const c_char) -> &'a str {
+        if str.is_null() {
+            return "";
+        }
+        unsafe { CStr::from_ptr(str) }.to_str().unwrap_or_default()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::FontMgr;
+    use std::sync::{Arc, Mutex};
+
+    use crate::{Data, FontMgr, FontMgrImpl, FontStyle, FontStyleSet, Typeface, Unichar};
 
     #[test]
     #[serial_test::serial]
@@ -279,4 +608,87 @@ mod tests {
             }
         }
     }
+
+    struct SingleFamily(Vec<Typeface>, Arc<Mutex<Option<usize>>>);
+
+    impl FontMgrImpl for SingleFamily {
+        fn count_families(&self) -> usize {
+            1
+        }
+
+        fn family_name(&self, _index: usize) -> String {
+            "Single".into()
+        }
+
+        fn match_family(&self, family_name: &str) -> FontStyleSet {
+            match family_name {
+                "" | "Single" => FontStyleSet::from_typefaces(&self.0),
+                _ => FontStyleSet::new_empty(),
+            }
+        }
+
+        fn match_family_style_character(
+            &self,
+            _family_name: &str,
+            _style: FontStyle,
+            _bcp_47: &[&str],
+            character: Unichar,
+        ) -> Option<Typeface> {
+            self.0
+                .iter()
+                .find(|tf| tf.unichar_to_glyph(character) != 0)
+                .cloned()
+        }
+
+        fn new_from_data(&self, _data: Data, ttc_index: usize) -> Option<Typeface> {
+            *self.1.lock().unwrap() = Some(ttc_index);
+            None
+        }
+    }
+
+    #[cfg(feature = "fontmgr-data")]
+    #[test]
+    fn font_mgr_from_font_data() {
+        let ubuntu = Data::new_copy(include_bytes!(
+            "../../../skia-org/src/fonts/Ubuntu-Regular.ttf"
+        ));
+        let font_mgr = FontMgr::from_font_data([ubuntu]);
+        assert_eq!(font_mgr.count_families(), 1);
+        assert_eq!(font_mgr.family_name(0), "Ubuntu");
+        assert!(font_mgr
+            .match_family_style("Ubuntu", FontStyle::normal())
+            .is_some());
+    }
+
+    #[cfg(feature = "fontmgr-directory")]
+    #[test]
+    fn font_mgr_from_directory() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../skia-org/src/fonts");
+        let font_mgr = FontMgr::from_directory(path).unwrap();
+        assert!(font_mgr.family_names().any(|name| name == "Ubuntu"));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn rust_font_mgr() {
+        let typeface = FontMgr::default()
+            .legacy_make_typeface(None, FontStyle::default())
+            .unwrap();
+        let ttc_index = Arc::new(Mutex::new(None));
+        let font_mgr = FontMgr::from_impl(SingleFamily(vec![typeface], ttc_index.clone()));
+        assert_eq!(font_mgr.count_families(), 1);
+        assert_eq!(font_mgr.family_name(0), "Single");
+        assert_eq!(font_mgr.new_style_set(0).count(), 1);
+        assert!(font_mgr
+            .match_family_style("Single", FontStyle::bold())
+            .is_some());
+        assert!(font_mgr
+            .match_family_style("Unknown", FontStyle::normal())
+            .is_none());
+        assert!(font_mgr
+            .match_family_style_character("", FontStyle::normal(), &["en"], 'A' as Unichar)
+            .is_some());
+        assert!(font_mgr.new_from_data(&[0; 16], 2).is_none());
+        assert_eq!(*ttc_index.lock().unwrap(), Some(2));
+    }
 }
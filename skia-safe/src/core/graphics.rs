@@ -1,7 +1,20 @@
-use skia_bindings::SkGraphics;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, RwLock,
+};
 
+use skia_bindings::{self as sb, SkGraphics};
+
+use crate::{Canvas, Color, GlyphId};
+
+/// Initializes Skia's global state.
+///
+/// If the `svg` feature is enabled, this also installs the `svg` module as the renderer of the
+/// glyphs of OpenType `SVG ` tables, unless a decoder was chosen before. This also happens when
+/// the first [`crate::FontMgr`] or deserialized [`crate::Typeface`] is created.
 pub fn init() {
     unsafe { SkGraphics::Init() };
+    init_open_type_svg_decoder();
 }
 
 pub fn font_cache_limit() -> usize {
@@ -87,4 +100,253 @@ pub fn purge_all_caches() {
 }
 
 // TODO: ImageGeneratorFromEncodedDataFactory
-// TODO: SetOpenTypeSVGDecoderFactory & GetOpenTypeSVGDecoderFactory
+
+/// Renders the glyphs of one SVG document of an OpenType `SVG ` table.
+pub trait OpenTypeSVGDecoder: Send {
+    /// The approximate number of bytes the decoder uses, which counts towards the font cache.
+    fn approximate_size(&self) -> usize;
+
+    /// Renders the glyph `glyph_id` into `canvas`, which is scaled so that one unit is one font
+    /// unit of a font with `upem` units per em. `foreground_color` is the text color and `palette`
+    /// the selected color palette of the font. Returns `false` if the glyph could not be rendered.
+    fn render(
+        &mut self,
+        canvas: &Canvas,
+        upem: i32,
+        glyph_id: GlyphId,
+        foreground_color: Color,
+        palette: &[Color],
+    ) -> bool;
+}
+
+type OpenTypeSVGDecoderFactory =
+    Arc<dyn Fn(&[u8]) -> Option<Box<dyn OpenTypeSVGDecoder>> + Send + Sync>;
+
+static OPEN_TYPE_SVG_DECODER_FACTORY: RwLock<Option<OpenTypeSVGDecoderFactory>> = RwLock::new(None);
+static OPEN_TYPE_SVG_DECODER_FACTORY_SET: AtomicBool = AtomicBool::new(false);
+
+/// Sets the factory that creates the decoders of the SVG documents of OpenType `SVG ` tables.
+///
+/// The factory receives the (possibly gzip compressed) SVG document and returns `None` if it does
+/// not support it. This replaces the decoder of the `svg` feature.
+///
+/// The factory and the decoders are called from the threads that render text. If one of them
+/// panics, the process is aborted.
+pub fn set_open_type_svg_decoder_factory(
+    factory: impl Fn(&[u8]) -> Option<Box<dyn OpenTypeSVGDecoder>> + Send + Sync + 'static,
+) {
+    let mut current = OPEN_TYPE_SVG_DECODER_FACTORY.write().unwrap();
+    *current = Some(Arc::new(factory));
+    OPEN_TYPE_SVG_DECODER_FACTORY_SET.store(true, Ordering::SeqCst);
+    unsafe { sb::C_SkGraphics_SetOpenTypeSVGDecoderFactory_Rust(Some(open_type_svg::make)) }
+}
+
+/// Removes the OpenType SVG decoder factory, glyphs of `SVG ` tables are not rendered anymore.
+pub fn remove_open_type_svg_decoder_factory() {
+    let mut current = OPEN_TYPE_SVG_DECODER_FACTORY.write().unwrap();
+    OPEN_TYPE_SVG_DECODER_FACTORY_SET.store(true, Ordering::SeqCst);
+    unsafe { sb::C_SkGraphics_SetOpenTypeSVGDecoderFactory_Rust(None) }
+    *current = None;
+}
+
+/// Renders the glyphs of OpenType `SVG ` tables with the `svg` module.
+///
+/// This is also done by [`init()`] and when the first font manager is created, if no other
+/// decoder was chosen before.
+#[cfg(feature = "svg")]
+pub fn set_svg_module_open_type_svg_decoder() {
+    let mut current = OPEN_TYPE_SVG_DECODER_FACTORY.write().unwrap();
+    OPEN_TYPE_SVG_DECODER_FACTORY_SET.store(true, Ordering::SeqCst);
+    unsafe { sb::C_SkGraphics_SetOpenTypeSVGDecoderFactory_SVG() }
+    *current = None;
+}
+
+/// Installs the default OpenType SVG decoder, if no decoder was chosen yet.
+///
+/// Called by [`init()`] and by the functions that create font managers and typefaces, so that
+/// OpenType-SVG glyphs are rendered without an explicit call to [`init()`].
+pub(crate) fn init_open_type_svg_decoder() {
+    if OPEN_TYPE_SVG_DECODER_FACTORY_SET.load(Ordering::SeqCst) {
+        return;
+    }
+    // The lock orders this against the functions that choose a decoder explicitly.
+    let _current = OPEN_TYPE_SVG_DECODER_FACTORY.write().unwrap();
+    if OPEN_TYPE_SVG_DECODER_FACTORY_SET.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    #[cfg(feature = "svg")]
+    unsafe {
+        sb::C_SkGraphics_SetOpenTypeSVGDecoderFactory_SVG()
+    }
+}
+
+mod open_type_svg {
+    use std::{mem, panic, process};
+
+    use skia_bindings::{RustOpenTypeSVGDecoder_Param, SkCanvas, SkColor, SkGlyphID, TraitObject};
+
+    use super::{OpenTypeSVGDecoder, OPEN_TYPE_SVG_DECODER_FACTORY};
+    use crate::{prelude::*, Canvas, Color};
+
+    pub extern "C" fn make(
+        svg: *const u8,
+        length: usize,
+        param: *mut RustOpenTypeSVGDecoder_Param,
+    ) -> bool {
+        // The factory is called without holding the lock, so that it may replace itself.
+        let Some(factory) = OPEN_TYPE_SVG_DECODER_FACTORY
+            .read()
+            .ok()
+            .and_then(|factory| factory.clone())
+        else {
+            return false;
+        };
+        let svg = unsafe { safer::from_raw_parts(svg, length) };
+        let Some(decoder) = guard("make", || factory(svg)) else {
+            return false;
+        };
+        unsafe {
+            *param = RustOpenTypeSVGDecoder_Param {
+                trait_: mem::transmute::<*mut dyn OpenTypeSVGDecoder, TraitObject>(Box::into_raw(
+                    decoder,
+                )),
+                approximateSize: Some(approximate_size),
+                render: Some(render),
+                drop: Some(drop),
+            }
+        };
+        true
+    }
+
+    extern "C" fn approximate_size(to: TraitObject) -> usize {
+        guard("approximate_size", || to_decoder(to).approximate_size())
+    }
+
+    extern "C" fn render(
+        to: TraitObject,
+        canvas: *mut SkCanvas,
+        upem: i32,
+        glyph_id: SkGlyphID,
+        foreground_color: SkColor,
+        palette: *const SkColor,
+        palette_count: usize,
+    ) -> bool {
+        let canvas = Canvas::borrow_from_native(unsafe { &*canvas });
+        let palette: &[Color] =
+            unsafe { safer::from_raw_parts(Color::from_native_ptr(palette), palette_count) };
+        guard("render", || {
+            to_decoder(to).render(
+                canvas,
+                upem,
+                glyph_id,
+                Color::from_native_c(foreground_color),
+                palette,
+            )
+        })
+    }
+
+    extern "C" fn drop(to: TraitObject) {
+        guard("drop", || {
+            mem::drop(unsafe {
+                Box::from_raw(mem::transmute::<TraitObject, *mut dyn OpenTypeSVGDecoder>(
+                    to,
+                ))
+            })
+        })
+    }
+
+    /// Panics must not unwind into Skia.
+    fn guard<R>(callback: &str, f: impl FnOnce() -> R) -> R {
+        match panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+            Ok(r) => r,
+            Err(_) => {
+                println!("Panic in FFI callback `OpenTypeSVGDecoder::{callback}`");
+                process::abort();
+            }
+        }
+    }
+
+    fn to_decoder<'a>(to: TraitObject) -> &'a mut dyn OpenTypeSVGDecoder {
+        unsafe { &mut *mem::transmute::<TraitObject, *mut dyn OpenTypeSVGDecoder>(to) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use skia_bindings as sb;
+
+    #[cfg(feature = "svg")]
+    use super::OPEN_TYPE_SVG_DECODER_FACTORY_SET;
+    use super::{
+        remove_open_type_svg_decoder_factory, set_open_type_svg_decoder_factory, OpenTypeSVGDecoder,
+    };
+    #[cfg(feature = "svg")]
+    use crate::FontMgr;
+    use crate::{Canvas, Color, GlyphId};
+
+    struct Decoder;
+
+    impl OpenTypeSVGDecoder for Decoder {
+        fn approximate_size(&self) -> usize {
+            0
+        }
+
+        fn render(&mut self, _: &Canvas, _: i32, _: GlyphId, _: Color, _: &[Color]) -> bool {
+            false
+        }
+    }
+
+    /// Creates a decoder with the factory Skia uses to render OpenType `SVG ` glyphs.
+    fn make_decoder(svg: &[u8]) -> bool {
+        unsafe { sb::C_SkGraphics_OpenTypeSVGDecoderFactory_make(svg.as_ptr(), svg.len()) }
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn custom_open_type_svg_decoder_factory_is_invoked() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        set_open_type_svg_decoder_factory(|svg| {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            (svg == b"<svg/>").then(|| Box::new(Decoder) as Box<dyn OpenTypeSVGDecoder>)
+        });
+        assert!(make_decoder(b"<svg/>"));
+        assert!(!make_decoder(b"<html/>"));
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+
+        remove_open_type_svg_decoder_factory();
+        assert!(!make_decoder(b"<svg/>"));
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn open_type_svg_decoder_factory_can_replace_itself() {
+        set_open_type_svg_decoder_factory(|_| {
+            remove_open_type_svg_decoder_factory();
+            Some(Box::new(Decoder))
+        });
+        assert!(make_decoder(b"<svg/>"));
+        assert!(!make_decoder(b"<svg/>"));
+    }
+
+    #[cfg(feature = "svg")]
+    #[test]
+    #[serial_test::serial]
+    fn creating_a_font_mgr_installs_the_svg_module_decoder() {
+        remove_open_type_svg_decoder_factory();
+        OPEN_TYPE_SVG_DECODER_FACTORY_SET.store(false, Ordering::SeqCst);
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg"/>"#;
+        assert!(!make_decoder(svg));
+
+        let _font_mgr = FontMgr::new();
+        assert!(make_decoder(svg));
+
+        // An explicitly chosen decoder is not replaced.
+        remove_open_type_svg_decoder_factory();
+        let _font_mgr = FontMgr::new();
+        assert!(!make_decoder(svg));
+    }
+}
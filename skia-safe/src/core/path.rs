@@ -3,8 +3,9 @@ use std::{fmt, marker::PhantomData, mem::forget, ptr};
 use skia_bindings::{self as sb, SkPath, SkPath_Iter, SkPath_RawIter};
 
 use crate::{
-    interop::DynamicMemoryWStream, matrix::ApplyPerspectiveClip, path_types, prelude::*, scalar,
-    Arc, Data, Matrix, PathDirection, PathFillType, Point, RRect, Rect, Vector,
+    core::sdf, interop::DynamicMemoryWStream, matrix::ApplyPerspectiveClip, path_types, prelude::*,
+    scalar, Arc, Bitmap, Data, ISize, Matrix, PathDirection, PathFillType, Point, RRect, Rect,
+    SdfKind, Vector,
 };
 
 #[deprecated(since = "0.25.0", note = "use PathDirection")]
@@ -2074,6 +2075,23 @@ impl Path {
     pub fn is_valid(&self) -> bool {
         unsafe { self.native().isValid() }
     }
+
+    /// Returns the signed distance field of the [`Path`] transformed by `matrix` in an area of
+    /// `size` pixels.
+    ///
+    /// `spread` is the distance in pixels from the outline at which the encoded distance reaches
+    /// `0` outside and `255` inside. See [`SdfKind`] for the formats of the returned bitmap.
+    ///
+    /// Returns `None` if `size` is empty or `spread` is not positive.
+    pub fn to_sdf(
+        &self,
+        matrix: &Matrix,
+        size: impl Into<ISize>,
+        spread: scalar,
+        kind: SdfKind,
+    ) -> Option<Bitmap> {
+        sdf::generate(&self.with_transform(matrix), size, spread, kind)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,523 @@
+//! Signed distance fields computed from the outline of a path, see [`Path::to_sdf()`] and
+//! [`crate::Font::get_sdf()`].
+//!
+//! The distances are computed analytically from the flattened outline, so the spread can be chosen
+//! freely and multi-channel fields can preserve sharp corners.
+
+use crate::{
+    path::{self, Verb},
+    scalar, AlphaType, Bitmap, ColorType, ISize, ImageInfo, Path, Point,
+};
+
+/// The format of a signed distance field.
+///
+/// Every channel encodes a signed distance `d` to the outline as `0.5 + d / (2 * spread)`, clamped
+/// to `0..=1`. Positive distances are inside the shape, so `128` lies on the outline.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum SdfKind {
+    /// A [`ColorType::Alpha8`] bitmap with the true signed distance to the outline.
+    #[default]
+    SingleChannel,
+    /// A [`ColorType::RGBA8888`] bitmap with unpremultiplied alpha. The red, green and blue
+    /// channels hold distances to differently colored edges of the outline. The median of the
+    /// three channels reconstructs the outline with sharp corners. The alpha channel holds the
+    /// true signed distance of [`Self::SingleChannel`].
+    MultiChannel,
+}
+
+/// Computes the distance field of `path` into a bitmap of `size` pixels. `path` is in pixel
+/// coordinates, distances are measured from the pixel centers.
+pub(crate) fn generate(
+    path: &Path,
+    size: impl Into<ISize>,
+    spread: scalar,
+    kind: SdfKind,
+) -> Option<Bitmap> {
+    let size = size.into();
+    if size.is_empty() || spread <= 0.0 || !spread.is_finite() {
+        return None;
+    }
+    let contours = contours(path);
+    let (color_type, alpha_type) = match kind {
+        SdfKind::SingleChannel => (ColorType::Alpha8, AlphaType::Premul),
+        SdfKind::MultiChannel => (ColorType::RGBA8888, AlphaType::Unpremul),
+    };
+    let mut bitmap = Bitmap::new();
+    if !bitmap.try_alloc_pixels_info(&ImageInfo::new(size, color_type, alpha_type, None), None) {
+        return None;
+    }
+
+    let bytes_per_pixel = bitmap.bytes_per_pixel();
+    let row_bytes = bitmap.row_bytes();
+    let pixels = unsafe {
+        std::slice::from_raw_parts_mut(bitmap.pixels() as *mut u8, row_bytes * size.height as usize)
+    };
+    for y in 0..size.height as usize {
+        let row = &mut pixels[y * row_bytes..][..size.width as usize * bytes_per_pixel];
+        for (x, pixel) in row.chunks_exact_mut(bytes_per_pixel).enumerate() {
+            let p = Point::new(x as scalar + 0.5, y as scalar + 0.5);
+            let inside = path.contains(p);
+            match kind {
+                SdfKind::SingleChannel => {
+                    pixel[0] = encode(true_distance(&contours, p, inside), spread)
+                }
+                SdfKind::MultiChannel => {
+                    let (distances, true_distance) = multi_channel_distances(&contours, p, inside);
+                    for (channel, distance) in pixel.iter_mut().zip(distances) {
+                        *channel = encode(distance, spread);
+                    }
+                    pixel[3] = encode(true_distance, spread);
+                }
+            }
+        }
+    }
+    bitmap.set_immutable();
+    Some(bitmap)
+}
+
+fn encode(distance: scalar, spread: scalar) -> u8 {
+    ((0.5 + distance / (2.0 * spread)).clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+const RED: u8 = 1;
+const GREEN: u8 = 2;
+const BLUE: u8 = 4;
+const YELLOW: u8 = RED | GREEN;
+const CYAN: u8 = GREEN | BLUE;
+const WHITE: u8 = RED | GREEN | BLUE;
+
+/// A segment of the path flattened to a polyline, and the channels it contributes to.
+struct Edge {
+    points: Vec<Point>,
+    /// The tangents of the segment at its end points. They differ from the directions of the
+    /// first and last chord of a flattened curve.
+    start_tangent: Point,
+    end_tangent: Point,
+    color: u8,
+}
+
+struct Contour {
+    edges: Vec<Edge>,
+    /// `1.0` if the shape is on the left side of the edges, `-1.0` if it is on the right side.
+    inside: scalar,
+}
+
+/// The distance to the closest point of an edge.
+#[derive(Copy, Clone)]
+struct EdgeDistance {
+    /// The absolute distance.
+    distance: scalar,
+    /// How parallel the direction to the closest point is to the edge, only non-zero at the
+    /// vertices of the polyline. Used to resolve ties between edges that share a vertex.
+    dot: scalar,
+    /// The distance, positive on the left side of the edge.
+    signed: scalar,
+    /// The signed distance to the edge extended by tangents beyond its end points.
+    pseudo: scalar,
+}
+
+impl EdgeDistance {
+    const FAR: Self = Self {
+        distance: scalar::INFINITY,
+        dot: 0.0,
+        signed: scalar::INFINITY,
+        pseudo: scalar::INFINITY,
+    };
+
+    fn is_closer_than(&self, other: &Self) -> bool {
+        const EPSILON: scalar = 1e-4;
+        if (self.distance - other.distance).abs() <= EPSILON {
+            self.dot < other.dot
+        } else {
+            self.distance < other.distance
+        }
+    }
+}
+
+fn true_distance(contours: &[Contour], p: Point, inside: bool) -> scalar {
+    let distance = contours
+        .iter()
+        .flat_map(|contour| &contour.edges)
+        .map(|edge| edge_distance(edge, p).distance)
+        .fold(scalar::INFINITY, scalar::min);
+    if inside {
+        distance
+    } else {
+        -distance
+    }
+}
+
+/// Returns the distances of the red, green, and blue channel and the true signed distance.
+fn multi_channel_distances(contours: &[Contour], p: Point, inside: bool) -> ([scalar; 3], scalar) {
+    let mut closest = [(EdgeDistance::FAR, 1.0); 3];
+    let mut true_distance = scalar::INFINITY;
+    for contour in contours {
+        for edge in &contour.edges {
+            let distance = edge_distance(edge, p);
+            true_distance = true_distance.min(distance.distance);
+            for (channel, closest) in closest.iter_mut().enumerate() {
+                if edge.color & (1 << channel) != 0 && distance.is_closer_than(&closest.0) {
+                    *closest = (distance, contour.inside);
+                }
+            }
+        }
+    }
+    let true_distance = if inside {
+        true_distance
+    } else {
+        -true_distance
+    };
+    let distances = closest.map(|(distance, sign)| distance.pseudo * sign);
+
+    // Where the median disagrees with the fill, for example where contours overlap, the channels
+    // are replaced by the true distance.
+    let mut sorted = distances;
+    sorted.sort_by(scalar::total_cmp);
+    let median = sorted[1];
+    if !median.is_finite() || (median > 0.0) != inside {
+        return ([true_distance; 3], true_distance);
+    }
+    (distances, true_distance)
+}
+
+fn edge_distance(edge: &Edge, p: Point) -> EdgeDistance {
+    let last = edge.points.len() - 2;
+    let mut closest = EdgeDistance::FAR;
+    let mut closest_segment = (0, 0.0);
+    for (i, segment) in edge.points.windows(2).enumerate() {
+        let (a, b) = (segment[0], segment[1]);
+        let ab = b - a;
+        let t = Point::dot_product(p - a, ab) / Point::dot_product(ab, ab);
+        let q = a + ab * t.clamp(0.0, 1.0);
+        let qp = p - q;
+        let distance = length(qp);
+        let dot = if (0.0..=1.0).contains(&t) || distance == 0.0 {
+            0.0
+        } else {
+            (Point::dot_product(ab, qp) / (length(ab) * distance)).abs()
+        };
+        let candidate = EdgeDistance {
+            distance,
+            dot,
+            signed: distance.copysign(Point::cross_product(ab, qp)),
+            pseudo: 0.0,
+        };
+        if candidate.is_closer_than(&closest) {
+            closest = candidate;
+            closest_segment = (i, t);
+        }
+    }
+
+    // Beyond the end points of the edge, the distance to the tangent is used, if it is closer.
+    closest.pseudo = closest.signed;
+    let (i, t) = closest_segment;
+    let tangent = match (i, t) {
+        (0, t) if t < 0.0 => Some((edge.points[0], edge.start_tangent)),
+        (i, t) if i == last && t > 1.0 => Some((edge.points[i + 1], edge.end_tangent)),
+        _ => None,
+    };
+    if let Some((end, direction)) = tangent {
+        let pseudo = Point::cross_product(direction, p - end) / length(direction);
+        if pseudo.abs() <= closest.distance {
+            closest.pseudo = pseudo;
+        }
+    }
+    closest
+}
+
+fn length(v: Point) -> scalar {
+    (v.x * v.x + v.y * v.y).sqrt()
+}
+
+/// About the maximum distance in pixels between a curve and the chords it is flattened to.
+const FLATTEN_TOLERANCE: scalar = 0.05;
+
+fn contours(path: &Path) -> Vec<Contour> {
+    let mut contours = Vec::new();
+    let mut edges = Vec::new();
+    let mut iter = path::Iter::new(path, true);
+    while let Some((verb, points)) = iter.next() {
+        let edge = match verb {
+            Verb::Move => {
+                finish_contour(path, &mut contours, std::mem::take(&mut edges));
+                continue;
+            }
+            Verb::Line => points.clone(),
+            Verb::Quad => flatten(&points, |t| {
+                let u = 1.0 - t;
+                points[0] * (u * u) + points[1] * (2.0 * u * t) + points[2] * (t * t)
+            }),
+            Verb::Conic => {
+                let w = iter.conic_weight().unwrap_or(1.0);
+                flatten(&points, |t| {
+                    let u = 1.0 - t;
+                    let (a, b, c) = (u * u, 2.0 * u * t * w, t * t);
+                    (points[0] * a + points[1] * b + points[2] * c) / (a + b + c)
+                })
+            }
+            Verb::Cubic => flatten(&points, |t| {
+                let u = 1.0 - t;
+                points[0] * (u * u * u)
+                    + points[1] * (3.0 * u * u * t)
+                    + points[2] * (3.0 * u * t * t)
+                    + points[3] * (t * t * t)
+            }),
+            _ => continue,
+        };
+        let (first, last) = (points[0], points[points.len() - 1]);
+        let start_tangent = points.iter().find(|p| **p != first).map(|p| *p - first);
+        let end_tangent = points.iter().rev().find(|p| **p != last).map(|p| last - *p);
+        let (Some(start_tangent), Some(end_tangent)) = (start_tangent, end_tangent) else {
+            continue;
+        };
+        let mut edge_points: Vec<Point> = Vec::with_capacity(edge.len());
+        for p in edge {
+            if edge_points.last() != Some(&p) {
+                edge_points.push(p);
+            }
+        }
+        if edge_points.len() >= 2 {
+            edges.push(Edge {
+                points: edge_points,
+                start_tangent,
+                end_tangent,
+                color: WHITE,
+            });
+        }
+    }
+    finish_contour(path, &mut contours, edges);
+    contours
+}
+
+fn flatten(control: &[Point], eval: impl Fn(scalar) -> Point) -> Vec<Point> {
+    let control_length: scalar = control.windows(2).map(|s| length(s[1] - s[0])).sum();
+    let segments = (control_length / (8.0 * FLATTEN_TOLERANCE))
+        .sqrt()
+        .ceil()
+        .clamp(1.0, 256.0) as usize;
+    (0..=segments)
+        .map(|i| eval(i as scalar / segments as scalar))
+        .collect()
+}
+
+fn finish_contour(path: &Path, contours: &mut Vec<Contour>, mut edges: Vec<Edge>) {
+    if edges.is_empty() {
+        return;
+    }
+    color_edges(&mut edges);
+    let inside = inside_side(path, &edges);
+    contours.push(Contour { edges, inside });
+}
+
+/// Tests on which side of the contour the shape is filled.
+///
+/// The test is done at a point that lies on the outline: the middle of the longest line or a
+/// vertex of a flattened curve.
+fn inside_side(path: &Path, edges: &[Edge]) -> scalar {
+    let candidates = edges.iter().flat_map(|edge| {
+        let line = (edge.points.len() == 2).then(|| {
+            (
+                (edge.points[0] + edge.points[1]) / 2.0,
+                edge.points[1] - edge.points[0],
+            )
+        });
+        let vertices = edge.points.windows(3).map(|w| (w[1], w[2] - w[0]));
+        line.into_iter().chain(vertices)
+    });
+    let longest = candidates.max_by(|(_, a), (_, b)| length(*a).total_cmp(&length(*b)));
+    if let Some((center, direction)) = longest {
+        let normal = Point::new(-direction.y, direction.x) / length(direction);
+        let offset = normal * (length(direction) / 4.0).min(0.01);
+        match (
+            path.contains(center + offset),
+            path.contains(center - offset),
+        ) {
+            (true, false) => return 1.0,
+            (false, true) => return -1.0,
+            _ => {}
+        }
+    }
+    1.0
+}
+
+/// Assigns the channels to the edges of a contour so that the edges that meet at a corner share
+/// only one channel, see Viktor Chlumský's msdfgen.
+fn color_edges(edges: &mut Vec<Edge>) {
+    let mut corners = find_corners(edges);
+    if corners.is_empty() {
+        // Smooth contour, all channels are the same.
+        return;
+    }
+    if corners.len() == 1 {
+        // A teardrop shape, the contour is split into three parts of different colors.
+        if edges.len() < 3 {
+            let parts = if edges.len() == 1 { 3 } else { 2 };
+            *edges = std::mem::take(edges)
+                .into_iter()
+                .flat_map(|edge| split(edge, parts))
+                .collect();
+            corners = find_corners(edges);
+        }
+        let colors = [CYAN, WHITE, YELLOW];
+        let n = edges.len();
+        let corner = corners.first().copied().unwrap_or_default();
+        for i in 0..n {
+            let third = (3.0 + 2.875 * i as f64 / (n - 1) as f64 - 1.4375 + 0.5) as usize - 2;
+            edges[(corner + i) % n].color = colors[third.min(2)];
+        }
+        return;
+    }
+    let initial = CYAN;
+    let mut color = initial;
+    let mut spline = 0;
+    let start = corners[0];
+    let n = edges.len();
+    for i in 0..n {
+        let index = (start + i) % n;
+        if spline + 1 < corners.len() && corners[spline + 1] == index {
+            spline += 1;
+            let banned = if spline == corners.len() - 1 {
+                initial
+            } else {
+                0
+            };
+            color = switch_color(color, banned);
+        }
+        edges[index].color = color;
+    }
+}
+
+/// Returns the indices of the edges that start at a corner.
+fn find_corners(edges: &[Edge]) -> Vec<usize> {
+    // The sine of the maximum angle between the tangents of two smooth edges (3 radians).
+    const SMOOTH: scalar = 0.141;
+    let Some(last) = edges.last() else {
+        return Vec::new();
+    };
+    let mut previous = last.end_tangent;
+    let mut corners = Vec::new();
+    for (i, edge) in edges.iter().enumerate() {
+        let direction = edge.start_tangent;
+        let (a, b) = (previous / length(previous), direction / length(direction));
+        if Point::dot_product(a, b) <= 0.0 || Point::cross_product(a, b).abs() > SMOOTH {
+            corners.push(i);
+        }
+        previous = edge.end_tangent;
+    }
+    corners
+}
+
+/// Switches to one of the two other two-channel colors, avoiding `banned` if possible.
+fn switch_color(color: u8, banned: u8) -> u8 {
+    let combined = color & banned;
+    if combined == RED || combined == GREEN || combined == BLUE {
+        return combined ^ WHITE;
+    }
+    let shifted = color << 1;
+    (shifted | shifted >> 3) & WHITE
+}
+
+/// Splits an edge into `parts` of about the same length.
+fn split(edge: Edge, parts: usize) -> Vec<Edge> {
+    let lengths: Vec<scalar> = edge
+        .points
+        .windows(2)
+        .scan(0.0, |total, s| {
+            *total += length(s[1] - s[0]);
+            Some(*total)
+        })
+        .collect();
+    let total = lengths[lengths.len() - 1];
+    let mut result = Vec::with_capacity(parts);
+    let mut points = vec![edge.points[0]];
+    let mut start_tangent = edge.start_tangent;
+    let mut segment = 0;
+    for part in 1..=parts {
+        let end = total * part as scalar / parts as scalar;
+        while segment < lengths.len() - 1 && lengths[segment] < end {
+            segment += 1;
+            points.push(edge.points[segment]);
+        }
+        let start = if segment == 0 {
+            0.0
+        } else {
+            lengths[segment - 1]
+        };
+        let (a, b) = (edge.points[segment], edge.points[segment + 1]);
+        let (split_point, end_tangent) = if part == parts {
+            (b, edge.end_tangent)
+        } else {
+            let t = ((end - start) / (lengths[segment] - start)).clamp(0.0, 1.0);
+            (a + (b - a) * t, b - a)
+        };
+        if points.last() != Some(&split_point) {
+            points.push(split_point);
+        }
+        if points.len() >= 2 {
+            result.push(Edge {
+                points: std::mem::replace(&mut points, vec![split_point]),
+                start_tangent,
+                end_tangent,
+                color: edge.color,
+            });
+            start_tangent = end_tangent;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Path, Rect, SdfKind};
+
+    #[test]
+    fn single_channel_rect() {
+        let path = Path::rect(Rect::from_xywh(4.0, 4.0, 12.0, 12.0), None);
+        let field = super::generate(&path, (20, 20), 4.0, SdfKind::SingleChannel).unwrap();
+        let pixmap = field.pixmap();
+        let alpha = |x, y| pixmap.pixels::<u8>().unwrap()[y * pixmap.row_bytes() + x];
+        // 1.5 pixels inside: 0.5 + 1.5 / 8
+        assert_eq!(alpha(5, 10), 175);
+        // 1.5 pixels outside.
+        assert_eq!(alpha(2, 10), 80);
+        // Beyond the spread.
+        assert_eq!(alpha(10, 10), 255);
+        assert_eq!(alpha(0, 0), 0);
+    }
+
+    #[test]
+    fn spread_scales_the_distances() {
+        let path = Path::rect(Rect::from_xywh(4.0, 4.0, 12.0, 12.0), None);
+        let field = super::generate(&path, (20, 20), 2.0, SdfKind::SingleChannel).unwrap();
+        let pixmap = field.pixmap();
+        // 1.5 pixels inside: 0.5 + 1.5 / 4
+        assert_eq!(
+            pixmap.pixels::<u8>().unwrap()[10 * pixmap.row_bytes() + 5],
+            223
+        );
+        assert!(super::generate(&path, (20, 20), 0.0, SdfKind::SingleChannel).is_none());
+    }
+
+    #[test]
+    fn multi_channel_median_matches_the_fill() {
+        let mut path = Path::new();
+        path.move_to((2.0, 2.0))
+            .line_to((18.0, 4.0))
+            .line_to((6.0, 18.0))
+            .close();
+        path.add_circle((30.0, 10.0), 6.0, None);
+        let field = super::generate(&path, (40, 20), 3.0, SdfKind::MultiChannel).unwrap();
+        let pixmap = field.pixmap();
+        let pixels = pixmap.pixels::<[u8; 4]>().unwrap();
+        for y in 0..20 {
+            for x in 0..40 {
+                let [r, g, b, a] = pixels[y * pixmap.row_bytes_as_pixels() + x];
+                let mut rgb = [r, g, b];
+                rgb.sort();
+                let inside = path.contains((x as f32 + 0.5, y as f32 + 0.5));
+                assert_eq!(rgb[1] > 127, inside, "median at {x}, {y}");
+                assert_eq!(a > 127, inside, "alpha at {x}, {y}");
+            }
+        }
+    }
+}
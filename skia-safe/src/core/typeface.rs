@@ -5,11 +5,16 @@ use skia_bindings::{self as sb, SkRefCntBase, SkTypeface, SkTypeface_LocalizedSt
 use crate::{
     font_arguments,
     font_parameters::VariationAxis,
+    graphics,
     interop::{self, NativeStreamBase, RustStream, RustWStream, StreamAsset},
     prelude::*,
     Data, EncodedText, FontArguments, FontMgr, FontStyle, FourByteTag, GlyphId, Rect, Unichar,
 };
 
+mod open_type;
+
+pub use open_type::*;
+
 pub type TypefaceId = skia_bindings::SkTypefaceID;
 pub type FontTableTag = skia_bindings::SkFontTableTag;
 
@@ -126,6 +131,7 @@ impl Typeface {
         mut data: impl io::Read,
         last_resort_mgr: impl Into<Option<FontMgr>>,
     ) -> Option<Typeface> {
+        graphics::init_open_type_svg_decoder();
         let mut stream = RustStream::new(&mut data);
         Typeface::from_ptr(unsafe {
             sb::C_SkTypeface_MakeDeserialize(
@@ -267,6 +273,29 @@ impl Typeface {
     }
 
     // TODO: Register()
+
+    /// Creates a font that contains only the glyphs in `glyphs` and `.notdef` by using the
+    /// HarfBuzz subsetter of the PDF backend. Glyph ids are retained, so glyph runs shaped with
+    /// this typeface can be rendered with the subset font.
+    ///
+    /// Returns `None` if the font can not be subsetted, or if the embedding permissions in its
+    /// `OS/2.fsType` field restrict the license, forbid subsetting, or allow bitmap embedding
+    /// only.
+    #[cfg(feature = "textlayout")]
+    pub fn subset(&self, glyphs: &[GlyphId]) -> Option<Data> {
+        Data::from_ptr(unsafe {
+            sb::C_SkTypeface_subset(self.native(), glyphs.as_ptr(), glyphs.len())
+        })
+    }
+
+    /// Creates a font that contains only the glyphs that are mapped to `unichars`, see
+    /// [`Self::subset()`].
+    #[cfg(feature = "textlayout")]
+    pub fn subset_unichars(&self, unichars: &[Unichar]) -> Option<Data> {
+        let mut glyphs = vec![0; unichars.len()];
+        self.unichars_to_glyphs(unichars, &mut glyphs);
+        self.subset(&glyphs)
+    }
 }
 
 pub type LocalizedStringsIter = RefHandle<SkTypeface_LocalizedStrings>;
@@ -349,6 +378,19 @@ mod tests {
         assert!(any);
     }
 
+    #[test]
+    #[cfg(feature = "textlayout")]
+    fn subset_default_typeface() {
+        let tf = FontMgr::new()
+            .legacy_make_typeface(None, FontStyle::normal())
+            .unwrap();
+        let unichars: Vec<crate::Unichar> = "Hello".chars().map(|c| c as _).collect();
+        let subset = tf.subset_unichars(&unichars).unwrap();
+        let (data, _ttc_index) = tf.to_font_data().unwrap();
+        assert!(!subset.is_empty());
+        assert!(subset.len() < data.len());
+    }
+
     #[test]
     fn get_font_data_of_default() {
         let tf = FontMgr::new()
@@ -0,0 +1,391 @@
+use crate::{font_arguments::variation_position::Coordinate, Color, FourByteTag, Typeface};
+
+/// A named instance of a variable font from its `fvar` table, for example "Bold Condensed".
+#[derive(Clone, PartialEq, Debug)]
+pub struct NamedInstance {
+    /// The subfamily name of the instance, empty if the `name` table has none.
+    pub name: String,
+    pub post_script_name: Option<String>,
+    /// The position of the instance on the variation axes, which can be passed to
+    /// [`crate::FontArguments::set_variation_design_position()`].
+    pub coordinates: Vec<Coordinate>,
+}
+
+/// A color palette from the `CPAL` table.
+///
+/// The index of a palette in [`Typeface::color_palettes()`] selects it in
+/// [`crate::font_arguments::Palette`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ColorPalette {
+    pub colors: Vec<Color>,
+    pub name: Option<String>,
+    pub usable_with_light_background: bool,
+    pub usable_with_dark_background: bool,
+}
+
+const DEFAULT_SCRIPT: FourByteTag = FourByteTag::from_chars('D', 'F', 'L', 'T');
+const NO_NAME: u16 = 0xffff;
+
+impl Typeface {
+    /// Returns the scripts the `GSUB` and `GPOS` tables have features for, for example `latn`
+    /// or `arab`.
+    pub fn supported_scripts(&self) -> Vec<FourByteTag> {
+        let mut scripts = Vec::new();
+        for table in self.layout_tables() {
+            let table = Reader(&table);
+            let Some(script_list) = table.offset16(4) else {
+                continue;
+            };
+            for i in 0..script_list.u16(0).unwrap_or(0) as usize {
+                if let Some(tag) = script_list.tag(2 + i * 6) {
+                    scripts.push(tag);
+                }
+            }
+        }
+        sort_tags(scripts)
+    }
+
+    /// Returns the features of the `GSUB` and `GPOS` tables for `script` and `language`, for
+    /// example `liga`, `smcp` or `ss01`.
+    ///
+    /// If the font has no features for `script`, the ones of the default script are returned. If
+    /// `language` is `None` or the script has no features specific to it, the features of the
+    /// script's default language are returned.
+    pub fn layout_features(
+        &self,
+        script: FourByteTag,
+        language: impl Into<Option<FourByteTag>>,
+    ) -> Vec<FourByteTag> {
+        let language = language.into();
+        let mut features = Vec::new();
+        for table in self.layout_tables() {
+            features.extend(table_features(Reader(&table), script, language).unwrap_or_default());
+        }
+        sort_tags(features)
+    }
+
+    /// Returns the named instances of a variable font, in the order of its `fvar` table.
+    pub fn named_instances(&self) -> Vec<NamedInstance> {
+        self.parse_named_instances().unwrap_or_default()
+    }
+
+    /// Returns the color palettes of a color font, in the order of its `CPAL` table.
+    pub fn color_palettes(&self) -> Vec<ColorPalette> {
+        self.parse_color_palettes().unwrap_or_default()
+    }
+
+    fn layout_tables(&self) -> Vec<Vec<u8>> {
+        [('G', 'S', 'U', 'B'), ('G', 'P', 'O', 'S')]
+            .into_iter()
+            .filter_map(|tag| self.open_type_table(tag.into()))
+            .collect()
+    }
+
+    fn open_type_table(&self, tag: FourByteTag) -> Option<Vec<u8>> {
+        self.copy_table_data(*tag)
+            .map(|data| data.as_bytes().to_vec())
+    }
+
+    /// Returns the string with the id `name_id` of the `name` table, preferring US English.
+    fn name_string(&self, name_id: u16) -> Option<String> {
+        let table = self.open_type_table(('n', 'a', 'm', 'e').into())?;
+        let table = Reader(&table);
+        let count = table.u16(2)? as usize;
+        let strings = table.u16(4)? as usize;
+
+        let mut best: Option<(u32, String)> = None;
+        for i in 0..count {
+            let record = 6 + i * 12;
+            if table.u16(record + 6)? != name_id {
+                continue;
+            }
+            let platform = table.u16(record)?;
+            let encoding = table.u16(record + 2)?;
+            let language = table.u16(record + 4)?;
+            let length = table.u16(record + 8)? as usize;
+            let offset = strings + table.u16(record + 10)? as usize;
+            let bytes = table.0.get(offset..offset + length)?;
+
+            let (rank, string) = match (platform, encoding) {
+                (3, 1 | 10) | (0, _) => {
+                    let rank = if platform == 3 && language == 0x409 {
+                        0
+                    } else {
+                        1
+                    };
+                    (rank, utf16_be(bytes))
+                }
+                // Mac Roman, which agrees with Latin-1 in the ASCII range that names mostly use.
+                (1, 0) => (2, bytes.iter().map(|b| *b as char).collect()),
+                _ => continue,
+            };
+            if best.as_ref().map_or(true, |(best, _)| rank < *best) {
+                best = Some((rank, string));
+            }
+        }
+        best.map(|(_, string)| string)
+    }
+
+    fn parse_named_instances(&self) -> Option<Vec<NamedInstance>> {
+        let table = self.open_type_table(('f', 'v', 'a', 'r').into())?;
+        let table = Reader(&table);
+        let axes_offset = table.u16(4)? as usize;
+        let axis_count = table.u16(8)? as usize;
+        let axis_size = table.u16(10)? as usize;
+        let instance_count = table.u16(12)? as usize;
+        let instance_size = table.u16(14)? as usize;
+
+        let axes: Vec<FourByteTag> = (0..axis_count)
+            .map(|i| table.tag(axes_offset + i * axis_size))
+            .collect::<Option<_>>()?;
+        let has_post_script_name = instance_size >= axis_count * 4 + 6;
+
+        let instances = axes_offset + axis_count * axis_size;
+        let mut result = Vec::with_capacity(instance_count);
+        for i in 0..instance_count {
+            let instance = instances + i * instance_size;
+            let coordinates = axes
+                .iter()
+                .enumerate()
+                .map(|(a, axis)| {
+                    Some(Coordinate {
+                        axis: *axis,
+                        value: table.fixed(instance + 4 + a * 4)?,
+                    })
+                })
+                .collect::<Option<_>>()?;
+            let post_script_name = if has_post_script_name {
+                Some(table.u16(instance + 4 + axis_count * 4)?)
+                    .filter(|id| *id != NO_NAME)
+                    .and_then(|id| self.name_string(id))
+            } else {
+                None
+            };
+            result.push(NamedInstance {
+                name: self.name_string(table.u16(instance)?).unwrap_or_default(),
+                post_script_name,
+                coordinates,
+            });
+        }
+        Some(result)
+    }
+
+    fn parse_color_palettes(&self) -> Option<Vec<ColorPalette>> {
+        let table = self.open_type_table(('C', 'P', 'A', 'L').into())?;
+        let table = Reader(&table);
+        let version = table.u16(0)?;
+        let entries = table.u16(2)? as usize;
+        let count = table.u16(4)? as usize;
+        let records = table.u32(8)? as usize;
+        let indices = 12;
+
+        // Version 1 adds palette types and labels after the color record indices.
+        let offset_at = |index: usize| {
+            Some(version)
+                .filter(|v| *v >= 1)
+                .and_then(|_| table.u32(indices + count * 2 + index * 4))
+                .filter(|offset| *offset != 0)
+                .map(|offset| offset as usize)
+        };
+        let types = offset_at(0);
+        let labels = offset_at(1);
+
+        let mut palettes = Vec::with_capacity(count);
+        for i in 0..count {
+            let first = table.u16(indices + i * 2)? as usize;
+            let colors = (0..entries)
+                .map(|e| {
+                    let record = records + (first + e) * 4;
+                    let [b, g, r, a] = table.0.get(record..record + 4)?.try_into().ok()?;
+                    Some(Color::from_argb(a, r, g, b))
+                })
+                .collect::<Option<_>>()?;
+            let flags = types
+                .and_then(|types| table.u32(types + i * 4))
+                .unwrap_or(0);
+            let name = labels
+                .and_then(|labels| table.u16(labels + i * 2))
+                .filter(|id| *id != NO_NAME)
+                .and_then(|id| self.name_string(id));
+            palettes.push(ColorPalette {
+                colors,
+                name,
+                usable_with_light_background: flags & 1 != 0,
+                usable_with_dark_background: flags & 2 != 0,
+            });
+        }
+        Some(palettes)
+    }
+}
+
+/// Returns the feature tags of a `GSUB` or `GPOS` table for `script` and `language`.
+fn table_features(
+    table: Reader,
+    script: FourByteTag,
+    language: Option<FourByteTag>,
+) -> Option<Vec<FourByteTag>> {
+    let script_list = table.offset16(4)?;
+    let feature_list = table.offset16(6)?;
+
+    let find_script = |tag: FourByteTag| {
+        (0..script_list.u16(0)? as usize)
+            .find(|i| script_list.tag(2 + i * 6) == Some(tag))
+            .and_then(|i| script_list.offset16(2 + i * 6 + 4))
+    };
+    let script_table = find_script(script).or_else(|| find_script(DEFAULT_SCRIPT))?;
+
+    let lang_sys = language
+        .and_then(|language| {
+            (0..script_table.u16(2)? as usize)
+                .find(|i| script_table.tag(4 + i * 6) == Some(language))
+                .and_then(|i| script_table.offset16(4 + i * 6 + 4))
+        })
+        .or_else(|| script_table.offset16(0))?;
+
+    let required = lang_sys.u16(2)?;
+    let count = lang_sys.u16(4)? as usize;
+    let indices = (0..count)
+        .filter_map(|i| lang_sys.u16(6 + i * 2))
+        .chain(Some(required).filter(|r| *r != 0xffff));
+    Some(
+        indices
+            .filter_map(|index| feature_list.tag(2 + index as usize * 6))
+            .collect(),
+    )
+}
+
+fn sort_tags(mut tags: Vec<FourByteTag>) -> Vec<FourByteTag> {
+    tags.sort_by_key(|tag| **tag);
+    tags.dedup();
+    tags
+}
+
+fn utf16_be(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Reads big endian values from a table.
+#[derive(Copy, Clone)]
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn u16(&self, offset: usize) -> Option<u16> {
+        Some(u16::from_be_bytes(
+            self.0.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        Some(u32::from_be_bytes(
+            self.0.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    }
+
+    fn tag(&self, offset: usize) -> Option<FourByteTag> {
+        self.u32(offset).map(FourByteTag::new)
+    }
+
+    /// Reads a 16.16 fixed point number.
+    fn fixed(&self, offset: usize) -> Option<f32> {
+        Some(self.u32(offset)? as i32 as f32 / 65536.0)
+    }
+
+    /// Returns a reader of the data at the 16-bit offset stored at `offset`, `None` if it is null.
+    fn offset16(&self, offset: usize) -> Option<Reader<'a>> {
+        match self.u16(offset)? as usize {
+            0 => None,
+            at => self.0.get(at..).map(Reader),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{table_features, Reader};
+    use crate::{FontMgr, FontStyle, FourByteTag};
+
+    fn tag(t: &str) -> FourByteTag {
+        let c: Vec<char> = t.chars().collect();
+        FourByteTag::from_chars(c[0], c[1], c[2], c[3])
+    }
+
+    /// A `GSUB` table with the scripts `DFLT` and `latn`, where `latn` has the language `TRK`.
+    fn gsub() -> Vec<u8> {
+        let mut t: Vec<u8> = Vec::new();
+        let push16 = |t: &mut Vec<u8>, v: u16| t.extend(v.to_be_bytes());
+        let push_tag = |t: &mut Vec<u8>, v: &str| t.extend(v.as_bytes());
+        // Header: version 1.0, ScriptList at 10, FeatureList at 52, no LookupList.
+        for v in [1, 0, 10, 52, 0] {
+            push16(&mut t, v);
+        }
+        // ScriptList at 10, the offsets are relative to it.
+        push16(&mut t, 2);
+        push_tag(&mut t, "DFLT");
+        push16(&mut t, 14);
+        push_tag(&mut t, "latn");
+        push16(&mut t, 26);
+        // DFLT Script at 24 with the default LangSys at 28 that has feature 0.
+        for v in [4, 0, 0, 0xffff, 1, 0] {
+            push16(&mut t, v);
+        }
+        // latn Script at 36 without a default LangSys, but with the one of TRK at 46.
+        assert_eq!(t.len(), 36);
+        push16(&mut t, 0);
+        push16(&mut t, 1);
+        push_tag(&mut t, "TRK ");
+        push16(&mut t, 10);
+        // TRK LangSys at 46 with the required feature 1 only.
+        for v in [0, 1, 0] {
+            push16(&mut t, v);
+        }
+        // FeatureList at 52.
+        assert_eq!(t.len(), 52);
+        push16(&mut t, 2);
+        push_tag(&mut t, "liga");
+        push16(&mut t, 0);
+        push_tag(&mut t, "locl");
+        push16(&mut t, 0);
+        t
+    }
+
+    #[test]
+    fn features_of_scripts_and_languages() {
+        let gsub = gsub();
+        let features = |script: &str, language: Option<&str>| {
+            table_features(Reader(&gsub), tag(script), language.map(tag)).unwrap_or_default()
+        };
+        assert_eq!(features("DFLT", None), [tag("liga")]);
+        // Scripts without features fall back to the default script.
+        assert_eq!(features("arab", None), [tag("liga")]);
+        // latn has no default language.
+        assert!(features("latn", None).is_empty());
+        assert!(features("latn", Some("DEU ")).is_empty());
+        assert_eq!(features("latn", Some("TRK ")), [tag("locl")]);
+    }
+
+    #[test]
+    fn tables_of_the_default_typeface() {
+        let tf = FontMgr::new()
+            .legacy_make_typeface(None, FontStyle::normal())
+            .unwrap();
+        let scripts = tf.supported_scripts();
+        let mut sorted = scripts.clone();
+        sorted.sort_by_key(|tag| **tag);
+        sorted.dedup();
+        assert_eq!(scripts, sorted);
+        assert_eq!(
+            tf.layout_features(tag("zzzz"), None),
+            tf.layout_features(tag("DFLT"), None)
+        );
+        for instance in tf.named_instances() {
+            assert!(!instance.coordinates.is_empty());
+        }
+        for palette in tf.color_palettes() {
+            assert!(!palette.colors.is_empty());
+        }
+    }
+}
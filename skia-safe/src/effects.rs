@@ -22,4 +22,4 @@ pub use _1d_path_effect::*;
 pub use _2d_path_effect::*;
 pub use color_matrix::ColorMatrix;
 pub use high_contrast_filter::{high_contrast_config, HighContrastConfig};
-pub use runtime_effect::RuntimeEffect;
+pub use runtime_effect::{RuntimeEffect, RuntimeEffectBuilder};
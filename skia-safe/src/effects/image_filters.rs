@@ -3,9 +3,10 @@ use std::ptr;
 use skia_bindings::{self as sb, SkImageFilter, SkRect};
 
 use crate::{
-    prelude::*, scalar, Blender, Color, ColorChannel, ColorFilter, CubicResampler, IPoint, IRect,
-    ISize, Image, ImageFilter, Matrix, Picture, Point3, Rect, SamplingOptions, Shader, TileMode,
-    Vector,
+    prelude::*,
+    runtime_effect::{ChildType, RuntimeShaderBuilder},
+    scalar, Blender, Color, ColorChannel, ColorFilter, CubicResampler, IPoint, IRect, ISize, Image,
+    ImageFilter, Matrix, Picture, Point3, Rect, SamplingOptions, Shader, TileMode, Vector,
 };
 
 /// This is just a convenience type to allow passing [`IRect`]s, [`Rect`]s, and optional references
@@ -481,7 +482,65 @@ pub fn picture<'a>(
     })
 }
 
-// TODO: RuntimeShader
+/// Create a filter that fills the output with the per-pixel evaluation of the shader of
+/// `builder`, with the child shaders `child_names` set to the outputs of `inputs`.
+///
+/// The shader is defined in the image filter's local coordinate system, so it is affected by the
+/// canvas' transform. A `None` input is the source image of the filter. An empty child name
+/// refers to the only child of an effect that has exactly one.
+///
+/// Returns `None` if the names and inputs do not match up, or if a name is not a shader child of
+/// the effect.
+pub fn runtime_shader(
+    builder: &RuntimeShaderBuilder,
+    child_names: &[impl AsRef<str>],
+    inputs: impl IntoIterator<Item = Option<ImageFilter>>,
+) -> Option<ImageFilter> {
+    runtime_shader_with_sample_radius(builder, 0.0, child_names, inputs)
+}
+
+/// Like [`runtime_shader()`], but the shader may sample its children up to `max_sample_radius`
+/// pixels away from the current position, so the inputs are evaluated on a larger area.
+pub fn runtime_shader_with_sample_radius(
+    builder: &RuntimeShaderBuilder,
+    max_sample_radius: scalar,
+    child_names: &[impl AsRef<str>],
+    inputs: impl IntoIterator<Item = Option<ImageFilter>>,
+) -> Option<ImageFilter> {
+    let effect = builder.effect();
+    let declarations = effect.children();
+    let child_indices = child_names
+        .iter()
+        .map(|name| {
+            let child = match name.as_ref() {
+                "" if declarations.len() == 1 => &declarations[0],
+                name => effect.find_child(name)?,
+            };
+            (child.ty() == ChildType::Shader).then(|| child.index())
+        })
+        .collect::<Option<Vec<usize>>>()?;
+    let inputs: Vec<Option<ImageFilter>> = inputs.into_iter().collect();
+    if inputs.len() != child_indices.len() {
+        return None;
+    }
+    let inputs: Vec<*mut SkImageFilter> =
+        inputs.into_iter().map(|f| f.into_ptr_or_null()).collect();
+
+    let uniforms = builder.uniforms_data();
+    let children = builder.native_children();
+    ImageFilter::from_ptr(unsafe {
+        sb::C_SkImageFilters_RuntimeShader(
+            effect.native(),
+            uniforms.native(),
+            children.as_ptr() as *const sb::SkRuntimeEffect_ChildPtr,
+            children.len(),
+            max_sample_radius,
+            child_indices.as_ptr(),
+            inputs.as_ptr(),
+            inputs.len(),
+        )
+    })
+}
 
 pub use skia_bindings::SkImageFilters_Dither as Dither;
 variant_name!(Dither::Yes);
@@ -1122,7 +1181,10 @@ impl Picture {
 #[cfg(test)]
 mod tests {
     use super::CropRect;
-    use crate::{IRect, Rect};
+    use crate::{
+        image_filters, surfaces, Color, Color4f, IRect, Paint, Rect, RuntimeEffect,
+        RuntimeEffectBuilder,
+    };
 
     fn cr(crop_rect: impl Into<CropRect>) -> CropRect {
         crop_rect.into()
@@ -1156,4 +1218,40 @@ mod tests {
         let cr_by_ref = cr(rect);
         assert_eq!(cr_by_ref, CropRect(Some(rect)));
     }
+
+    #[test]
+    fn runtime_shader_samples_the_source() {
+        let effect = RuntimeEffect::make_for_shader(
+            r#"
+                uniform shader content;
+                uniform half4 tint;
+                half4 main(float2 p) {
+                    return content.eval(p) * tint;
+                }
+            "#,
+            None,
+        )
+        .unwrap();
+        let mut builder = RuntimeEffectBuilder::new(effect);
+        builder
+            .set_uniform("tint", Color4f::new(0.5, 1.0, 1.0, 1.0))
+            .unwrap();
+
+        assert!(image_filters::runtime_shader(&builder, &["unknown"], [None]).is_none());
+        assert!(image_filters::runtime_shader(&builder, &["tint"], [None]).is_none());
+        assert!(image_filters::runtime_shader(&builder, &["content"], []).is_none());
+        let filter = image_filters::runtime_shader(&builder, &[""], [None]).unwrap();
+
+        let mut surface = surfaces::raster_n32_premul((8, 8)).unwrap();
+        surface.canvas().clear(Color::WHITE);
+        let mut paint = Paint::default();
+        paint.set_color(Color::RED).set_image_filter(filter);
+        surface.canvas().draw_rect(Rect::from_wh(8.0, 8.0), &paint);
+        // Half of the red of the source: neither the background, nor the transparent black an
+        // unbound input or missing uniforms would produce.
+        let pixmap = surface.peek_pixels().unwrap();
+        let color = pixmap.get_color((4, 4));
+        assert!((127..=128).contains(&color.r()), "{color:?}");
+        assert_eq!((color.g(), color.b(), color.a()), (0, 0, 255));
+    }
 }
@@ -9,6 +9,16 @@ use skia_bindings::{
 };
 use std::{fmt, marker::PhantomData, ops::DerefMut, ptr};
 
+mod builder;
+mod debug_trace;
+mod diagnostics;
+mod uniforms;
+
+pub use builder::*;
+pub use debug_trace::*;
+pub use diagnostics::*;
+pub use uniforms::*;
+
 pub type Uniform = Handle<SkRuntimeEffect_Uniform>;
 unsafe_send_sync!(Uniform);
 
@@ -160,6 +170,7 @@ impl fmt::Debug for RuntimeEffect {
 }
 
 impl RuntimeEffect {
+    /// Compiles `sksl`. The error is the text of the SkSL compiler, see [`parse_diagnostics()`].
     pub fn make_for_color_filer<'a>(
         sksl: impl AsRef<str>,
         options: impl Into<Option<&'a Options>>,
@@ -177,6 +188,7 @@ impl RuntimeEffect {
         .ok_or_else(|| error.to_string())
     }
 
+    /// Compiles `sksl`. The error is the text of the SkSL compiler, see [`parse_diagnostics()`].
     pub fn make_for_shader<'a>(
         sksl: impl AsRef<str>,
         options: impl Into<Option<&'a Options>>,
@@ -190,6 +202,7 @@ impl RuntimeEffect {
         .ok_or_else(|| error.to_string())
     }
 
+    /// Compiles `sksl`. The error is the text of the SkSL compiler, see [`parse_diagnostics()`].
     pub fn make_for_blender<'a>(
         sksl: impl AsRef<str>,
         options: impl Into<Option<&'a Options>>,
@@ -274,8 +287,6 @@ impl RuntimeEffect {
         })
     }
 
-    // TODO: wrap MakeTraced
-
     pub fn source(&self) -> &str {
         let mut len = 0;
         let ptr = unsafe { sb::C_SkRuntimeEffect_source(self.native(), &mut len) };
@@ -403,6 +414,3 @@ impl ChildPtr {
         .borrows(self)
     }
 }
-
-// TODO: wrap SkRuntimeEffectBuilder, SkRuntimeShaderBuilder, SkRuntimeColorFilterBuilder,
-// SkRuntimeBlendBuilder
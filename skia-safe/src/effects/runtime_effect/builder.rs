@@ -0,0 +1,553 @@
+use std::{error::Error, fmt, marker::PhantomData, ptr};
+
+use skia_bindings as sb;
+
+use super::{uniform, ChildPtr, ChildType, RuntimeEffect, Uniforms};
+use crate::{
+    prelude::*, scalar, Blender, Color4f, ColorFilter, Data, Matrix, Point, Point3, Shader, M44,
+    V2, V3, V4,
+};
+
+/// A value that can be assigned to a uniform of a [`RuntimeEffect`] with
+/// [`RuntimeEffectBuilder::set_uniform()`].
+///
+/// Single values are assigned to uniforms that are not arrays, slices and vectors of values to
+/// uniform arrays of the same length. Note that `[f32; 2]` to `[f32; 4]` are SkSL vectors, so a
+/// `float` array is set from a slice, for example `[1.0, 2.0].as_slice()`. Matrices are stored in
+/// the column-major order SkSL uses.
+pub trait UniformValue {
+    /// The type of the uniform, or of the elements of the uniform array.
+    const TYPE: uniform::Type;
+
+    /// The number of elements, 1 for a value that is not an array.
+    fn count(&self) -> usize {
+        1
+    }
+
+    /// Appends the uniform data of the value to `data`.
+    fn write(&self, data: &mut Vec<u8>);
+}
+
+fn write_scalars(data: &mut Vec<u8>, scalars: &[scalar]) {
+    data.extend(scalars.iter().flat_map(|s| s.to_ne_bytes()));
+}
+
+fn write_ints(data: &mut Vec<u8>, ints: &[i32]) {
+    data.extend(ints.iter().flat_map(|i| i.to_ne_bytes()));
+}
+
+macro_rules! uniform_value {
+    ($ty:ty, $uniform_type:ident, $write:ident, |$v:ident| $elements:expr) => {
+        impl UniformValue for $ty {
+            const TYPE: uniform::Type = uniform::Type::$uniform_type;
+
+            fn write(&self, data: &mut Vec<u8>) {
+                let $v = self;
+                $write(data, &$elements)
+            }
+        }
+    };
+}
+
+uniform_value!(scalar, Float, write_scalars, |v| [*v]);
+uniform_value!([scalar; 2], Float2, write_scalars, |v| *v);
+uniform_value!([scalar; 3], Float3, write_scalars, |v| *v);
+uniform_value!([scalar; 4], Float4, write_scalars, |v| *v);
+uniform_value!(Point, Float2, write_scalars, |v| [v.x, v.y]);
+uniform_value!(V2, Float2, write_scalars, |v| [v.x, v.y]);
+uniform_value!(Point3, Float3, write_scalars, |v| [v.x, v.y, v.z]);
+uniform_value!(V3, Float3, write_scalars, |v| [v.x, v.y, v.z]);
+uniform_value!(V4, Float4, write_scalars, |v| [v.x, v.y, v.z, v.w]);
+uniform_value!(Color4f, Float4, write_scalars, |v| [v.r, v.g, v.b, v.a]);
+uniform_value!([[scalar; 2]; 2], Float2x2, write_scalars, |v| v.concat());
+uniform_value!([[scalar; 3]; 3], Float3x3, write_scalars, |v| v.concat());
+uniform_value!([[scalar; 4]; 4], Float4x4, write_scalars, |v| v.concat());
+uniform_value!(Matrix, Float3x3, write_scalars, |v| {
+    let mut m = [0.0; 9];
+    v.get_9(&mut m);
+    [m[0], m[3], m[6], m[1], m[4], m[7], m[2], m[5], m[8]]
+});
+uniform_value!(M44, Float4x4, write_scalars, |v| {
+    let mut m = [0.0; 16];
+    v.get_col_major(&mut m);
+    m
+});
+uniform_value!(i32, Int, write_ints, |v| [*v]);
+uniform_value!([i32; 2], Int2, write_ints, |v| *v);
+uniform_value!([i32; 3], Int3, write_ints, |v| *v);
+uniform_value!([i32; 4], Int4, write_ints, |v| *v);
+
+impl<T: UniformValue> UniformValue for [T] {
+    const TYPE: uniform::Type = T::TYPE;
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn write(&self, data: &mut Vec<u8>) {
+        self.iter().for_each(|v| v.write(data))
+    }
+}
+
+impl<T: UniformValue> UniformValue for Vec<T> {
+    const TYPE: uniform::Type = T::TYPE;
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn write(&self, data: &mut Vec<u8>) {
+        self.as_slice().write(data)
+    }
+}
+
+impl<T: UniformValue + ?Sized> UniformValue for &T {
+    const TYPE: uniform::Type = T::TYPE;
+
+    fn count(&self) -> usize {
+        (**self).count()
+    }
+
+    fn write(&self, data: &mut Vec<u8>) {
+        (**self).write(data)
+    }
+}
+
+/// The reason [`RuntimeEffectBuilder`] rejected a uniform or a child, or [`Uniforms::check()`]
+/// rejected an effect.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BuilderError {
+    UnknownUniform(String),
+    /// A uniform of the effect is missing in a [`Uniforms`] struct.
+    MissingUniform(String),
+    /// The uniform is at index `expected` in the effect, but at index `actual` in a [`Uniforms`]
+    /// struct.
+    UniformOrder {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+    UniformType {
+        name: String,
+        expected: uniform::Type,
+        actual: uniform::Type,
+    },
+    /// The number of elements does not match the length of the uniform array, or 1 if the uniform
+    /// is not an array.
+    UniformCount {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+    UniformSize {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+    UnknownChild(String),
+    ChildType {
+        name: String,
+        expected: ChildType,
+        actual: ChildType,
+    },
+}
+
+impl fmt::Display for BuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use BuilderError::*;
+        match self {
+            UnknownUniform(name) => write!(f, "The effect has no uniform {name:?}"),
+            MissingUniform(name) => write!(f, "Uniform {name:?} of the effect is missing"),
+            UniformOrder {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Uniform {name:?} is declared at index {expected}, but the field is at {actual}"
+            ),
+            UniformType {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Uniform {name:?} is of type {expected:?}, but the value is of type {actual:?}"
+            ),
+            UniformCount {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Uniform {name:?} has {expected} element(s), but the value has {actual}"
+            ),
+            UniformSize {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Uniform {name:?} has {expected} bytes, but the value has {actual}"
+            ),
+            UnknownChild(name) => write!(f, "The effect has no child {name:?}"),
+            ChildType {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Child {name:?} is a {expected:?}, but a {actual:?} was set"
+            ),
+        }
+    }
+}
+
+impl Error for BuilderError {}
+
+/// Sets the uniforms and children of a [`RuntimeEffect`] by name and creates shaders, color
+/// filters and blenders from it.
+///
+/// In contrast to packing the uniform data by hand, the values are checked against the
+/// declarations of the effect, so reordering the uniforms in the SkSL code does not break the
+/// effect. Uniforms that are not set are zero, children that are not set are `null` in SkSL.
+#[derive(Clone, Debug)]
+pub struct RuntimeEffectBuilder {
+    effect: RuntimeEffect,
+    uniforms: Vec<u8>,
+    children: Vec<Option<ChildPtr>>,
+}
+
+/// Skia's name of the builder for shaders.
+pub type RuntimeShaderBuilder = RuntimeEffectBuilder;
+/// Skia's name of the builder for color filters.
+pub type RuntimeColorFilterBuilder = RuntimeEffectBuilder;
+/// Skia's name of the builder for blenders.
+pub type RuntimeBlendBuilder = RuntimeEffectBuilder;
+
+impl RuntimeEffectBuilder {
+    pub fn new(effect: impl Into<RuntimeEffect>) -> Self {
+        let effect = effect.into();
+        let uniforms = vec![0; effect.uniform_size()];
+        let children = vec![None; effect.children().len()];
+        Self {
+            effect,
+            uniforms,
+            children,
+        }
+    }
+
+    pub fn effect(&self) -> &RuntimeEffect {
+        &self.effect
+    }
+
+    /// The uniform data in the layout of [`RuntimeEffect::uniforms()`].
+    pub fn uniforms(&self) -> &[u8] {
+        &self.uniforms
+    }
+
+    pub fn uniforms_data(&self) -> Data {
+        Data::new_copy(&self.uniforms)
+    }
+
+    /// The children in the order of [`RuntimeEffect::children()`].
+    pub fn children(&self) -> &[Option<ChildPtr>] {
+        &self.children
+    }
+
+    /// Sets the uniform `name` to `value`.
+    ///
+    /// Fails if the effect has no such uniform, or if its type, its number of elements, or its
+    /// size does not match the value.
+    pub fn set_uniform<V: UniformValue>(
+        &mut self,
+        name: impl AsRef<str>,
+        value: V,
+    ) -> Result<&mut Self, BuilderError> {
+        let name = name.as_ref();
+        let uniform = self
+            .effect
+            .find_uniform(name)
+            .ok_or_else(|| BuilderError::UnknownUniform(name.into()))?;
+        if uniform.ty() != V::TYPE {
+            return Err(BuilderError::UniformType {
+                name: name.into(),
+                expected: uniform.ty(),
+                actual: V::TYPE,
+            });
+        }
+        let count: usize = uniform.count().try_into().unwrap();
+        if value.count() != count {
+            return Err(BuilderError::UniformCount {
+                name: name.into(),
+                expected: count,
+                actual: value.count(),
+            });
+        }
+        let mut data = Vec::with_capacity(uniform.size_in_bytes());
+        value.write(&mut data);
+        if data.len() != uniform.size_in_bytes() {
+            return Err(BuilderError::UniformSize {
+                name: name.into(),
+                expected: uniform.size_in_bytes(),
+                actual: data.len(),
+            });
+        }
+        let offset = uniform.offset();
+        self.uniforms[offset..offset + data.len()].copy_from_slice(&data);
+        Ok(self)
+    }
+
+    /// Sets all uniforms from a [`Uniforms`] struct.
+    ///
+    /// Fails if the fields do not match the uniforms of the effect, see [`Uniforms::check()`], or
+    /// if the written data does not match the size of the uniforms of the effect.
+    pub fn set_uniforms<U: Uniforms>(&mut self, uniforms: &U) -> Result<&mut Self, BuilderError> {
+        U::check(&self.effect)?;
+        let expected = self.effect.uniform_size();
+        let mut data = Vec::with_capacity(expected);
+        uniforms.write(&mut data);
+        if data.len() != expected {
+            return Err(BuilderError::UniformSize {
+                name: std::any::type_name::<U>().into(),
+                expected,
+                actual: data.len(),
+            });
+        }
+        self.uniforms = data;
+        Ok(self)
+    }
+
+    /// Sets the child `name` to a shader, color filter, or blender.
+    ///
+    /// Fails if the effect has no such child, or if the child is of a different type.
+    pub fn set_child(
+        &mut self,
+        name: impl AsRef<str>,
+        child: impl Into<ChildPtr>,
+    ) -> Result<&mut Self, BuilderError> {
+        let name = name.as_ref();
+        let child = child.into();
+        let declaration = self
+            .effect
+            .find_child(name)
+            .ok_or_else(|| BuilderError::UnknownChild(name.into()))?;
+        if declaration.ty() != child.ty() {
+            return Err(BuilderError::ChildType {
+                name: name.into(),
+                expected: declaration.ty(),
+                actual: child.ty(),
+            });
+        }
+        self.children[declaration.index()] = Some(child);
+        Ok(self)
+    }
+
+    /// Resets the child `name` to `null`.
+    pub fn clear_child(&mut self, name: impl AsRef<str>) -> Result<&mut Self, BuilderError> {
+        let name = name.as_ref();
+        let declaration = self
+            .effect
+            .find_child(name)
+            .ok_or_else(|| BuilderError::UnknownChild(name.into()))?;
+        self.children[declaration.index()] = None;
+        Ok(self)
+    }
+
+    pub fn make_shader<'a>(&self, local_matrix: impl Into<Option<&'a Matrix>>) -> Option<Shader> {
+        let mut children = self.native_children();
+        Shader::from_ptr(unsafe {
+            sb::C_SkRuntimeEffect_makeShader(
+                self.effect.native(),
+                self.uniforms_data().into_ptr(),
+                children_ptr(&mut children),
+                children.len(),
+                local_matrix.into().native_ptr_or_null(),
+            )
+        })
+    }
+
+    pub fn make_color_filter(&self) -> Option<ColorFilter> {
+        let mut children = self.native_children();
+        ColorFilter::from_ptr(unsafe {
+            sb::C_SkRuntimeEffect_makeColorFilter(
+                self.effect.native(),
+                self.uniforms_data().into_ptr(),
+                children_ptr(&mut children),
+                children.len(),
+            )
+        })
+    }
+
+    pub fn make_blender(&self) -> Option<Blender> {
+        let mut children = self.native_children();
+        Blender::from_ptr(unsafe {
+            sb::C_SkRuntimeEffect_makeBlender(
+                self.effect.native(),
+                self.uniforms_data().into_ptr(),
+                children_ptr(&mut children),
+                children.len(),
+            )
+        })
+    }
+
+    pub(crate) fn native_children(&self) -> Vec<Borrows<sb::SkRuntimeEffect_ChildPtr>> {
+        self.children
+            .iter()
+            .map(|child| match child {
+                Some(child) => child.native(),
+                None => sb::SkRuntimeEffect_ChildPtr {
+                    fChild: sb::sk_sp {
+                        fPtr: ptr::null_mut(),
+                        _phantom_0: PhantomData,
+                    },
+                }
+                .borrows(self),
+            })
+            .collect()
+    }
+}
+
+fn children_ptr(
+    children: &mut [Borrows<sb::SkRuntimeEffect_ChildPtr>],
+) -> *mut sb::SkRuntimeEffect_ChildPtr {
+    children
+        .first_mut()
+        .map(|c| &mut **c as *mut _)
+        .unwrap_or(ptr::null_mut())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BuilderError, RuntimeEffectBuilder};
+    use crate::{
+        runtime_effect::{uniform, UniformField, Uniforms},
+        shaders, Color, Color4f, Matrix, Point, RuntimeEffect,
+    };
+
+    const SKSL: &str = r#"
+        uniform shader image;
+        uniform float2 offset;
+        layout(color) uniform half4 tint;
+        uniform float weights[3];
+        uniform float3x3 transform;
+        uniform int steps;
+
+        half4 main(float2 p) {
+            float3 q = transform * float3(p + offset, 1);
+            half4 c = image.eval(q.xy);
+            for (int i = 0; i < 3; i++) {
+                c *= weights[i];
+            }
+            return c * tint * half(steps);
+        }
+    "#;
+
+    #[test]
+    fn uniforms_are_written_at_their_offsets() {
+        let effect = RuntimeEffect::make_for_shader(SKSL, None).unwrap();
+        let mut builder = RuntimeEffectBuilder::new(effect.clone());
+
+        builder
+            .set_uniform("steps", 2)
+            .unwrap()
+            .set_uniform("offset", Point::new(1.0, 2.0))
+            .unwrap()
+            .set_uniform("tint", Color4f::from(Color::RED))
+            .unwrap()
+            .set_uniform("weights", [0.5, 1.0, 2.0].as_slice())
+            .unwrap()
+            .set_uniform("transform", Matrix::translate((3.0, 4.0)))
+            .unwrap();
+
+        let read = |name: &str, i: usize| {
+            let offset = effect.find_uniform(name).unwrap().offset() + i * 4;
+            let bytes = &builder.uniforms()[offset..offset + 4];
+            f32::from_ne_bytes(bytes.try_into().unwrap())
+        };
+        assert_eq!(read("offset", 1), 2.0);
+        assert_eq!(read("tint", 0), 1.0);
+        assert_eq!(read("weights", 2), 2.0);
+        // column-major, the translation is in the last column
+        assert_eq!(read("transform", 6), 3.0);
+        assert_eq!(read("transform", 7), 4.0);
+        let steps = effect.find_uniform("steps").unwrap().offset();
+        assert_eq!(builder.uniforms()[steps..steps + 4], 2i32.to_ne_bytes());
+
+        // Without the child.
+        assert!(builder.make_shader(None).is_some());
+        builder
+            .set_child("image", shaders::color(Color::BLUE))
+            .unwrap();
+        assert!(builder.make_shader(None).is_some());
+    }
+
+    #[test]
+    fn mismatches_are_rejected() {
+        let effect = RuntimeEffect::make_for_shader(SKSL, None).unwrap();
+        let mut builder = RuntimeEffectBuilder::new(effect);
+
+        assert_eq!(
+            builder.set_uniform("unknown", 1.0).unwrap_err(),
+            BuilderError::UnknownUniform("unknown".into())
+        );
+        assert_eq!(
+            builder.set_uniform("offset", 1.0).unwrap_err(),
+            BuilderError::UniformType {
+                name: "offset".into(),
+                expected: uniform::Type::Float2,
+                actual: uniform::Type::Float,
+            }
+        );
+        assert_eq!(
+            builder.set_uniform("weights", vec![1.0, 2.0]).unwrap_err(),
+            BuilderError::UniformCount {
+                name: "weights".into(),
+                expected: 3,
+                actual: 2,
+            }
+        );
+        assert!(matches!(
+            builder.set_uniform("weights", [1.0, 2.0, 3.0]),
+            Err(BuilderError::UniformType { .. })
+        ));
+        assert!(matches!(
+            builder.set_child("image", crate::color_filters::linear_to_srgb_gamma()),
+            Err(BuilderError::ChildType { .. })
+        ));
+        assert!(builder.uniforms().iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn uniforms_of_the_wrong_size_are_rejected() {
+        struct Short;
+
+        impl Uniforms for Short {
+            const FIELDS: &'static [UniformField] = &[UniformField {
+                name: "a",
+                ty: uniform::Type::Float,
+                count: 1,
+            }];
+
+            fn write(&self, data: &mut Vec<u8>) {
+                data.extend([0; 2]);
+            }
+        }
+
+        let effect = RuntimeEffect::make_for_shader(
+            "uniform float a; half4 main(float2 p) { return half4(a); }",
+            None,
+        )
+        .unwrap();
+        let mut builder = RuntimeEffectBuilder::new(effect);
+        assert!(matches!(
+            builder.set_uniforms(&Short),
+            Err(BuilderError::UniformSize {
+                expected: 4,
+                actual: 2,
+                ..
+            })
+        ));
+        assert_eq!(builder.uniforms(), [0; 4]);
+    }
+}
@@ -0,0 +1,425 @@
+use std::{fmt, ptr};
+
+use skia_bindings::{self as sb, SkRefCntBase, SkSL_DebugTrace};
+
+use super::RuntimeEffect;
+use crate::{interop::DynamicMemoryWStream, prelude::*, IPoint, Shader};
+
+/// The trace of the execution of an SkSL shader for a single pixel, see
+/// [`RuntimeEffect::make_traced()`].
+pub type DebugTrace = RCHandle<SkSL_DebugTrace>;
+
+impl NativeRefCountedBase for SkSL_DebugTrace {
+    type Base = SkRefCntBase;
+}
+
+impl fmt::Debug for DebugTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DebugTrace").finish()
+    }
+}
+
+impl DebugTrace {
+    /// Serializes the trace to the JSON format of Skia's SkSL debugger.
+    pub fn to_json(&self) -> String {
+        let mut stream = DynamicMemoryWStream::new();
+        unsafe { sb::C_SkSL_DebugTrace_writeTrace(self.native(), stream.native_mut().base_mut()) }
+        String::from_utf8_lossy(stream.detach_as_data().as_bytes()).into_owned()
+    }
+
+    /// Returns a human-readable dump of the trace.
+    pub fn dump(&self) -> String {
+        let mut stream = DynamicMemoryWStream::new();
+        unsafe { sb::C_SkSL_DebugTrace_dump(self.native(), stream.native_mut().base_mut()) }
+        String::from_utf8_lossy(stream.detach_as_data().as_bytes()).into_owned()
+    }
+
+    /// Returns the recorded source, variables, functions and events.
+    ///
+    /// The trace is empty until the shader is drawn with the raster backend and covers the traced
+    /// pixel. Returns `None` if the trace can not be read.
+    pub fn trace(&self) -> Option<Trace> {
+        Trace::from_json(&self.to_json())
+    }
+}
+
+impl RuntimeEffect {
+    /// Returns a copy of the runtime effect `shader` that records a [`DebugTrace`] of the
+    /// evaluation of the pixel at the device coordinate `trace_coord`.
+    ///
+    /// Tracing is only supported by the raster backend. Returns `None` if `shader` is not created
+    /// from a [`RuntimeEffect`].
+    pub fn make_traced(
+        shader: impl Into<Shader>,
+        trace_coord: impl Into<IPoint>,
+    ) -> Option<(Shader, DebugTrace)> {
+        let mut debug_trace = ptr::null_mut();
+        let shader = Shader::from_ptr(unsafe {
+            sb::C_SkRuntimeEffect_MakeTraced(
+                shader.into().into_ptr(),
+                trace_coord.into().native(),
+                &mut debug_trace,
+            )
+        });
+        Some((shader?, DebugTrace::from_ptr(debug_trace)?))
+    }
+}
+
+/// A readable form of a [`DebugTrace`].
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Trace {
+    /// The lines of the SkSL source.
+    pub source: Vec<String>,
+    /// The variables, one slot per component.
+    pub slots: Vec<TraceSlot>,
+    /// The names of the functions.
+    pub functions: Vec<String>,
+    /// The events in the order of execution.
+    pub events: Vec<TraceEvent>,
+}
+
+/// A component of a variable, or of the return value of a function.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TraceSlot {
+    pub name: String,
+    pub columns: i32,
+    pub rows: i32,
+    /// The index of the component in the variable.
+    pub component_index: i32,
+    /// The index of the component in the array element the slot belongs to.
+    pub group_index: i32,
+    pub kind: NumberKind,
+    /// The line the variable is declared on.
+    pub line: i32,
+    /// The index of the function the slot holds the return value of.
+    pub return_value_of: Option<usize>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum NumberKind {
+    Float,
+    Signed,
+    Unsigned,
+    Boolean,
+    Nonnumeric,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TraceValue {
+    Float(f32),
+    Signed(i32),
+    Unsigned(u32),
+    Boolean(bool),
+    /// The raw bits of a value of a slot that is not numeric.
+    Bits(i32),
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TraceEvent {
+    /// A 1-based line of the source is executed.
+    Line(usize),
+    /// A slot is assigned a value.
+    Var { slot: usize, value: TraceValue },
+    /// The function at the index is entered.
+    Enter(usize),
+    /// The function at the index is exited.
+    Exit(usize),
+    /// A scope is entered (positive) or exited (negative).
+    Scope(i32),
+}
+
+impl Trace {
+    /// Parses the JSON format of [`DebugTrace::to_json()`].
+    pub fn from_json(json: &str) -> Option<Trace> {
+        let root = json::parse(json)?;
+        let source = root
+            .get("source")?
+            .as_array()?
+            .iter()
+            .map(|line| line.as_str().map(String::from))
+            .collect::<Option<_>>()?;
+        let slots: Vec<TraceSlot> = root
+            .get("slots")?
+            .as_array()?
+            .iter()
+            .map(|slot| {
+                let int = |key| slot.get(key).and_then(|v| v.as_i32());
+                let component_index = int("index")?;
+                Some(TraceSlot {
+                    name: slot.get("name")?.as_str()?.into(),
+                    columns: int("columns")?,
+                    rows: int("rows")?,
+                    component_index,
+                    group_index: int("groupIdx").unwrap_or(component_index),
+                    kind: match int("kind")? {
+                        0 => NumberKind::Float,
+                        1 => NumberKind::Signed,
+                        2 => NumberKind::Unsigned,
+                        3 => NumberKind::Boolean,
+                        _ => NumberKind::Nonnumeric,
+                    },
+                    line: int("line")?,
+                    return_value_of: int("retval").and_then(|i| i.try_into().ok()),
+                })
+            })
+            .collect::<Option<_>>()?;
+        let functions = root
+            .get("functions")?
+            .as_array()?
+            .iter()
+            .map(|f| f.get("name")?.as_str().map(String::from))
+            .collect::<Option<_>>()?;
+        let events = root
+            .get("trace")?
+            .as_array()?
+            .iter()
+            .map(|event| {
+                let event = event.as_array()?;
+                // Trailing zeros are omitted.
+                let data = |i: usize| event.get(i).map_or(Some(0), |v| v.as_i32());
+                let index = |i: usize| data(i).and_then(|d| usize::try_from(d).ok());
+                Some(match data(0)? {
+                    0 => TraceEvent::Line(index(1)?),
+                    1 => {
+                        let slot = index(1)?;
+                        let bits = data(2)?;
+                        let value = match slots.get(slot)?.kind {
+                            NumberKind::Float => TraceValue::Float(f32::from_bits(bits as u32)),
+                            NumberKind::Signed => TraceValue::Signed(bits),
+                            NumberKind::Unsigned => TraceValue::Unsigned(bits as u32),
+                            NumberKind::Boolean => TraceValue::Boolean(bits != 0),
+                            NumberKind::Nonnumeric => TraceValue::Bits(bits),
+                        };
+                        TraceEvent::Var { slot, value }
+                    }
+                    2 => TraceEvent::Enter(index(1)?),
+                    3 => TraceEvent::Exit(index(1)?),
+                    4 => TraceEvent::Scope(data(1)?),
+                    _ => return None,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(Trace {
+            source,
+            slots,
+            functions,
+            events,
+        })
+    }
+
+    /// The 1-based lines in the order they were executed.
+    pub fn executed_lines(&self) -> impl Iterator<Item = usize> + '_ {
+        self.events.iter().filter_map(|event| match event {
+            TraceEvent::Line(line) => Some(*line),
+            _ => None,
+        })
+    }
+
+    /// The values that were assigned to the slots of the variable `name`, in the order of
+    /// execution, with the component index of the slot.
+    pub fn values_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = (i32, TraceValue)> + 'a {
+        self.events.iter().filter_map(move |event| match event {
+            TraceEvent::Var { slot, value } => {
+                let slot = self.slots.get(*slot)?;
+                (slot.name == name).then_some((slot.component_index, *value))
+            }
+            _ => None,
+        })
+    }
+}
+
+/// Just enough JSON to read debug traces.
+mod json {
+    use std::{iter::Peekable, str::Chars};
+
+    #[derive(Clone, PartialEq, Debug)]
+    pub enum Value {
+        Null,
+        Bool(bool),
+        Number(f64),
+        String(String),
+        Array(Vec<Value>),
+        Object(Vec<(String, Value)>),
+    }
+
+    impl Value {
+        pub fn get(&self, key: &str) -> Option<&Value> {
+            match self {
+                Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+                _ => None,
+            }
+        }
+
+        pub fn as_array(&self) -> Option<&[Value]> {
+            match self {
+                Value::Array(values) => Some(values),
+                _ => None,
+            }
+        }
+
+        pub fn as_str(&self) -> Option<&str> {
+            match self {
+                Value::String(s) => Some(s),
+                _ => None,
+            }
+        }
+
+        pub fn as_i32(&self) -> Option<i32> {
+            match self {
+                Value::Number(n) if n.fract() == 0.0 => i32::try_from(*n as i64).ok(),
+                _ => None,
+            }
+        }
+    }
+
+    pub fn parse(json: &str) -> Option<Value> {
+        let mut chars = json.chars().peekable();
+        let value = parse_value(&mut chars)?;
+        skip_whitespace(&mut chars);
+        chars.peek().is_none().then_some(value)
+    }
+
+    fn skip_whitespace(chars: &mut Peekable<Chars>) {
+        while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+    }
+
+    fn parse_value(chars: &mut Peekable<Chars>) -> Option<Value> {
+        skip_whitespace(chars);
+        match *chars.peek()? {
+            '{' => {
+                chars.next();
+                let mut members = Vec::new();
+                skip_whitespace(chars);
+                if chars.next_if_eq(&'}').is_none() {
+                    loop {
+                        skip_whitespace(chars);
+                        let key = parse_string(chars)?;
+                        skip_whitespace(chars);
+                        chars.next_if_eq(&':')?;
+                        members.push((key, parse_value(chars)?));
+                        skip_whitespace(chars);
+                        match chars.next()? {
+                            ',' => continue,
+                            '}' => break,
+                            _ => return None,
+                        }
+                    }
+                }
+                Some(Value::Object(members))
+            }
+            '[' => {
+                chars.next();
+                let mut values = Vec::new();
+                skip_whitespace(chars);
+                if chars.next_if_eq(&']').is_none() {
+                    loop {
+                        values.push(parse_value(chars)?);
+                        skip_whitespace(chars);
+                        match chars.next()? {
+                            ',' => continue,
+                            ']' => break,
+                            _ => return None,
+                        }
+                    }
+                }
+                Some(Value::Array(values))
+            }
+            '"' => parse_string(chars).map(Value::String),
+            't' => parse_literal(chars, "true", Value::Bool(true)),
+            'f' => parse_literal(chars, "false", Value::Bool(false)),
+            'n' => parse_literal(chars, "null", Value::Null),
+            _ => {
+                let mut number = String::new();
+                while let Some(c) = chars
+                    .next_if(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+                {
+                    number.push(c);
+                }
+                number.parse().ok().map(Value::Number)
+            }
+        }
+    }
+
+    fn parse_literal(chars: &mut Peekable<Chars>, literal: &str, value: Value) -> Option<Value> {
+        for expected in literal.chars() {
+            chars.next_if_eq(&expected)?;
+        }
+        Some(value)
+    }
+
+    fn parse_string(chars: &mut Peekable<Chars>) -> Option<String> {
+        chars.next_if_eq(&'"')?;
+        let mut string = String::new();
+        loop {
+            match chars.next()? {
+                '"' => return Some(string),
+                '\\' => string.push(match chars.next()? {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'u' => {
+                        let hex: String = (0..4).map(|_| chars.next()).collect::<Option<_>>()?;
+                        let unit = u32::from_str_radix(&hex, 16).ok()?;
+                        // Surrogate pairs are not needed for SkSL source and names.
+                        char::from_u32(unit).unwrap_or(char::REPLACEMENT_CHARACTER)
+                    }
+                    c => c,
+                }),
+                c => string.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NumberKind, Trace, TraceEvent, TraceValue};
+    use crate::{surfaces, Paint, Rect, RuntimeEffect, RuntimeEffectBuilder};
+
+    #[test]
+    fn parse_trace_json() {
+        let json = r#"{"version":"20220209","source":["half4 main(float2 p) {","  float x = 0.5;","  return half4(x);","}"],
+            "slots":[{"name":"x","columns":1,"rows":1,"index":0,"kind":0,"line":2},
+                     {"name":"[main].result","columns":4,"rows":1,"index":0,"kind":0,"line":1,"retval":0}],
+            "functions":[{"name":"half4 main(float2 p)"}],
+            "trace":[[2],[4,1],[0,2],[1,0,1056964608],[0,3],[4,-1],[3]]}"#;
+        let trace = Trace::from_json(json).unwrap();
+        assert_eq!(trace.source.len(), 4);
+        assert_eq!(trace.slots[0].kind, NumberKind::Float);
+        assert_eq!(trace.slots[1].return_value_of, Some(0));
+        assert_eq!(trace.functions, ["half4 main(float2 p)"]);
+        assert_eq!(trace.events[0], TraceEvent::Enter(0));
+        assert_eq!(trace.events.last(), Some(&TraceEvent::Exit(0)));
+        assert_eq!(trace.executed_lines().collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(
+            trace.values_of("x").collect::<Vec<_>>(),
+            [(0, TraceValue::Float(0.5))]
+        );
+        assert!(Trace::from_json("{").is_none());
+    }
+
+    #[test]
+    fn trace_a_pixel() {
+        let effect = RuntimeEffect::make_for_shader(
+            "half4 main(float2 p) {\n    float x = p.x / 4;\n    return half4(x, 0, 0, 1);\n}\n",
+            None,
+        )
+        .unwrap();
+        let shader = RuntimeEffectBuilder::new(effect).make_shader(None).unwrap();
+        let (shader, debug_trace) = RuntimeEffect::make_traced(shader, (2, 1)).unwrap();
+
+        let mut surface = surfaces::raster_n32_premul((4, 4)).unwrap();
+        let mut paint = Paint::default();
+        paint.set_shader(shader);
+        surface.canvas().draw_rect(Rect::from_wh(4.0, 4.0), &paint);
+
+        let trace = debug_trace.trace().unwrap();
+        assert_eq!(trace.source.len(), 4);
+        assert!(trace.executed_lines().any(|line| line == 2));
+        assert!(trace
+            .values_of("x")
+            .any(|(_, value)| value == TraceValue::Float(2.5 / 4.0)));
+    }
+}
@@ -0,0 +1,272 @@
+use std::ops::Range;
+
+use skia_bindings as sb;
+
+use crate::{interop, prelude::*};
+
+/// The kind of program an SkSL source is compiled to, see [`validate()`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ProgramKind {
+    Shader,
+    ColorFilter,
+    Blender,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// An error or warning of the SkSL compiler.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The 1-based line the diagnostic refers to, `None` if it refers to the whole program.
+    pub line: Option<usize>,
+    /// The 1-based column in characters.
+    pub column: Option<usize>,
+    /// The UTF-8 range in the source the diagnostic refers to. It ends at the end of the line if
+    /// the range continues on the following lines.
+    pub range: Option<Range<usize>>,
+    pub message: String,
+    /// The line of the source as printed by the compiler, long lines are shortened with `...`.
+    pub snippet: Option<String>,
+}
+
+/// Parses the error text returned by [`RuntimeEffect::make_for_shader()`] and the other
+/// factories into diagnostics.
+///
+/// `sksl` is the source that was compiled, it is used to resolve the columns and ranges.
+pub fn parse_diagnostics(error_text: &str, sksl: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = error_text.lines().collect();
+    let mut diagnostics = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let text = lines[i];
+        i += 1;
+        let (severity, text) = if let Some(text) = text.strip_prefix("error: ") {
+            (Severity::Error, text)
+        } else if let Some(text) = text.strip_prefix("warning: ") {
+            (Severity::Warning, text)
+        } else {
+            continue;
+        };
+        let (line, message) = match text.split_once(": ") {
+            Some((line, message)) if line.parse::<usize>().is_ok() => (line.parse().ok(), message),
+            _ => (None, text),
+        };
+        let mut diagnostic = Diagnostic {
+            severity,
+            line,
+            column: None,
+            range: None,
+            message: message.into(),
+            snippet: None,
+        };
+        if let (Some(line), Some(&snippet), Some(&carets)) = (line, lines.get(i), lines.get(i + 1))
+        {
+            if is_caret_line(carets) {
+                if let Some((column, range)) = locate(sksl, line, snippet, carets) {
+                    diagnostic.column = Some(column);
+                    diagnostic.range = Some(range);
+                }
+                diagnostic.snippet = Some(snippet.into());
+                i += 2;
+            }
+        }
+        diagnostics.push(diagnostic);
+    }
+    diagnostics
+}
+
+/// Compiles `sksl` to a program of `kind`.
+///
+/// Returns the warnings if the program compiles, and the errors and warnings otherwise. The
+/// source is compiled like [`super::RuntimeEffect::make_for_shader()`] and the other factories
+/// do, which does not need a surface or a GPU context.
+pub fn validate(
+    sksl: impl AsRef<str>,
+    kind: ProgramKind,
+) -> Result<Vec<Diagnostic>, Vec<Diagnostic>> {
+    let sksl = sksl.as_ref();
+    let kind = match kind {
+        ProgramKind::Shader => 0,
+        ProgramKind::ColorFilter => 1,
+        ProgramKind::Blender => 2,
+    };
+    let source = interop::String::from_str(sksl);
+    let mut text = interop::String::default();
+    let compiled =
+        unsafe { sb::C_SkRuntimeEffect_Validate(kind, source.native(), text.native_mut()) };
+    let text = text.to_string();
+    let mut diagnostics = parse_diagnostics(&text, sksl);
+    if compiled {
+        return Ok(diagnostics);
+    }
+    if !diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            line: None,
+            column: None,
+            range: None,
+            message: text.trim().into(),
+            snippet: None,
+        });
+    }
+    Err(diagnostics)
+}
+
+fn is_caret_line(line: &str) -> bool {
+    line.contains('^') && line.chars().all(|c| matches!(c, ' ' | '^' | '.'))
+}
+
+const ELLIPSIS: &str = "...";
+
+/// Resolves the caret line the compiler prints below the `snippet` of line `line` to a column
+/// and a range in `sksl`.
+///
+/// The compiler prints tabs as four spaces and one character per byte in the caret line. Lines
+/// that are longer than 100 bytes before the error start with an ellipsis.
+fn locate(sksl: &str, line: usize, snippet: &str, carets: &str) -> Option<(usize, Range<usize>)> {
+    let line_start: usize = sksl
+        .split('\n')
+        .take(line.checked_sub(1)?)
+        .map(|l| l.len() + 1)
+        .sum();
+    let text = sksl.get(line_start..)?.split('\n').next()?;
+
+    // The printed form of the line and, for each of its bytes, the byte offset and the column of
+    // the character in the source.
+    let mut printed = String::new();
+    let mut positions = Vec::new();
+    for (column, (offset, c)) in text.char_indices().enumerate() {
+        let width = match c {
+            '\t' => {
+                printed.push_str("    ");
+                4
+            }
+            '\0' => {
+                printed.push(' ');
+                1
+            }
+            c => {
+                printed.push(c);
+                c.len_utf8()
+            }
+        };
+        positions.resize(positions.len() + width, (offset, column));
+    }
+    positions.push((text.len(), text.chars().count()));
+
+    let shown = snippet.strip_suffix(ELLIPSIS).unwrap_or(snippet);
+    let (first, prefix) = if printed.starts_with(shown) {
+        (0, 0)
+    } else {
+        let shown = shown.strip_prefix(ELLIPSIS)?;
+        (printed.find(shown)?, ELLIPSIS.len())
+    };
+
+    let caret = carets.find('^')?;
+    let count = carets[caret..].chars().take_while(|c| *c == '^').count();
+    let start = caret.checked_sub(prefix)? + first;
+    let (start_offset, column) = *positions.get(start)?;
+    let end_offset = positions
+        .get(start + count)
+        .map_or(text.len(), |(offset, _)| *offset);
+    Some((
+        column + 1,
+        line_start + start_offset..line_start + end_offset,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_diagnostics, validate, Diagnostic, ProgramKind, Severity};
+    use crate::RuntimeEffect;
+
+    #[test]
+    fn parse_compiler_errors() {
+        let sksl = "half4 main(float2 p) {\n\tfloat é = x;\n    return half4(1);\n}\n";
+        let error_text = "error: 2: unknown identifier 'x'\n    float é = x;\n               ^\n\
+                          error: missing return\n\
+                          2 errors\n";
+        let diagnostics = parse_diagnostics(error_text, sksl);
+        assert_eq!(
+            diagnostics,
+            [
+                Diagnostic {
+                    severity: Severity::Error,
+                    line: Some(2),
+                    column: Some(12),
+                    range: Some(35..36),
+                    message: "unknown identifier 'x'".into(),
+                    snippet: Some("    float é = x;".into()),
+                },
+                Diagnostic {
+                    severity: Severity::Error,
+                    line: None,
+                    column: None,
+                    range: None,
+                    message: "missing return".into(),
+                    snippet: None,
+                }
+            ]
+        );
+        assert_eq!(&sksl[35..36], "x");
+
+        // Line numbers start at 1.
+        let diagnostics = parse_diagnostics("error: 0: unexpected\nx\n^\n", sksl);
+        assert_eq!(diagnostics[0].line, Some(0));
+        assert_eq!(diagnostics[0].range, None);
+    }
+
+    #[test]
+    fn parse_shortened_lines() {
+        let sksl = format!("{}y;", "x".repeat(150));
+        let error_text = format!(
+            "error: 1: unknown identifier 'y'\n...{}y\n{}^\n",
+            "x".repeat(100),
+            " ".repeat(103)
+        );
+        let diagnostics = parse_diagnostics(&error_text, &sksl);
+        assert_eq!(diagnostics[0].column, Some(151));
+        assert_eq!(diagnostics[0].range, Some(150..151));
+    }
+
+    #[test]
+    fn validate_sksl() {
+        assert_eq!(
+            validate(
+                "half4 main(float2 p) { return half4(1); }",
+                ProgramKind::Shader
+            ),
+            Ok(Vec::new())
+        );
+        let diagnostics = validate(
+            "half4 main(float2 p) {\n    return half4(unknown);\n}\n",
+            ProgramKind::Shader,
+        )
+        .unwrap_err();
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].line, Some(2));
+        assert!(diagnostics[0].message.contains("unknown"));
+        assert!(validate(
+            "half4 main(float2 p) { return half4(1); }",
+            ProgramKind::Blender
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn validate_like_runtime_effect() {
+        // `while` loops are not allowed in SkSL version 100, which runtime effects are limited to.
+        let sksl = "half4 main(float2 p) {\n    int i = 0;\n    while (i < 2) { i++; }\n    return half4(1);\n}\n";
+        assert!(RuntimeEffect::make_for_shader(sksl, None).is_err());
+        let diagnostics = validate(sksl, ProgramKind::Shader).unwrap_err();
+        assert_eq!(diagnostics[0].line, Some(3));
+    }
+}
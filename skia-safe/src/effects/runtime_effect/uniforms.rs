@@ -0,0 +1,100 @@
+use super::{uniform, BuilderError, RuntimeEffect};
+use crate::Data;
+
+/// Derives [`Uniforms`] for a struct with named fields, see the documentation of the trait.
+#[cfg(feature = "derive")]
+pub use skia_safe_derive::Uniforms;
+
+/// The declaration of a uniform in a [`Uniforms`] struct.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct UniformField {
+    pub name: &'static str,
+    /// The type of the uniform, or of the elements of the uniform array.
+    pub ty: uniform::Type,
+    /// The number of elements, 1 for uniforms that are not arrays.
+    pub count: usize,
+}
+
+/// A struct that holds all the uniforms of a [`RuntimeEffect`].
+///
+/// Usually derived, which requires the `derive` feature:
+///
+/// ```
+/// use skia_safe::{runtime_effect::Uniforms, Color4f, Point};
+///
+/// #[derive(Uniforms)]
+/// #[repr(C)]
+/// struct Ripple {
+///     center: Point,
+///     #[uniform(name = "u_time")]
+///     time: f32,
+///     tint: Color4f,
+///     #[uniform(array)]
+///     weights: [f32; 3],
+/// }
+/// ```
+///
+/// The uniform data is packed in the order of the fields, as Skia expects it, and each field is
+/// converted with [`UniformValue::write()`](super::UniformValue::write), so matrices are stored
+/// in column-major order. Call [`Uniforms::check()`] when the effect is loaded to verify that the
+/// fields match the names, types and order of the uniforms the effect declares.
+pub trait Uniforms {
+    /// The uniforms in the order of the uniform data.
+    const FIELDS: &'static [UniformField];
+
+    /// Appends the uniform data to `data`.
+    fn write(&self, data: &mut Vec<u8>);
+
+    /// Returns the uniform data to be passed to [`RuntimeEffect::make_shader()`],
+    /// [`RuntimeEffect::make_color_filter()`], or [`RuntimeEffect::make_blender()`].
+    fn to_data(&self) -> Data {
+        let mut data = Vec::new();
+        self.write(&mut data);
+        Data::new_copy(&data)
+    }
+
+    /// Verifies that [`Self::FIELDS`] matches the uniforms of `effect`.
+    fn check(effect: &RuntimeEffect) -> Result<(), BuilderError>
+    where
+        Self: Sized,
+    {
+        check_fields(Self::FIELDS, effect)
+    }
+}
+
+fn check_fields(fields: &[UniformField], effect: &RuntimeEffect) -> Result<(), BuilderError> {
+    let uniforms = effect.uniforms();
+    for (index, field) in fields.iter().enumerate() {
+        let name = field.name;
+        let Some(position) = uniforms.iter().position(|u| u.name() == name) else {
+            return Err(BuilderError::UnknownUniform(name.into()));
+        };
+        if position != index {
+            return Err(BuilderError::UniformOrder {
+                name: name.into(),
+                expected: position,
+                actual: index,
+            });
+        }
+        let uniform = &uniforms[position];
+        if uniform.ty() != field.ty {
+            return Err(BuilderError::UniformType {
+                name: name.into(),
+                expected: uniform.ty(),
+                actual: field.ty,
+            });
+        }
+        let count: usize = uniform.count().try_into().unwrap();
+        if count != field.count {
+            return Err(BuilderError::UniformCount {
+                name: name.into(),
+                expected: count,
+                actual: field.count,
+            });
+        }
+    }
+    if let Some(missing) = uniforms.get(fields.len()) {
+        return Err(BuilderError::MissingUniform(missing.name().into()));
+    }
+    Ok(())
+}
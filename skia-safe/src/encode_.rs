@@ -1,6 +1,8 @@
 use crate::{Bitmap, EncodedImageFormat, Pixmap};
 
+#[cfg(feature = "jpeg-encode")]
 pub mod jpeg_encoder;
+#[cfg(feature = "png-encode")]
 pub mod png_encoder;
 #[cfg(feature = "webp-encode")]
 pub mod webp_encoder;
@@ -37,9 +39,10 @@ impl crate::Image {
 }
 
 pub mod encode {
-    use super::{jpeg_encoder, png_encoder};
     use crate::{Bitmap, EncodedImageFormat, Pixmap};
 
+    // Parameters are unused when all encoders are disabled.
+    #[allow(unused_variables, unused_mut)]
     pub fn pixmap(
         bitmap: &Pixmap,
         format: EncodedImageFormat,
@@ -48,14 +51,18 @@ pub mod encode {
         let mut data = Vec::new();
         let quality = quality.into().unwrap_or(100).clamp(0, 100);
         match format {
+            #[cfg(feature = "jpeg-encode")]
             EncodedImageFormat::JPEG => {
+                use super::jpeg_encoder;
                 let opts = jpeg_encoder::Options {
                     quality,
                     ..jpeg_encoder::Options::default()
                 };
                 jpeg_encoder::encode(bitmap, &mut data, &opts)
             }
+            #[cfg(feature = "png-encode")]
             EncodedImageFormat::PNG => {
+                use super::png_encoder;
                 let opts = png_encoder::Options::default();
                 png_encoder::encode(bitmap, &mut data, &opts)
             }
@@ -86,6 +93,7 @@ pub mod encode {
         pixmap(&pixels, format, quality)
     }
 
+    #[allow(unused_variables)]
     pub fn image<'a>(
         context: impl Into<Option<&'a mut crate::gpu::DirectContext>>,
         image: &crate::Image,
@@ -94,14 +102,18 @@ pub mod encode {
     ) -> Option<crate::Data> {
         let quality = quality.into().unwrap_or(100).clamp(0, 100);
         match image_format {
+            #[cfg(feature = "jpeg-encode")]
             EncodedImageFormat::JPEG => {
+                use super::jpeg_encoder;
                 let opts = jpeg_encoder::Options {
                     quality,
                     ..jpeg_encoder::Options::default()
                 };
                 jpeg_encoder::encode_image(context, image, &opts)
             }
+            #[cfg(feature = "png-encode")]
             EncodedImageFormat::PNG => {
+                use super::png_encoder;
                 let opts = png_encoder::Options::default();
                 png_encoder::encode_image(context, image, &opts)
             }
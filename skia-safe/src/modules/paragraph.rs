@@ -2,29 +2,43 @@ use crate::interop::AsStr;
 use std::ops::Index;
 
 mod dart_types;
+mod editable_paragraph;
 mod font_arguments;
 mod font_collection;
+mod hyphenation;
+mod inline_widget;
+mod markup;
 mod metrics;
 #[allow(clippy::module_inception)]
 pub mod paragraph;
 mod paragraph_builder;
 mod paragraph_cache;
 mod paragraph_style;
+mod text_flow;
 mod text_shadow;
 mod text_style;
+mod truncation;
 mod typeface_font_provider;
+mod vertical_paragraph;
 
 pub use dart_types::*;
+pub use editable_paragraph::*;
 pub use font_arguments::*;
 pub use font_collection::*;
+pub use hyphenation::*;
+pub use inline_widget::*;
+pub use markup::*;
 pub use metrics::*;
 pub use paragraph::Paragraph;
 pub use paragraph_builder::*;
 pub use paragraph_cache::*;
 pub use paragraph_style::*;
+pub use text_flow::*;
 pub use text_shadow::*;
 pub use text_style::*;
+pub use truncation::*;
 pub use typeface_font_provider::*;
+pub use vertical_paragraph::*;
 
 /// Efficient reference type to a C++ vector of font family SkStrings.
 ///
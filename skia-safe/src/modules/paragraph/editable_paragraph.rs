@@ -0,0 +1,1206 @@
+//! A text editing model on top of [`Paragraph`].
+
+use std::ops::Range;
+
+use super::{
+    Affinity, FontCollection, Paragraph, ParagraphBuilder, ParagraphStyle, RectHeightStyle,
+    RectWidthStyle, TextBox, TextDecoration, TextDirection, TextStyle,
+};
+use crate::{scalar, Canvas, Point, Rect};
+
+/// A selection in the text of an [`EditableParagraph`].
+///
+/// All offsets are UTF-8 byte offsets into the text. If `anchor` and `focus` are equal, the
+/// selection is a caret. `affinity` resolves the position of the caret at line breaks:
+/// [`Affinity::Upstream`] places it at the end of the previous line.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TextSelection {
+    pub anchor: usize,
+    pub focus: usize,
+    pub affinity: Affinity,
+}
+
+impl Default for TextSelection {
+    fn default() -> Self {
+        Self::caret(0)
+    }
+}
+
+impl TextSelection {
+    pub fn new(anchor: usize, focus: usize) -> Self {
+        Self {
+            anchor,
+            focus,
+            affinity: Affinity::Downstream,
+        }
+    }
+
+    pub fn caret(offset: usize) -> Self {
+        Self::new(offset, offset)
+    }
+
+    pub fn is_collapsed(&self) -> bool {
+        self.anchor == self.focus
+    }
+
+    /// The selected range, ordered from the lower to the higher offset.
+    pub fn range(&self) -> Range<usize> {
+        self.anchor.min(self.focus)..self.anchor.max(self.focus)
+    }
+}
+
+/// The caret movements supported by [`EditableParagraph::move_caret()`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum CaretMovement {
+    /// One grapheme cluster to the left in visual order, which is backwards in left-to-right text
+    /// and forwards in right-to-left text. At the left edge of a line, the caret moves to the
+    /// right edge of the adjacent line.
+    Left,
+    /// One grapheme cluster to the right in visual order. At the right edge of a line, the caret
+    /// moves to the left edge of the adjacent line.
+    Right,
+    /// To the start of the previous word.
+    WordLeft,
+    /// To the end of the next word.
+    WordRight,
+    /// To the previous line, keeping the horizontal position.
+    Up,
+    /// To the next line, keeping the horizontal position.
+    Down,
+    /// To the start of the current line.
+    Home,
+    /// To the end of the current line.
+    End,
+    /// To the start of the text.
+    TextStart,
+    /// To the end of the text.
+    TextEnd,
+}
+
+/// A run of text that is laid out with one [`TextStyle`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct StyleRun {
+    pub range: Range<usize>,
+    pub style: TextStyle,
+}
+
+/// The text, style runs, selection, and composing range of an editable paragraph.
+///
+/// The text is laid out as one [`Paragraph`] per block of text between hard line breaks. An edit
+/// of the text or the styles rebuilds only the blocks it touches, the other blocks keep their
+/// shaped and laid out paragraphs. A modified block is built lazily when it is needed for the
+/// first time, so a sequence of edits without queries in between is shaped only once. If only the
+/// layout width changes, the existing paragraphs are laid out again without rebuilding them, and
+/// changing the selection does not touch them at all.
+///
+/// Because every block is a paragraph of its own, [`ParagraphStyle::max_lines()`] and the
+/// ellipsis apply to each block separately.
+///
+/// All offsets are UTF-8 byte offsets into [`Self::text()`]. All positions are relative to the top
+/// left corner of the first block.
+#[derive(Debug)]
+pub struct EditableParagraph {
+    paragraph_style: ParagraphStyle,
+    font_collection: FontCollection,
+    text: String,
+    /// Contiguous, non-empty runs that cover the whole text. If the text is empty, there is one
+    /// empty run that defines the style of the text inserted next.
+    runs: Vec<StyleRun>,
+    /// The blocks that cover the text in order, separated by the hard line breaks. There is at
+    /// least one block.
+    blocks: Vec<Block>,
+    selection: TextSelection,
+    composing: Option<Range<usize>>,
+    /// The horizontal position that is kept when the caret moves up or down.
+    preferred_x: Option<scalar>,
+    width: scalar,
+}
+
+impl EditableParagraph {
+    pub fn new(
+        paragraph_style: &ParagraphStyle,
+        font_collection: impl Into<FontCollection>,
+    ) -> Self {
+        Self {
+            paragraph_style: paragraph_style.clone(),
+            font_collection: font_collection.into(),
+            text: String::new(),
+            runs: vec![StyleRun {
+                range: 0..0,
+                style: paragraph_style.text_style().clone(),
+            }],
+            blocks: Block::split("", 0..0),
+            selection: TextSelection::default(),
+            composing: None,
+            preferred_x: None,
+            width: scalar::INFINITY,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text and the styles with `text` in the default text style of the paragraph
+    /// style. Places the caret at the end of the text.
+    pub fn set_text(&mut self, text: impl Into<String>) -> &mut Self {
+        self.text = text.into();
+        self.blocks = Block::split(&self.text, 0..self.text.len());
+        self.runs = vec![StyleRun {
+            range: 0..self.text.len(),
+            style: self.paragraph_style.text_style().clone(),
+        }];
+        self.selection = TextSelection::caret(self.text.len());
+        self.composing = None;
+        self.preferred_x = None;
+        self
+    }
+
+    pub fn style_runs(&self) -> &[StyleRun] {
+        &self.runs
+    }
+
+    /// Applies `style` to the text in `range`.
+    pub fn set_style(&mut self, range: Range<usize>, style: &TextStyle) -> &mut Self {
+        let range = self.clamp_range(range);
+        if range.is_empty() {
+            return self;
+        }
+        self.split_run_at(range.start);
+        self.split_run_at(range.end);
+        for run in &mut self.runs {
+            if run.range.start >= range.start && run.range.end <= range.end {
+                run.style = style.clone();
+            }
+        }
+        self.merge_runs();
+        self.invalidate(range);
+        self
+    }
+
+    pub fn selection(&self) -> TextSelection {
+        self.selection
+    }
+
+    pub fn set_selection(&mut self, selection: TextSelection) -> &mut Self {
+        self.selection = TextSelection {
+            anchor: self.clamp_offset(selection.anchor),
+            focus: self.clamp_offset(selection.focus),
+            affinity: selection.affinity,
+        };
+        self.preferred_x = None;
+        self
+    }
+
+    pub fn selected_text(&self) -> &str {
+        &self.text[self.selection.range()]
+    }
+
+    /// The range of the text that is currently composed by an input method.
+    pub fn composing_range(&self) -> Option<Range<usize>> {
+        self.composing.clone()
+    }
+
+    /// Sets the width the paragraph is laid out with.
+    pub fn set_width(&mut self, width: scalar) -> &mut Self {
+        self.width = width;
+        self
+    }
+
+    pub fn width(&self) -> scalar {
+        self.width
+    }
+
+    /// Returns the vertical positions and the paragraphs of the blocks between the hard line
+    /// breaks, built and laid out with the current text, styles and width.
+    pub fn paragraphs(&mut self) -> impl Iterator<Item = (scalar, &Paragraph)> {
+        let tops = self.block_tops();
+        tops.into_iter().zip(
+            self.blocks
+                .iter()
+                .filter_map(|block| block.paragraph.as_ref()),
+        )
+    }
+
+    /// The height of all blocks.
+    pub fn height(&mut self) -> scalar {
+        self.paragraphs()
+            .map(|(_, paragraph)| paragraph.height())
+            .sum()
+    }
+
+    pub fn paint(&mut self, canvas: &Canvas, p: impl Into<Point>) {
+        let p = p.into();
+        for (top, paragraph) in self.paragraphs() {
+            paragraph.paint(canvas, (p.x, p.y + top));
+        }
+    }
+
+    /// Inserts `text` at the caret, replacing the selection. The inserted text uses the style of
+    /// the text before the caret.
+    pub fn insert(&mut self, text: &str) -> &mut Self {
+        self.set_composing(None);
+        let range = self.selection.range();
+        self.replace(range, text);
+        self
+    }
+
+    /// Deletes the selection, or if the selection is collapsed, the grapheme cluster before the
+    /// caret.
+    pub fn delete_backward(&mut self) -> &mut Self {
+        self.set_composing(None);
+        let range = if self.selection.is_collapsed() {
+            let focus = self.selection.focus;
+            self.prev_grapheme(focus)..focus
+        } else {
+            self.selection.range()
+        };
+        self.replace(range, "");
+        self
+    }
+
+    /// Deletes the selection, or if the selection is collapsed, the grapheme cluster after the
+    /// caret.
+    pub fn delete_forward(&mut self) -> &mut Self {
+        self.set_composing(None);
+        let range = if self.selection.is_collapsed() {
+            let focus = self.selection.focus;
+            focus..self.next_grapheme(focus)
+        } else {
+            self.selection.range()
+        };
+        self.replace(range, "");
+        self
+    }
+
+    /// Replaces the text that is currently composed by an input method, or the selection if there
+    /// is none, with `text`. Composing text is underlined.
+    ///
+    /// An empty `text` cancels the composition.
+    pub fn set_composing_text(&mut self, text: &str) -> &mut Self {
+        let range = self
+            .composing
+            .take()
+            .unwrap_or_else(|| self.selection.range());
+        let start = range.start;
+        self.replace(range, text);
+        self.set_composing((!text.is_empty()).then(|| start..start + text.len()));
+        self
+    }
+
+    /// Ends the composition and keeps the composed text.
+    pub fn commit_composing(&mut self) -> &mut Self {
+        self.set_composing(None);
+        self
+    }
+
+    /// Moves the caret. If `extend` is `true`, the selection is extended instead.
+    pub fn move_caret(&mut self, movement: CaretMovement, extend: bool) -> &mut Self {
+        use CaretMovement::*;
+
+        let selection = self.selection;
+        if !extend && !selection.is_collapsed() && matches!(movement, Left | Right) {
+            let range = selection.range();
+            let ltr = self.direction_at(selection.focus) == TextDirection::LTR;
+            let offset = if (movement == Left) == ltr {
+                range.start
+            } else {
+                range.end
+            };
+            self.selection = TextSelection::caret(offset);
+            self.preferred_x = None;
+            return self;
+        }
+
+        let focus = selection.focus;
+        let (focus, affinity) = match movement {
+            Left | Right => self.visual_neighbor(movement == Right),
+            WordLeft => (self.prev_word_start(focus), Affinity::Downstream),
+            WordRight => (self.next_word_end(focus), Affinity::Downstream),
+            Up | Down => {
+                let caret = self.caret_rect();
+                let x = *self.preferred_x.get_or_insert(caret.left);
+                let (focus, affinity) = self.vertical_target(caret, x, movement == Down);
+                self.selection = TextSelection {
+                    anchor: if extend { selection.anchor } else { focus },
+                    focus,
+                    affinity,
+                };
+                return self;
+            }
+            Home | End => self.line_boundary(movement == End),
+            TextStart => (0, Affinity::Downstream),
+            TextEnd => (self.text.len(), Affinity::Downstream),
+        };
+
+        self.selection = TextSelection {
+            anchor: if extend { selection.anchor } else { focus },
+            focus,
+            affinity,
+        };
+        self.preferred_x = None;
+        self
+    }
+
+    /// Returns the text offset and affinity closest to `p`.
+    pub fn hit_test(&mut self, p: impl Into<Point>) -> (usize, Affinity) {
+        let p = p.into();
+        let (index, top) = self.block_at_y(p.y);
+        let position = self
+            .block_paragraph(index)
+            .get_glyph_position_at_coordinate((p.x, p.y - top));
+        let offset = self.blocks[index].utf8_offset(position.position.max(0) as usize);
+        (offset, position.affinity)
+    }
+
+    /// Places the caret at the text position closest to `p`. If `extend` is `true`, the selection
+    /// is extended instead.
+    pub fn set_caret_at(&mut self, p: impl Into<Point>, extend: bool) -> &mut Self {
+        let (focus, affinity) = self.hit_test(p);
+        self.selection = TextSelection {
+            anchor: if extend { self.selection.anchor } else { focus },
+            focus,
+            affinity,
+        };
+        self.preferred_x = None;
+        self
+    }
+
+    /// Returns the rectangles that cover the selection.
+    pub fn selection_rects(&mut self) -> Vec<TextBox> {
+        let range = self.selection.range();
+        self.rects_for_range(range, RectHeightStyle::Max)
+    }
+
+    /// Returns the rectangles that cover the composing text.
+    pub fn composing_rects(&mut self) -> Vec<TextBox> {
+        match self.composing.clone() {
+            Some(range) => self.rects_for_range(range, RectHeightStyle::Tight),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the position of the caret at the focus of the selection, as a rectangle with a
+    /// width of zero.
+    pub fn caret_rect(&mut self) -> Rect {
+        let focus = self.selection.focus;
+        let len = self.text.len();
+
+        let after_newline = focus > 0 && self.text[..focus].ends_with('\n');
+        let upstream = focus > 0 && self.selection.affinity == Affinity::Upstream;
+
+        if !after_newline && (upstream || focus == len) && focus > 0 {
+            let prev = self.prev_grapheme(focus);
+            if let Some(tb) = self
+                .rects_for_range(prev..focus, RectHeightStyle::Max)
+                .last()
+            {
+                let x = match tb.direct {
+                    TextDirection::LTR => tb.rect.right,
+                    TextDirection::RTL => tb.rect.left,
+                };
+                return Rect::new(x, tb.rect.top, x, tb.rect.bottom);
+            }
+        }
+
+        if focus < len {
+            let next = self.next_grapheme(focus);
+            if let Some(tb) = self
+                .rects_for_range(focus..next, RectHeightStyle::Max)
+                .first()
+            {
+                let x = match tb.direct {
+                    TextDirection::LTR => tb.rect.left,
+                    TextDirection::RTL => tb.rect.right,
+                };
+                return Rect::new(x, tb.rect.top, x, tb.rect.bottom);
+            }
+        }
+
+        // Empty lines have no glyphs, so the line metrics define the caret position.
+        let rtl = self.paragraph_style.text_direction() == TextDirection::RTL;
+        let lines = self.lines();
+        let line = lines
+            .iter()
+            .rev()
+            .find(|line| line.range.start <= focus)
+            .or_else(|| lines.first());
+        match line {
+            Some(line) => {
+                let x = if rtl {
+                    line.left + line.width
+                } else {
+                    line.left
+                };
+                Rect::new(
+                    x,
+                    line.baseline - line.ascent,
+                    x,
+                    line.baseline + line.descent,
+                )
+            }
+            None => Rect::default(),
+        }
+    }
+
+    /// Builds the paragraph of the block that covers `range` of the text.
+    fn build(&self, range: Range<usize>) -> Paragraph {
+        let mut builder =
+            ParagraphBuilder::new(&self.paragraph_style, self.font_collection.clone());
+        if range.is_empty() {
+            // The line height of an empty block is defined by the style at its position.
+            builder.push_style(self.style_before(range.start));
+            return builder.build();
+        }
+        for run in &self.runs {
+            let r = run.range.start.max(range.start)..run.range.end.min(range.end);
+            if r.is_empty() {
+                continue;
+            }
+            let mut push = |range: Range<usize>, composing: bool| {
+                if range.is_empty() {
+                    return;
+                }
+                if composing {
+                    let mut style = run.style.clone();
+                    style.set_decoration_type(style.decoration_type() | TextDecoration::UNDERLINE);
+                    builder.push_style(&style);
+                } else {
+                    builder.push_style(&run.style);
+                }
+                builder.add_text(&self.text[range]);
+                builder.pop();
+            };
+            match &self.composing {
+                Some(c) => {
+                    push(r.start..c.start.clamp(r.start, r.end), false);
+                    push(
+                        c.start.clamp(r.start, r.end)..c.end.clamp(r.start, r.end),
+                        true,
+                    );
+                    push(c.end.clamp(r.start, r.end)..r.end, false);
+                }
+                None => push(r, false),
+            }
+        }
+        builder.build()
+    }
+
+    /// Drops the paragraphs of the blocks that overlap or touch `range`.
+    fn invalidate(&mut self, range: Range<usize>) {
+        for block in &mut self.blocks {
+            if block.range.start <= range.end && range.start <= block.range.end {
+                block.paragraph = None;
+            }
+        }
+    }
+
+    /// Sets the composing range and rebuilds the blocks that change their underline.
+    fn set_composing(&mut self, composing: Option<Range<usize>>) {
+        if let Some(range) = self.composing.take() {
+            self.invalidate(range);
+        }
+        if let Some(range) = composing.clone() {
+            self.invalidate(range);
+        }
+        self.composing = composing;
+    }
+
+    /// Replaces the text in `range` with `text` and places the caret after it.
+    fn replace(&mut self, range: Range<usize>, text: &str) {
+        let range = self.clamp_range(range);
+        let style = self.style_before(range.start).clone();
+        let first_block = self.block_index(range.start);
+        let last_block = self.block_index(range.end);
+
+        self.text.replace_range(range.clone(), text);
+
+        // Remove the replaced range from the runs.
+        let removed = range.len();
+        let map = |x: usize| {
+            if x <= range.start {
+                x
+            } else if x >= range.end {
+                x - removed
+            } else {
+                range.start
+            }
+        };
+        for run in &mut self.runs {
+            run.range = map(run.range.start)..map(run.range.end);
+        }
+        self.runs.retain(|run| !run.range.is_empty());
+
+        // Insert the new text with the style before the replaced range.
+        let pos = range.start;
+        let inserted = text.len();
+        let mut runs = Vec::with_capacity(self.runs.len() + 2);
+        let mut new_run = Some(StyleRun {
+            range: pos..pos + inserted,
+            style,
+        });
+        for run in self.runs.drain(..) {
+            let r = run.range.clone();
+            if r.end <= pos {
+                runs.push(run);
+            } else if r.start >= pos {
+                runs.extend(new_run.take());
+                runs.push(StyleRun {
+                    range: r.start + inserted..r.end + inserted,
+                    style: run.style,
+                });
+            } else {
+                runs.push(StyleRun {
+                    range: r.start..pos,
+                    style: run.style.clone(),
+                });
+                runs.extend(new_run.take());
+                runs.push(StyleRun {
+                    range: pos + inserted..r.end + inserted,
+                    style: run.style,
+                });
+            }
+        }
+        runs.extend(new_run);
+        self.runs = runs;
+        self.merge_runs();
+
+        // Only the blocks that contained the replaced range are split again and rebuilt, the
+        // blocks after them are moved.
+        let start = self.blocks[first_block].range.start;
+        let end = self.blocks[last_block].range.end - removed + inserted;
+        for block in &mut self.blocks[last_block + 1..] {
+            block.range =
+                block.range.start - removed + inserted..block.range.end - removed + inserted;
+        }
+        let blocks = Block::split(&self.text, start..end);
+        self.blocks.splice(first_block..=last_block, blocks);
+
+        self.selection = TextSelection::caret(pos + inserted);
+        self.preferred_x = None;
+    }
+
+    /// The style of the text that ends at `offset`, or of the first run if `offset` is `0`.
+    fn style_before(&self, offset: usize) -> &TextStyle {
+        let run = self
+            .runs
+            .iter()
+            .find(|run| run.range.start < offset && offset <= run.range.end)
+            .unwrap_or(&self.runs[0]);
+        &run.style
+    }
+
+    fn split_run_at(&mut self, offset: usize) {
+        if let Some(i) = self
+            .runs
+            .iter()
+            .position(|run| run.range.start < offset && offset < run.range.end)
+        {
+            let run = &mut self.runs[i];
+            let tail = StyleRun {
+                range: offset..run.range.end,
+                style: run.style.clone(),
+            };
+            run.range.end = offset;
+            self.runs.insert(i + 1, tail);
+        }
+    }
+
+    /// Merges adjacent runs with equal styles and removes empty runs, but keeps at least one run.
+    fn merge_runs(&mut self) {
+        let mut merged: Vec<StyleRun> = Vec::with_capacity(self.runs.len());
+        for run in self.runs.drain(..) {
+            if run.range.is_empty() {
+                if merged.is_empty() && self.text.is_empty() {
+                    merged.push(run);
+                }
+                continue;
+            }
+            match merged.last_mut() {
+                Some(last) if last.range.is_empty() => *last = run,
+                Some(last) if last.style == run.style => last.range.end = run.range.end,
+                _ => merged.push(run),
+            }
+        }
+        if merged.is_empty() {
+            merged.push(StyleRun {
+                range: 0..0,
+                style: self.paragraph_style.text_style().clone(),
+            });
+        }
+        self.runs = merged;
+    }
+
+    /// The index of the block that contains the caret position `offset`. The position before a
+    /// hard line break belongs to the block the line break ends.
+    fn block_index(&self, offset: usize) -> usize {
+        self.blocks
+            .partition_point(|block| block.range.end < offset)
+            .min(self.blocks.len() - 1)
+    }
+
+    /// Returns the paragraph of the block at `index`, built and laid out with the current text,
+    /// styles and width.
+    fn block_paragraph(&mut self, index: usize) -> &mut Paragraph {
+        if self.blocks[index].paragraph.is_none() {
+            let paragraph = self.build(self.blocks[index].range.clone());
+            let block = &mut self.blocks[index];
+            block.paragraph = Some(paragraph);
+            block.laid_out_width = None;
+        }
+        let width = self.width;
+        let block = &mut self.blocks[index];
+        let paragraph = block.paragraph.as_mut().unwrap();
+        if block.laid_out_width != Some(width) {
+            paragraph.layout(width);
+            block.laid_out_width = Some(width);
+        }
+        paragraph
+    }
+
+    /// Lays out all blocks and returns their vertical positions.
+    fn block_tops(&mut self) -> Vec<scalar> {
+        let mut top = 0.0;
+        (0..self.blocks.len())
+            .map(|index| {
+                let block_top = top;
+                top += self.block_paragraph(index).height();
+                block_top
+            })
+            .collect()
+    }
+
+    /// Returns the index and the vertical position of the block at the vertical position `y`.
+    fn block_at_y(&mut self, y: scalar) -> (usize, scalar) {
+        let tops = self.block_tops();
+        let index = tops.partition_point(|top| *top <= y).saturating_sub(1);
+        (index, tops[index])
+    }
+
+    /// Returns the lines of all blocks.
+    fn lines(&mut self) -> Vec<Line> {
+        let tops = self.block_tops();
+        let mut lines = Vec::new();
+        for (block, top) in self.blocks.iter().zip(tops) {
+            let Some(paragraph) = &block.paragraph else {
+                continue;
+            };
+            let metrics = paragraph.get_line_metrics();
+            let count = metrics.len();
+            lines.extend(metrics.iter().enumerate().map(|(i, line)| Line {
+                range: block.utf8_offset(line.start_index)..block.utf8_offset(line.end_index),
+                left: line.left as scalar,
+                width: line.width as scalar,
+                baseline: top + line.baseline as scalar,
+                ascent: line.ascent as scalar,
+                descent: line.descent as scalar,
+                hard_break: line.hard_break || i + 1 == count,
+            }));
+        }
+        lines
+    }
+
+    fn rects_for_range(
+        &mut self,
+        range: Range<usize>,
+        height_style: RectHeightStyle,
+    ) -> Vec<TextBox> {
+        if range.is_empty() {
+            return Vec::new();
+        }
+        let tops = self.block_tops();
+        let mut rects = Vec::new();
+        for (block, top) in self.blocks.iter().zip(tops) {
+            let start = range.start.max(block.range.start);
+            let end = range.end.min(block.range.end);
+            let Some(paragraph) = block.paragraph.as_ref().filter(|_| start < end) else {
+                continue;
+            };
+            let range = block.utf16_offset(start)..block.utf16_offset(end);
+            rects.extend(
+                paragraph
+                    .get_rects_for_range(range, height_style, RectWidthStyle::Tight)
+                    .into_iter()
+                    .map(|tb| TextBox {
+                        rect: tb.rect.with_offset((0.0, top)),
+                        direct: tb.direct,
+                    }),
+            );
+        }
+        rects
+    }
+
+    /// Returns the grapheme cluster that contains the character at `offset` and its direction.
+    fn cluster_at(&mut self, offset: usize) -> Option<(Range<usize>, TextDirection)> {
+        let index = self.block_index(offset);
+        if offset >= self.blocks[index].range.end {
+            return None;
+        }
+        let utf16 = self.blocks[index].utf16_offset(offset);
+        let info = self
+            .block_paragraph(index)
+            .get_glyph_info_at_utf16_offset(utf16)?;
+        let block = &self.blocks[index];
+        let range = &info.grapheme_cluster_text_range;
+        Some((
+            block.utf8_offset(range.start)..block.utf8_offset(range.end),
+            info.text_direction,
+        ))
+    }
+
+    /// Returns the bounds of the grapheme cluster closest to `p`.
+    fn closest_cluster_bounds(&mut self, p: Point) -> Option<Rect> {
+        let (index, top) = self.block_at_y(p.y);
+        let cluster = self
+            .block_paragraph(index)
+            .get_closest_glyph_cluster_at((p.x, p.y - top))?;
+        Some(cluster.bounds.with_offset((0.0, top)))
+    }
+
+    fn next_grapheme(&mut self, offset: usize) -> usize {
+        if offset >= self.text.len() {
+            return self.text.len();
+        }
+        match self.cluster_at(offset) {
+            Some((cluster, _)) if cluster.end > offset => cluster.end,
+            _ => next_char_boundary(&self.text, offset),
+        }
+    }
+
+    fn prev_grapheme(&mut self, offset: usize) -> usize {
+        if offset == 0 {
+            return 0;
+        }
+        let prev = prev_char_boundary(&self.text, offset);
+        match self.cluster_at(prev) {
+            Some((cluster, _)) if cluster.start < offset => cluster.start,
+            _ => prev,
+        }
+    }
+
+    fn next_word_end(&mut self, mut offset: usize) -> usize {
+        let len = self.text.len();
+        while offset < len {
+            let start = offset;
+            let boundary = self.word_boundary(offset);
+            offset = boundary.end.max(self.next_grapheme(offset));
+            if !self.text[start..offset].trim().is_empty() {
+                break;
+            }
+        }
+        offset
+    }
+
+    fn prev_word_start(&mut self, mut offset: usize) -> usize {
+        while offset > 0 {
+            let end = offset;
+            let prev = self.prev_grapheme(offset);
+            let boundary = self.word_boundary(prev);
+            offset = boundary.start.min(prev);
+            if !self.text[offset..end].trim().is_empty() {
+                break;
+            }
+        }
+        offset
+    }
+
+    fn word_boundary(&mut self, offset: usize) -> Range<usize> {
+        let index = self.block_index(offset);
+        let utf16 = self.blocks[index].utf16_offset(offset) as u32;
+        let range = self.block_paragraph(index).get_word_boundary(utf16);
+        let block = &self.blocks[index];
+        block.utf8_offset(range.start)..block.utf8_offset(range.end)
+    }
+
+    /// The direction of the text at the caret position `offset`.
+    fn direction_at(&mut self, offset: usize) -> TextDirection {
+        let len = self.text.len();
+        if len == 0 {
+            return self.paragraph_style.text_direction();
+        }
+        let index = if offset >= len {
+            prev_char_boundary(&self.text, len)
+        } else {
+            offset
+        };
+        self.cluster_at(index)
+            .map(|(_, direction)| direction)
+            .unwrap_or_else(|| self.paragraph_style.text_direction())
+    }
+
+    /// Returns the text position on the line above or below the caret at the horizontal
+    /// position `x`.
+    fn vertical_target(&mut self, caret: Rect, x: scalar, down: bool) -> (usize, Affinity) {
+        let lines = self.lines();
+        let target = line_at(&lines, caret.center_y()).and_then(|n| {
+            if down {
+                lines.get(n + 1)
+            } else {
+                n.checked_sub(1).and_then(|n| lines.get(n))
+            }
+        });
+        match target.map(|target| target.baseline) {
+            Some(y) => self.hit_test((x, y)),
+            None if down => (self.text.len(), Affinity::Downstream),
+            None => (0, Affinity::Downstream),
+        }
+    }
+
+    /// Returns the caret position one grapheme cluster to the left or to the right of the caret
+    /// in visual order. At the edge of a line, the caret wraps to the opposite edge of the
+    /// previous or next line.
+    fn visual_neighbor(&mut self, right: bool) -> (usize, Affinity) {
+        let caret = self.caret_rect();
+        let (x, y) = (caret.left, caret.center_y());
+
+        let probe = if right { x + 0.5 } else { x - 0.5 };
+        let bounds = self
+            .closest_cluster_bounds(Point::new(probe, y))
+            .filter(|bounds| {
+                let center = bounds.center_x();
+                if right {
+                    center > x
+                } else {
+                    center < x
+                }
+            });
+        if let Some(bounds) = bounds {
+            // A point in the cluster close to its far edge resolves to the caret position at
+            // that edge, regardless of the direction of the cluster.
+            let x = if right {
+                bounds.right - bounds.width() / 4.0
+            } else {
+                bounds.left + bounds.width() / 4.0
+            };
+            return self.hit_test((x, y));
+        }
+
+        let lines = self.lines();
+        let ltr = self.paragraph_style.text_direction() == TextDirection::LTR;
+        let target = line_at(&lines, y).and_then(|n| {
+            if right == ltr {
+                lines.get(n + 1)
+            } else {
+                n.checked_sub(1).and_then(|n| lines.get(n))
+            }
+        });
+        match target {
+            Some(line) => {
+                let x = if right {
+                    line.left
+                } else {
+                    line.left + line.width
+                };
+                self.hit_test((x, line.baseline))
+            }
+            None => (self.selection.focus, self.selection.affinity),
+        }
+    }
+
+    fn line_boundary(&mut self, end: bool) -> (usize, Affinity) {
+        let caret = self.caret_rect();
+        let lines = self.lines();
+        let boundary = line_at(&lines, caret.center_y()).map(|n| {
+            let line = &lines[n];
+            if end {
+                // At the end of a soft wrapped line, the caret stays on that line.
+                let affinity = if line.hard_break {
+                    Affinity::Downstream
+                } else {
+                    Affinity::Upstream
+                };
+                (line.range.end, affinity)
+            } else {
+                (line.range.start, Affinity::Downstream)
+            }
+        });
+        boundary.unwrap_or((self.selection.focus, self.selection.affinity))
+    }
+
+    fn clamp_offset(&self, offset: usize) -> usize {
+        let offset = offset.min(self.text.len());
+        if self.text.is_char_boundary(offset) {
+            offset
+        } else {
+            prev_char_boundary(&self.text, offset)
+        }
+    }
+
+    fn clamp_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.clamp_offset(range.start);
+        start..self.clamp_offset(range.end).max(start)
+    }
+}
+
+/// The text between two hard line breaks, laid out as one paragraph.
+#[derive(Debug)]
+struct Block {
+    /// The range of the block in the text, without the line break that ends it.
+    range: Range<usize>,
+    offsets: OffsetMap,
+    paragraph: Option<Paragraph>,
+    laid_out_width: Option<scalar>,
+}
+
+impl Block {
+    /// Splits `range` of `text` into blocks at the hard line breaks.
+    fn split(text: &str, range: Range<usize>) -> Vec<Block> {
+        let mut start = range.start;
+        text[range]
+            .split('\n')
+            .map(|block_text| {
+                let range = start..start + block_text.len();
+                start = range.end + 1;
+                Block {
+                    range,
+                    offsets: OffsetMap::new(block_text),
+                    paragraph: None,
+                    laid_out_width: None,
+                }
+            })
+            .collect()
+    }
+
+    /// The UTF-16 offset in the paragraph of the block of the text offset `utf8`.
+    fn utf16_offset(&self, utf8: usize) -> usize {
+        self.offsets.utf16(utf8.saturating_sub(self.range.start))
+    }
+
+    /// The text offset of the UTF-16 offset `utf16` in the paragraph of the block.
+    fn utf8_offset(&self, utf16: usize) -> usize {
+        self.range.start + self.offsets.utf8(utf16)
+    }
+}
+
+/// A laid out line of a block.
+struct Line {
+    range: Range<usize>,
+    left: scalar,
+    width: scalar,
+    baseline: scalar,
+    ascent: scalar,
+    descent: scalar,
+    /// The line ends with a hard line break or at the end of the text.
+    hard_break: bool,
+}
+
+/// Maps between the UTF-8 offsets of the text of a block and the UTF-16 offsets `Paragraph` uses
+/// in constant time. It is rebuilt whenever the text of the block changes.
+#[derive(Clone, Debug)]
+struct OffsetMap {
+    /// The UTF-16 offset of every UTF-8 offset, including the end of the text. Offsets inside a
+    /// character map to the start of the character.
+    utf16: Vec<usize>,
+    /// The UTF-8 offset of every UTF-16 offset, including the end of the text.
+    utf8: Vec<usize>,
+}
+
+impl OffsetMap {
+    fn new(text: &str) -> Self {
+        let mut utf16 = Vec::with_capacity(text.len() + 1);
+        let mut utf8 = Vec::with_capacity(text.len() + 1);
+        for (i, c) in text.char_indices() {
+            let offset = utf8.len();
+            utf16.resize(utf16.len() + c.len_utf8(), offset);
+            utf8.resize(offset + c.len_utf16(), i);
+        }
+        utf16.push(utf8.len());
+        utf8.push(text.len());
+        Self { utf16, utf8 }
+    }
+
+    fn utf16(&self, utf8: usize) -> usize {
+        self.utf16[utf8.min(self.utf16.len() - 1)]
+    }
+
+    fn utf8(&self, utf16: usize) -> usize {
+        self.utf8[utf16.min(self.utf8.len() - 1)]
+    }
+}
+
+/// Returns the index of the line that contains the vertical position `y`.
+fn line_at(lines: &[Line], y: scalar) -> Option<usize> {
+    lines
+        .iter()
+        .position(|line| y <= line.baseline + line.descent)
+        .or_else(|| lines.len().checked_sub(1))
+}
+
+fn next_char_boundary(text: &str, offset: usize) -> usize {
+    text[offset..]
+        .chars()
+        .next()
+        .map_or(text.len(), |c| offset + c.len_utf8())
+}
+
+fn prev_char_boundary(text: &str, offset: usize) -> usize {
+    text[..offset]
+        .chars()
+        .next_back()
+        .map_or(0, |c| offset - c.len_utf8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CaretMovement, EditableParagraph, TextSelection};
+    use crate::{
+        icu,
+        textlayout::{FontCollection, ParagraphStyle, TextStyle},
+        FontMgr,
+    };
+
+    fn editable_paragraph() -> EditableParagraph {
+        icu::init();
+
+        let mut font_collection = FontCollection::new();
+        font_collection.set_default_font_manager(FontMgr::new(), None);
+        let mut paragraph_style = ParagraphStyle::new();
+        let mut text_style = TextStyle::new();
+        text_style.set_font_size(20.0);
+        paragraph_style.set_text_style(&text_style);
+        let mut editable = EditableParagraph::new(&paragraph_style, font_collection);
+        editable.set_width(200.0);
+        editable
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn insert_and_delete() {
+        let mut editable = editable_paragraph();
+        editable.insert("Hello").insert(" World");
+        assert_eq!(editable.text(), "Hello World");
+        assert_eq!(editable.selection(), TextSelection::caret(11));
+
+        editable.delete_backward();
+        assert_eq!(editable.text(), "Hello Worl");
+
+        editable.set_selection(TextSelection::new(0, 6));
+        editable.delete_forward();
+        assert_eq!(editable.text(), "Worl");
+        assert_eq!(editable.style_runs().len(), 1);
+        assert_eq!(editable.style_runs()[0].range, 0..4);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn caret_moves_over_grapheme_clusters() {
+        let mut editable = editable_paragraph();
+        // "e" followed by a combining acute accent.
+        editable.set_text("ae\u{301}b");
+        editable.set_selection(TextSelection::caret(1));
+        editable.move_caret(CaretMovement::Right, false);
+        assert_eq!(editable.selection().focus, 4);
+        editable.move_caret(CaretMovement::Left, true);
+        assert_eq!(editable.selection(), TextSelection::new(4, 1));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn caret_moves_across_lines() {
+        let mut editable = editable_paragraph();
+        // A character outside of the BMP is two UTF-16 code units.
+        editable.set_text("a\u{1d431}\nb");
+        editable.set_selection(TextSelection::caret(1));
+        editable.move_caret(CaretMovement::Right, false);
+        assert_eq!(editable.selection().focus, 5);
+        editable.move_caret(CaretMovement::Right, false);
+        assert_eq!(editable.selection().focus, 6);
+        editable.move_caret(CaretMovement::Left, false);
+        assert_eq!(editable.selection().focus, 5);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn caret_moves_visually_in_right_to_left_text() {
+        let mut editable = editable_paragraph();
+        // Hebrew letters are two bytes long, the first letter is displayed at the right.
+        editable.set_text("\u{5e9}\u{5dc}\u{5d5}\u{5dd}");
+        editable.set_selection(TextSelection::caret(0));
+        let start = editable.caret_rect().left;
+        editable.move_caret(CaretMovement::Left, false);
+        assert_eq!(editable.selection().focus, 2);
+        editable.move_caret(CaretMovement::Left, false);
+        assert_eq!(editable.selection().focus, 4);
+        assert!(editable.caret_rect().left < start);
+        editable.move_caret(CaretMovement::Right, false);
+        assert_eq!(editable.selection().focus, 2);
+        editable.move_caret(CaretMovement::Right, false);
+        assert_eq!(editable.selection().focus, 0);
+
+        // A selection collapses to its visual side.
+        editable.set_selection(TextSelection::new(2, 6));
+        editable.move_caret(CaretMovement::Left, false);
+        assert_eq!(editable.selection(), TextSelection::caret(6));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn edits_rebuild_only_the_touched_blocks() {
+        let mut editable = editable_paragraph();
+        editable.set_text("one\ntwo\nthree");
+        let height = editable.height();
+        assert_eq!(editable.paragraphs().count(), 3);
+
+        editable.set_selection(TextSelection::caret(7));
+        editable.insert("s");
+        assert_eq!(editable.text(), "one\ntwos\nthree");
+        assert!(editable.blocks[0].paragraph.is_some());
+        assert!(editable.blocks[1].paragraph.is_none());
+        assert!(editable.blocks[2].paragraph.is_some());
+        assert_eq!(editable.blocks[2].range, 9..14);
+        assert_eq!(editable.height(), height);
+
+        // A new line break splits the block, the caret is on the new empty line.
+        editable.insert("\n");
+        assert_eq!(editable.paragraphs().count(), 4);
+        assert!(editable.height() > height);
+        let caret = editable.caret_rect();
+        editable.move_caret(CaretMovement::Up, false);
+        let above = editable.caret_rect();
+        editable.move_caret(CaretMovement::TextEnd, false);
+        let below = editable.caret_rect();
+        assert!(above.center_y() < caret.center_y());
+        assert!(caret.center_y() < below.center_y());
+
+        editable.set_selection(TextSelection::new(3, 10));
+        editable.delete_backward();
+        assert_eq!(editable.text(), "onethree");
+        assert_eq!(editable.paragraphs().count(), 1);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn style_runs_follow_edits() {
+        let mut editable = editable_paragraph();
+        editable.set_text("one two three");
+        let mut large = editable.style_runs()[0].style.clone();
+        large.set_font_size(30.0);
+        editable.set_style(4..7, &large);
+        assert_eq!(editable.style_runs().len(), 3);
+
+        editable.set_selection(TextSelection::caret(7));
+        editable.insert("s");
+        let runs = editable.style_runs();
+        assert_eq!(runs[1].range, 4..8);
+        assert_eq!(runs[2].range, 8..14);
+
+        editable.set_selection(TextSelection::new(3, 9));
+        editable.delete_backward();
+        assert_eq!(editable.text(), "onethree");
+        assert_eq!(editable.style_runs().len(), 1);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn composing_text() {
+        let mut editable = editable_paragraph();
+        editable.insert("a");
+        editable.set_composing_text("k").set_composing_text("ka");
+        assert_eq!(editable.text(), "aka");
+        assert_eq!(editable.composing_range(), Some(1..3));
+        assert!(!editable.composing_rects().is_empty());
+        editable.commit_composing();
+        assert_eq!(editable.composing_range(), None);
+        assert_eq!(editable.selection(), TextSelection::caret(3));
+    }
+}
@@ -0,0 +1,552 @@
+use std::{collections::HashMap, ops::Range};
+
+use super::{FontCollection, Paragraph, ParagraphBuilder, ParagraphStyle, TextStyle};
+use crate::{scalar, Data};
+
+const SOFT_HYPHEN: char = '\u{ad}';
+const HYPHEN: &str = "-";
+
+/// Finds hyphenation points in words with Liang's algorithm, using TeX pattern dictionaries.
+#[derive(Clone, Debug)]
+pub struct Hyphenator {
+    /// The letters of a pattern mapped to the values between them.
+    patterns: HashMap<String, Vec<u8>>,
+    max_pattern_len: usize,
+    /// Words that are hyphenated explicitly, mapped to their break positions.
+    exceptions: HashMap<String, Vec<usize>>,
+    left_min: usize,
+    right_min: usize,
+}
+
+impl Hyphenator {
+    /// Loads a pattern dictionary in the TeX format, for example one of the `hyph-*.tex` files of
+    /// the hyph-utf8 project.
+    ///
+    /// The patterns are read from a `\patterns{...}` group, and exceptions from an optional
+    /// `\hyphenation{...}` group. If there is no `\patterns` group, the whole file is read as
+    /// whitespace separated patterns. Returns `None` if the data is not valid UTF-8 or contains no
+    /// patterns.
+    pub fn from_data(data: &Data) -> Option<Self> {
+        let text = std::str::from_utf8(data.as_bytes()).ok()?;
+        Self::from_tex(text)
+    }
+
+    /// Parses a pattern dictionary in the TeX format. See [`Self::from_data()`].
+    pub fn from_tex(tex: &str) -> Option<Self> {
+        let text: String = tex
+            .lines()
+            .map(|line| line.split('%').next().unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let patterns = group(&text, "\\patterns").unwrap_or(&text);
+        let exceptions = group(&text, "\\hyphenation").unwrap_or("");
+
+        let hyphenator = Self::new(patterns.split_whitespace(), exceptions.split_whitespace());
+        (!hyphenator.patterns.is_empty()).then_some(hyphenator)
+    }
+
+    /// Creates a hyphenator from Liang patterns like `"1na"` or `".hy3ph"`, and exceptions like
+    /// `"ta-ble"`.
+    pub fn new<'a>(
+        patterns: impl IntoIterator<Item = &'a str>,
+        exceptions: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        let mut hyphenator = Self {
+            patterns: HashMap::new(),
+            max_pattern_len: 0,
+            exceptions: HashMap::new(),
+            left_min: 2,
+            right_min: 3,
+        };
+        for pattern in patterns {
+            hyphenator.add_pattern(pattern);
+        }
+        for exception in exceptions {
+            hyphenator.add_exception(exception);
+        }
+        hyphenator
+    }
+
+    /// Sets the minimum number of characters before the first and after the last hyphen of a word,
+    /// `2` and `3` by default.
+    pub fn set_min(&mut self, left_min: usize, right_min: usize) -> &mut Self {
+        self.left_min = left_min.max(1);
+        self.right_min = right_min.max(1);
+        self
+    }
+
+    pub fn add_pattern(&mut self, pattern: &str) -> &mut Self {
+        let mut letters = String::new();
+        let mut values = vec![0];
+        for c in pattern.chars() {
+            match c.to_digit(10) {
+                Some(value) => *values.last_mut().unwrap() = value as u8,
+                None => {
+                    letters.extend(c.to_lowercase());
+                    values.push(0);
+                }
+            }
+        }
+        if !letters.is_empty() {
+            self.max_pattern_len = self.max_pattern_len.max(letters.chars().count());
+            self.patterns.insert(letters, values);
+        }
+        self
+    }
+
+    /// Adds a word that is hyphenated at the positions marked with `-`.
+    pub fn add_exception(&mut self, exception: &str) -> &mut Self {
+        let mut word = String::new();
+        let mut breaks = Vec::new();
+        for c in exception.chars() {
+            if c == '-' {
+                breaks.push(word.chars().count());
+            } else {
+                word.extend(c.to_lowercase());
+            }
+        }
+        self.exceptions.insert(word, breaks);
+        self
+    }
+
+    /// Returns the UTF-8 byte offsets in `word` at which it can be hyphenated.
+    pub fn hyphenate(&self, word: &str) -> Vec<usize> {
+        let chars: Vec<(usize, char)> = word.char_indices().collect();
+        let len = chars.len();
+        if len < self.left_min + self.right_min {
+            return Vec::new();
+        }
+
+        let lower: String = chars
+            .iter()
+            .map(|(_, c)| c.to_lowercase().next().unwrap_or(*c))
+            .collect();
+
+        let positions: Vec<usize> = match self.exceptions.get(&lower) {
+            Some(breaks) => breaks.clone(),
+            None => {
+                let dotted: Vec<char> = std::iter::once('.')
+                    .chain(lower.chars())
+                    .chain(std::iter::once('.'))
+                    .collect();
+                // values[i] is the value before dotted[i].
+                let mut values = vec![0u8; dotted.len() + 1];
+                let mut key = String::new();
+                for start in 0..dotted.len() {
+                    key.clear();
+                    let end = dotted.len().min(start + self.max_pattern_len);
+                    for &c in &dotted[start..end] {
+                        key.push(c);
+                        if let Some(pattern) = self.patterns.get(&key) {
+                            for (i, value) in pattern.iter().enumerate() {
+                                let v = &mut values[start + i];
+                                *v = (*v).max(*value);
+                            }
+                        }
+                    }
+                }
+                // The break before the character at word index i is at dotted index i + 1.
+                (1..len).filter(|i| values[i + 1] % 2 == 1).collect()
+            }
+        };
+
+        positions
+            .into_iter()
+            .filter(|i| *i >= self.left_min && *i + self.right_min <= len)
+            .map(|i| chars[i].0)
+            .collect()
+    }
+
+    /// Returns `text` with soft hyphens (U+00AD) inserted at the hyphenation points of its words.
+    pub fn insert_soft_hyphens(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        for (range, is_word) in words(text) {
+            let word = &text[range];
+            if !is_word {
+                result.push_str(word);
+                continue;
+            }
+            let mut last = 0;
+            for offset in self.hyphenate(word) {
+                result.push_str(&word[last..offset]);
+                result.push(SOFT_HYPHEN);
+                last = offset;
+            }
+            result.push_str(&word[last..]);
+        }
+        result
+    }
+}
+
+/// Returns the content of the first `{...}` group that follows `command`.
+fn group<'a>(text: &'a str, command: &str) -> Option<&'a str> {
+    let start = text.find(command)? + command.len();
+    let rest = text[start..].trim_start().strip_prefix('{')?;
+    Some(&rest[..rest.find('}')?])
+}
+
+/// Splits `text` into words made of alphabetic characters and the text in between.
+fn words(text: &str) -> Vec<(Range<usize>, bool)> {
+    let mut segments: Vec<(Range<usize>, bool)> = Vec::new();
+    for (i, c) in text.char_indices() {
+        let is_word = c.is_alphabetic();
+        let end = i + c.len_utf8();
+        match segments.last_mut() {
+            Some((range, w)) if *w == is_word => range.end = end,
+            _ => segments.push((i..end, is_word)),
+        }
+    }
+    segments
+}
+
+/// Builds paragraphs whose words are hyphenated at line breaks.
+///
+/// The [`Hyphenator`] for a text run is chosen by the language of the [`TextStyle::locale()`] of
+/// the run. Runs without a matching hyphenator are not hyphenated. At every line break that falls
+/// on a hyphenation point, a hyphen is inserted in the style of the hyphenated word.
+#[derive(Debug)]
+pub struct HyphenatingParagraphBuilder {
+    paragraph_style: ParagraphStyle,
+    font_collection: FontCollection,
+    hyphenators: HashMap<String, Hyphenator>,
+    runs: Vec<(String, TextStyle)>,
+}
+
+impl HyphenatingParagraphBuilder {
+    pub fn new(
+        paragraph_style: &ParagraphStyle,
+        font_collection: impl Into<FontCollection>,
+    ) -> Self {
+        Self {
+            paragraph_style: paragraph_style.clone(),
+            font_collection: font_collection.into(),
+            hyphenators: HashMap::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    /// Uses `hyphenator` for text runs with a locale of `language`, for example `"de"`.
+    ///
+    /// A hyphenator for a language like `"de"` is also used for locales like `"de-CH"`, unless
+    /// there is a hyphenator for the full locale.
+    pub fn set_hyphenator(
+        &mut self,
+        language: impl Into<String>,
+        hyphenator: Hyphenator,
+    ) -> &mut Self {
+        self.hyphenators
+            .insert(language.into().to_ascii_lowercase(), hyphenator);
+        self
+    }
+
+    pub fn add_text(&mut self, text: impl Into<String>, style: &TextStyle) -> &mut Self {
+        self.runs.push((text.into(), style.clone()));
+        self
+    }
+
+    /// Builds the paragraph and lays it out with `width`.
+    ///
+    /// The text of the paragraph differs from the added text at the hyphenation points, see
+    /// [`HyphenatedParagraph`].
+    pub fn build(&self, width: scalar) -> HyphenatedParagraph {
+        // The runs with soft hyphens and the UTF-16 offsets of the soft hyphens in the text.
+        let mut runs: Vec<String> = Vec::with_capacity(self.runs.len());
+        let mut soft_hyphens: Vec<SoftHyphen> = Vec::new();
+        let mut offset = 0;
+        for (run, (text, style)) in self.runs.iter().enumerate() {
+            let text = match self.hyphenator(style.locale()) {
+                Some(hyphenator) => hyphenator.insert_soft_hyphens(text),
+                None => text.clone(),
+            };
+            for (i, c) in text.char_indices() {
+                if c == SOFT_HYPHEN {
+                    soft_hyphens.push(SoftHyphen {
+                        run,
+                        byte_offset: i,
+                        utf16_offset: offset,
+                        layout_offset: offset,
+                        state: HyphenState::Unused,
+                    });
+                }
+                offset += c.len_utf16();
+            }
+            runs.push(text);
+        }
+
+        let mut hyphen_widths: HashMap<usize, scalar> = HashMap::new();
+
+        // A line that breaks at a soft hyphen gets a visible hyphen if it is still narrow enough.
+        // Otherwise, the soft hyphen is removed and the paragraph is laid out again.
+        let mut paragraph = loop {
+            let mut paragraph = self.build_runs(&runs, &soft_hyphens, false);
+            paragraph.layout(width);
+
+            // Removed soft hyphens shift the offsets of the following ones.
+            let mut removed = 0;
+            for hyphen in &mut soft_hyphens {
+                hyphen.layout_offset = hyphen.utf16_offset - removed;
+                if hyphen.state == HyphenState::Removed {
+                    removed += 1;
+                }
+            }
+
+            let mut changed = false;
+            for line in paragraph.get_line_metrics() {
+                let end = line.end_excluding_whitespaces;
+                let Some(hyphen) = soft_hyphens
+                    .iter_mut()
+                    .find(|h| h.state != HyphenState::Removed && h.layout_offset + 1 == end)
+                else {
+                    continue;
+                };
+                let hyphen_width = *hyphen_widths
+                    .entry(hyphen.run)
+                    .or_insert_with(|| self.hyphen_width(&self.runs[hyphen.run].1));
+                if line.width as scalar + hyphen_width <= width {
+                    hyphen.state = HyphenState::Visible;
+                } else {
+                    hyphen.state = HyphenState::Removed;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break paragraph;
+            }
+            // Visible hyphens may move when an earlier line changes.
+            for hyphen in &mut soft_hyphens {
+                if hyphen.state == HyphenState::Visible {
+                    hyphen.state = HyphenState::Unused;
+                }
+            }
+        };
+
+        if soft_hyphens.iter().any(|h| h.state == HyphenState::Visible) {
+            // Lines that break after a hyphen that replaces a soft hyphen are not wider than the
+            // width, so the line breaks stay the same.
+            paragraph = self.build_runs(&runs, &soft_hyphens, true);
+            paragraph.layout(width);
+        }
+
+        let mut inserted = Vec::new();
+        let mut hyphens = Vec::new();
+        let mut removed = 0;
+        for hyphen in &soft_hyphens {
+            let offset = hyphen.utf16_offset - removed;
+            match hyphen.state {
+                HyphenState::Removed => removed += 1,
+                HyphenState::Visible => {
+                    inserted.push(offset);
+                    hyphens.push(offset);
+                }
+                HyphenState::Unused => inserted.push(offset),
+            }
+        }
+        HyphenatedParagraph {
+            paragraph,
+            inserted,
+            hyphens,
+        }
+    }
+
+    fn hyphenator(&self, locale: &str) -> Option<&Hyphenator> {
+        let locale = locale.to_ascii_lowercase().replace('_', "-");
+        self.hyphenators.get(&locale).or_else(|| {
+            let language = locale.split('-').next()?;
+            self.hyphenators.get(language)
+        })
+    }
+
+    fn build_runs(
+        &self,
+        runs: &[String],
+        soft_hyphens: &[SoftHyphen],
+        visible_hyphens: bool,
+    ) -> Paragraph {
+        let mut builder =
+            ParagraphBuilder::new(&self.paragraph_style, self.font_collection.clone());
+        for (run, (text, (_, style))) in runs.iter().zip(&self.runs).enumerate() {
+            builder.push_style(style);
+            let mut last = 0;
+            for hyphen in soft_hyphens.iter().filter(|h| h.run == run) {
+                let replacement = match hyphen.state {
+                    HyphenState::Visible if visible_hyphens => HYPHEN,
+                    HyphenState::Removed => "",
+                    _ => continue,
+                };
+                builder.add_text(&text[last..hyphen.byte_offset]);
+                builder.add_text(replacement);
+                last = hyphen.byte_offset + SOFT_HYPHEN.len_utf8();
+            }
+            builder.add_text(&text[last..]);
+            builder.pop();
+        }
+        builder.build()
+    }
+
+    fn hyphen_width(&self, style: &TextStyle) -> scalar {
+        let mut builder =
+            ParagraphBuilder::new(&self.paragraph_style, self.font_collection.clone());
+        builder.push_style(style);
+        builder.add_text(HYPHEN);
+        let mut paragraph = builder.build();
+        paragraph.layout(scalar::INFINITY);
+        paragraph.max_intrinsic_width()
+    }
+}
+
+/// A paragraph built by [`HyphenatingParagraphBuilder`].
+///
+/// The text of the paragraph is not the added text: at every hyphenation point that does not
+/// make a line too wide, a soft hyphen (U+00AD) is inserted, which is replaced by a hyphen (`-`)
+/// where a line breaks. The UTF-16 offsets of the paragraph, for example those of
+/// [`Paragraph::get_line_metrics()`] or [`Paragraph::get_glyph_position_at_coordinate()`], are
+/// therefore shifted by one for every inserted character before them. Use
+/// [`Self::text_offset()`] and [`Self::paragraph_offset()`] to convert them.
+#[derive(Debug)]
+pub struct HyphenatedParagraph {
+    paragraph: Paragraph,
+    /// The UTF-16 offsets of the inserted characters in the paragraph.
+    inserted: Vec<usize>,
+    /// The UTF-16 offsets of the visible hyphens in the paragraph.
+    hyphens: Vec<usize>,
+}
+
+impl HyphenatedParagraph {
+    /// The paragraph, which is laid out with the width it was built for.
+    pub fn paragraph(&self) -> &Paragraph {
+        &self.paragraph
+    }
+
+    /// Returns the paragraph. Its hyphens are not updated if it is laid out again.
+    pub fn into_paragraph(self) -> Paragraph {
+        self.paragraph
+    }
+
+    /// The UTF-16 offsets of the hyphens that were inserted at line breaks, in the paragraph.
+    pub fn hyphens(&self) -> &[usize] {
+        &self.hyphens
+    }
+
+    /// Converts a UTF-16 offset in the paragraph to the UTF-16 offset in the added text. The
+    /// offset of an inserted character is converted to the offset of the character that follows
+    /// it.
+    pub fn text_offset(&self, paragraph_offset: usize) -> usize {
+        paragraph_offset
+            - self
+                .inserted
+                .partition_point(|offset| *offset < paragraph_offset)
+    }
+
+    /// Converts a UTF-16 offset in the added text to the UTF-16 offset in the paragraph.
+    pub fn paragraph_offset(&self, text_offset: usize) -> usize {
+        // The n-th inserted character is at its text offset + n, before the character at the
+        // text offset.
+        let count = self
+            .inserted
+            .iter()
+            .enumerate()
+            .take_while(|(n, offset)| *offset - n <= text_offset)
+            .count();
+        text_offset + count
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum HyphenState {
+    /// An invisible soft hyphen.
+    Unused,
+    /// The soft hyphen is replaced by a visible hyphen.
+    Visible,
+    /// The soft hyphen is removed, because the line would be too wide with a visible hyphen.
+    Removed,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct SoftHyphen {
+    run: usize,
+    byte_offset: usize,
+    /// The UTF-16 offset in the text with all soft hyphens.
+    utf16_offset: usize,
+    /// The UTF-16 offset in the text of the current layout pass.
+    layout_offset: usize,
+    state: HyphenState,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HyphenatingParagraphBuilder, Hyphenator};
+    use crate::{
+        icu,
+        textlayout::{FontCollection, ParagraphStyle, TextStyle},
+        FontMgr,
+    };
+
+    const PATTERNS: &str = r"
+        % A few patterns from Liang's thesis.
+        \patterns{
+            hy3ph he2n hena4 hen5at 1na n2at 1tio 2io o2n
+        }
+        \hyphenation{ ta-ble }
+    ";
+
+    #[test]
+    fn liang_patterns() {
+        let hyphenator = Hyphenator::from_tex(PATTERNS).unwrap();
+        assert_eq!(
+            hyphenator.insert_soft_hyphens("Hyphenation, table!"),
+            "Hy\u{ad}phen\u{ad}ation, ta\u{ad}ble!"
+        );
+        let mut hyphenator = hyphenator;
+        hyphenator.set_min(1, 1);
+        assert_eq!(hyphenator.hyphenate("table"), [2]);
+        assert_eq!(hyphenator.hyphenate("hyphenation"), [2, 6]);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn hyphenate_narrow_paragraph() {
+        icu::init();
+
+        let mut font_collection = FontCollection::new();
+        font_collection.set_default_font_manager(FontMgr::new(), None);
+        let mut style = TextStyle::new();
+        style.set_font_size(20.0).set_locale("en-US");
+
+        let mut builder = HyphenatingParagraphBuilder::new(&ParagraphStyle::new(), font_collection);
+        builder
+            .set_hyphenator("en", Hyphenator::from_tex(PATTERNS).unwrap())
+            .add_text("hyphenation hyphenation hyphenation", &style);
+        let width = 100.0;
+        let hyphenated = builder.build(width);
+        let paragraph = hyphenated.paragraph();
+        let lines = paragraph.get_line_metrics();
+        assert!(lines.len() > 1);
+        for line in &lines {
+            assert!(line.width as f32 <= width);
+        }
+
+        // Every hyphen ends a line, and at least one line ends with a hyphen.
+        assert!(!hyphenated.hyphens().is_empty());
+        for hyphen in hyphenated.hyphens() {
+            assert!(lines
+                .iter()
+                .any(|line| line.end_excluding_whitespaces == hyphen + 1));
+        }
+
+        // The offsets of the paragraph skip the inserted hyphens.
+        for hyphen in hyphenated.hyphens() {
+            let offset = hyphenated.text_offset(*hyphen);
+            assert_eq!(hyphenated.text_offset(hyphen + 1), offset);
+            assert_eq!(hyphenated.paragraph_offset(offset), hyphen + 1);
+            assert_eq!(hyphenated.paragraph_offset(offset - 1), hyphen - 1);
+        }
+        let len = "hyphenation hyphenation hyphenation".len();
+        for offset in 0..=len {
+            assert_eq!(
+                hyphenated.text_offset(hyphenated.paragraph_offset(offset)),
+                offset
+            );
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use super::{
+    FontCollection, Paragraph, ParagraphBuilder, ParagraphStyle, PlaceholderStyle, RectHeightStyle,
+    RectWidthStyle,
+};
+use crate::{matrix::ScaleToFit, scalar, Canvas, Drawable, Matrix, Point, Rect};
+
+/// The content of a placeholder that is painted together with its paragraph.
+pub enum InlineWidget {
+    /// A drawable that is scaled to fit and centered in the placeholder.
+    Drawable(Drawable),
+    /// A callback that draws into the canvas translated to the top left corner of the placeholder.
+    /// It receives the bounds of the placeholder at the origin.
+    Callback(Box<dyn Fn(&Canvas, Rect)>),
+}
+
+impl fmt::Debug for InlineWidget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Drawable(drawable) => f.debug_tuple("Drawable").field(drawable).finish(),
+            Self::Callback(_) => f.debug_tuple("Callback").finish(),
+        }
+    }
+}
+
+impl From<Drawable> for InlineWidget {
+    fn from(drawable: Drawable) -> Self {
+        Self::Drawable(drawable)
+    }
+}
+
+impl InlineWidget {
+    pub fn from_fn(f: impl Fn(&Canvas, Rect) + 'static) -> Self {
+        Self::Callback(Box::new(f))
+    }
+
+    fn paint(&self, canvas: &Canvas, rect: Rect) {
+        match self {
+            Self::Drawable(drawable) => {
+                let mut drawable = drawable.clone();
+                let bounds = drawable.bounds();
+                let matrix = if bounds.is_empty() {
+                    Matrix::translate((rect.left, rect.top))
+                } else {
+                    Matrix::rect_to_rect(bounds, rect, ScaleToFit::Center)
+                        .unwrap_or_else(|| Matrix::translate((rect.left, rect.top)))
+                };
+                canvas.draw_drawable(&mut drawable, Some(&matrix));
+            }
+            Self::Callback(f) => {
+                canvas.save();
+                canvas.translate((rect.left, rect.top));
+                f(canvas, Rect::from_size(rect.size()));
+                canvas.restore();
+            }
+        }
+    }
+}
+
+/// A [`ParagraphBuilder`] that adds placeholders with [`InlineWidget`]s.
+///
+/// All the functions of [`ParagraphBuilder`] are available, placeholders added with
+/// [`ParagraphBuilder::add_placeholder()`] stay empty.
+#[derive(Debug)]
+pub struct WidgetParagraphBuilder {
+    builder: ParagraphBuilder,
+    /// The UTF-16 offsets of the placeholders and their widgets.
+    widgets: Vec<(usize, InlineWidget)>,
+}
+
+impl Deref for WidgetParagraphBuilder {
+    type Target = ParagraphBuilder;
+
+    fn deref(&self) -> &Self::Target {
+        &self.builder
+    }
+}
+
+impl DerefMut for WidgetParagraphBuilder {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.builder
+    }
+}
+
+impl WidgetParagraphBuilder {
+    pub fn new(style: &ParagraphStyle, font_collection: impl Into<FontCollection>) -> Self {
+        Self {
+            builder: ParagraphBuilder::new(style, font_collection),
+            widgets: Vec::new(),
+        }
+    }
+
+    /// Adds a placeholder of `placeholder_style` that shows `widget`.
+    pub fn add_widget(
+        &mut self,
+        placeholder_style: &PlaceholderStyle,
+        widget: impl Into<InlineWidget>,
+    ) -> &mut Self {
+        let offset = self.builder.get_text().encode_utf16().count();
+        self.builder.add_placeholder(placeholder_style);
+        self.widgets.push((offset, widget.into()));
+        self
+    }
+
+    pub fn build(&mut self) -> WidgetParagraph {
+        WidgetParagraph {
+            paragraph: self.builder.build(),
+            widgets: std::mem::take(&mut self.widgets),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.builder.reset();
+        self.widgets.clear();
+    }
+}
+
+/// A [`Paragraph`] that paints the [`InlineWidget`]s of its placeholders.
+///
+/// The widgets are drawn into the canvas passed to [`WidgetParagraph::paint()`], so they are
+/// recorded, too, when the canvas belongs to a [`crate::PictureRecorder`].
+///
+/// The functions of [`Paragraph`] that do not change it are available. Note that the widgets are
+/// painted only by [`WidgetParagraph::paint()`], [`Paragraph::paint()`] called through the
+/// dereferenced paragraph leaves the placeholders empty.
+#[derive(Debug)]
+pub struct WidgetParagraph {
+    paragraph: Paragraph,
+    widgets: Vec<(usize, InlineWidget)>,
+}
+
+impl Deref for WidgetParagraph {
+    type Target = Paragraph;
+
+    fn deref(&self) -> &Self::Target {
+        &self.paragraph
+    }
+}
+
+impl WidgetParagraph {
+    pub fn layout(&mut self, width: scalar) {
+        self.paragraph.layout(width)
+    }
+
+    pub fn widgets(&self) -> impl Iterator<Item = &InlineWidget> {
+        self.widgets.iter().map(|(_, widget)| widget)
+    }
+
+    /// Returns the bounds of the widget at `index` relative to the paragraph, `None` if it is not
+    /// laid out, for example because it is on a line past the maximum number of lines.
+    pub fn widget_rect(&self, index: usize) -> Option<Rect> {
+        let (offset, _) = self.widgets.get(index)?;
+        self.paragraph
+            .get_rects_for_range(
+                *offset..*offset + 1,
+                RectHeightStyle::Tight,
+                RectWidthStyle::Tight,
+            )
+            .first()
+            .map(|text_box| text_box.rect)
+    }
+
+    /// Paints the text and then the widgets of the paragraph at `p`.
+    pub fn paint(&self, canvas: &Canvas, p: impl Into<Point>) {
+        let p = p.into();
+        self.paragraph.paint(canvas, p);
+        for (index, (_, widget)) in self.widgets.iter().enumerate() {
+            if let Some(rect) = self.widget_rect(index) {
+                widget.paint(canvas, rect.with_offset(p));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::{InlineWidget, WidgetParagraphBuilder};
+    use crate::{
+        icu,
+        textlayout::{
+            FontCollection, ParagraphStyle, PlaceholderAlignment, PlaceholderStyle, TextBaseline,
+            TextStyle,
+        },
+        FontMgr, PictureRecorder, Rect,
+    };
+
+    #[test]
+    #[serial_test::serial]
+    fn widgets_are_painted_at_their_placeholders() {
+        icu::init();
+
+        let mut font_collection = FontCollection::new();
+        font_collection.set_default_font_manager(FontMgr::new(), None);
+        let mut style = TextStyle::new();
+        style.set_font_size(16.0);
+        let placeholder = PlaceholderStyle::new(
+            20.0,
+            10.0,
+            PlaceholderAlignment::Middle,
+            TextBaseline::Alphabetic,
+            0.0,
+        );
+
+        let painted = Rc::new(RefCell::new(Vec::new()));
+        let mut builder = WidgetParagraphBuilder::new(&ParagraphStyle::new(), font_collection);
+        builder.push_style(&style);
+        builder.add_text("Hello ");
+        let p = painted.clone();
+        builder.add_widget(
+            &placeholder,
+            InlineWidget::from_fn(move |canvas, bounds| {
+                p.borrow_mut()
+                    .push((bounds, canvas.local_to_device_as_3x3().translate_x()));
+            }),
+        );
+        builder.add_text(" world");
+        let mut paragraph = builder.build();
+        paragraph.layout(1000.0);
+
+        let rect = paragraph.widget_rect(0).unwrap();
+        assert_eq!(rect.width(), 20.0);
+        assert_eq!(rect.height(), 10.0);
+        assert!(rect.left > 0.0);
+
+        let mut recorder = PictureRecorder::new();
+        let canvas = recorder.begin_recording(Rect::from_wh(1000.0, 100.0), None);
+        paragraph.paint(canvas, (5.0, 0.0));
+        recorder.finish_recording_as_picture(None).unwrap();
+
+        let painted = painted.borrow();
+        assert_eq!(painted.len(), 1);
+        assert_eq!(painted[0].0, Rect::from_wh(20.0, 10.0));
+        assert_eq!(painted[0].1, rect.left + 5.0);
+    }
+}
@@ -0,0 +1,787 @@
+//! Rich text markup for [`ParagraphBuilder`].
+
+use std::{error::Error, fmt, ops::Range};
+
+use super::{
+    ParagraphBuilder, PlaceholderAlignment, PlaceholderStyle, TextBaseline, TextDecoration,
+    TextDecorationStyle, TextStyle,
+};
+use crate::{
+    font_style::{Slant, Weight},
+    scalar, Color, FontStyle, Paint,
+};
+
+/// A link that was added by [`ParagraphBuilder::add_markup()`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MarkupLink {
+    pub href: String,
+    /// The UTF-16 text range of the link in the paragraph.
+    pub range: Range<usize>,
+}
+
+/// A placeholder that was added for an `<img>` element by [`ParagraphBuilder::add_markup()`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct MarkupPlaceholder {
+    pub src: Option<String>,
+    /// The UTF-16 text range of the placeholder's replacement character in the paragraph.
+    pub range: Range<usize>,
+    pub width: scalar,
+    pub height: scalar,
+}
+
+/// The text ranges of the links and placeholders added by [`ParagraphBuilder::add_markup()`].
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct MarkupRanges {
+    pub links: Vec<MarkupLink>,
+    pub placeholders: Vec<MarkupPlaceholder>,
+}
+
+/// The reason [`ParagraphBuilder::add_markup()`] rejected the markup.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MarkupErrorKind {
+    UnexpectedEnd,
+    UnexpectedCharacter(char),
+    UnknownElement(String),
+    UnexpectedEndTag(String),
+    UnclosedElement(String),
+    MissingAttribute(&'static str),
+    InvalidAttribute(String),
+    InvalidCharacterReference(String),
+    InvalidStyle(String),
+}
+
+/// An error in the markup passed to [`ParagraphBuilder::add_markup()`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MarkupError {
+    /// The byte offset into the markup the error was detected at.
+    pub offset: usize,
+    pub kind: MarkupErrorKind,
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use MarkupErrorKind::*;
+        match &self.kind {
+            UnexpectedEnd => write!(f, "Unexpected end of markup"),
+            UnexpectedCharacter(c) => write!(f, "Unexpected character {c:?}"),
+            UnknownElement(name) => write!(f, "Unsupported element <{name}>"),
+            UnexpectedEndTag(name) => write!(f, "Unexpected end tag </{name}>"),
+            UnclosedElement(name) => write!(f, "Element <{name}> is not closed"),
+            MissingAttribute(name) => write!(f, "Missing attribute {name}"),
+            InvalidAttribute(attr) => write!(f, "Invalid attribute {attr}"),
+            InvalidCharacterReference(r) => write!(f, "Invalid character reference &{r};"),
+            InvalidStyle(style) => write!(f, "Invalid style {style:?}"),
+        }?;
+        write!(f, " at offset {}", self.offset)
+    }
+}
+
+impl Error for MarkupError {}
+
+impl ParagraphBuilder {
+    /// Adds the text, styles and placeholders described by `markup`, a small subset of HTML.
+    ///
+    /// The supported elements are:
+    ///
+    /// - `<b>`, `<strong>`: bold font weight.
+    /// - `<i>`, `<em>`: italic font slant.
+    /// - `<u>`: underline. `<s>`, `<del>`: line through.
+    /// - `<span style="...">`: the CSS properties `color`, `background-color`, `font-family`,
+    ///   `font-size`, `font-weight`, `font-style`, `font-feature-settings`, `letter-spacing`,
+    ///   `word-spacing`, `text-decoration` (or `text-decoration-line`), `text-decoration-color`, and
+    ///   `text-decoration-style`. Unknown properties are ignored. Lengths are in pixels.
+    /// - `<a href="...">`: an underlined link. The text ranges of links are returned, so that they can
+    ///   be hit tested with [`super::Paragraph::get_rects_for_range()`].
+    /// - `<br>`: a line break.
+    /// - `<img width="..." height="..." src="...">`: a placeholder that is aligned to the baseline.
+    ///
+    /// The character references `&amp;`, `&lt;`, `&gt;`, `&quot;`, `&apos;`, `&nbsp;`, `&#...;` and
+    /// `&#x...;` are decoded. Whitespace is preserved as is.
+    ///
+    /// The styles of the elements are applied on top of the current style of the builder. If the
+    /// markup is invalid, nothing is added.
+    pub fn add_markup(&mut self, markup: &str) -> Result<MarkupRanges, MarkupError> {
+        let ops = parse(markup)?;
+
+        let mut offset = self.get_text().encode_utf16().count();
+        let mut ranges = MarkupRanges::default();
+        let mut open_links = Vec::new();
+        for op in ops {
+            match op {
+                Op::Text(text) => {
+                    offset += text.encode_utf16().count();
+                    self.add_text(text);
+                }
+                Op::Push(properties) => {
+                    let mut style = self.peek_style();
+                    for property in properties {
+                        property.apply(&mut style);
+                    }
+                    self.push_style(&style);
+                }
+                Op::Pop => {
+                    self.pop();
+                }
+                Op::LinkStart(href) => open_links.push((href, offset)),
+                Op::LinkEnd => {
+                    let (href, start) = open_links.pop().unwrap();
+                    ranges.links.push(MarkupLink {
+                        href,
+                        range: start..offset,
+                    });
+                }
+                Op::Placeholder { width, height, src } => {
+                    self.add_placeholder(&PlaceholderStyle::new(
+                        width,
+                        height,
+                        PlaceholderAlignment::Baseline,
+                        TextBaseline::Alphabetic,
+                        height,
+                    ));
+                    ranges.placeholders.push(MarkupPlaceholder {
+                        src,
+                        range: offset..offset + 1,
+                        width,
+                        height,
+                    });
+                    offset += 1;
+                }
+            }
+        }
+        Ok(ranges)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Op {
+    Text(String),
+    Push(Vec<Property>),
+    Pop,
+    LinkStart(String),
+    LinkEnd,
+    Placeholder {
+        width: scalar,
+        height: scalar,
+        src: Option<String>,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Property {
+    Color(Color),
+    Background(Color),
+    FontFamilies(Vec<String>),
+    FontSize(scalar),
+    Weight(Weight),
+    Slant(Slant),
+    FontFeature(String, i32),
+    LetterSpacing(scalar),
+    WordSpacing(scalar),
+    Decoration(TextDecoration),
+    DecorationColor(Color),
+    DecorationStyle(TextDecorationStyle),
+}
+
+impl Property {
+    fn apply(self, style: &mut TextStyle) {
+        match self {
+            Property::Color(color) => {
+                style.set_color(color);
+            }
+            Property::Background(color) => {
+                let mut paint = Paint::default();
+                paint.set_color(color);
+                style.set_background_paint(&paint);
+            }
+            Property::FontFamilies(families) => {
+                style.set_font_families(&families);
+            }
+            Property::FontSize(size) => {
+                style.set_font_size(size);
+            }
+            Property::Weight(weight) => {
+                let fs = style.font_style();
+                style.set_font_style(FontStyle::new(weight, fs.width(), fs.slant()));
+            }
+            Property::Slant(slant) => {
+                let fs = style.font_style();
+                style.set_font_style(FontStyle::new(fs.weight(), fs.width(), slant));
+            }
+            Property::FontFeature(tag, value) => style.add_font_feature(tag, value),
+            Property::LetterSpacing(spacing) => {
+                style.set_letter_spacing(spacing);
+            }
+            Property::WordSpacing(spacing) => {
+                style.set_word_spacing(spacing);
+            }
+            Property::Decoration(decoration) => {
+                // `text-decoration: none` removes all decorations.
+                let decoration = if decoration.is_empty() {
+                    decoration
+                } else {
+                    style.decoration_type() | decoration
+                };
+                style.set_decoration_type(decoration)
+            }
+            Property::DecorationColor(color) => style.set_decoration_color(color),
+            Property::DecorationStyle(decoration_style) => {
+                style.set_decoration_style(decoration_style)
+            }
+        }
+    }
+}
+
+fn parse(markup: &str) -> Result<Vec<Op>, MarkupError> {
+    Parser {
+        markup,
+        pos: 0,
+        ops: Vec::new(),
+        open: Vec::new(),
+    }
+    .parse()
+}
+
+struct Parser<'a> {
+    markup: &'a str,
+    pos: usize,
+    ops: Vec<Op>,
+    /// The names of the open elements and their start offsets.
+    open: Vec<(String, usize)>,
+}
+
+impl Parser<'_> {
+    fn parse(mut self) -> Result<Vec<Op>, MarkupError> {
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '<' => {
+                    if !text.is_empty() {
+                        self.ops.push(Op::Text(std::mem::take(&mut text)));
+                    }
+                    self.tag()?;
+                }
+                '&' => text.push(self.character_reference()?),
+                c => {
+                    text.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+        if !text.is_empty() {
+            self.ops.push(Op::Text(text));
+        }
+        if let Some((name, offset)) = self.open.pop() {
+            return Err(error(offset, MarkupErrorKind::UnclosedElement(name)));
+        }
+        Ok(self.ops)
+    }
+
+    fn tag(&mut self) -> Result<(), MarkupError> {
+        let start = self.pos;
+        self.pos += 1;
+        let end_tag = self.eat('/');
+        let name = self.name().to_ascii_lowercase();
+        if name.is_empty() {
+            return Err(self.unexpected());
+        }
+
+        if end_tag {
+            self.skip_whitespace();
+            self.expect('>')?;
+            return match self.open.pop() {
+                Some((open, _)) if open == name => {
+                    if name == "a" {
+                        self.ops.push(Op::LinkEnd);
+                    }
+                    self.ops.push(Op::Pop);
+                    Ok(())
+                }
+                _ => Err(error(start, MarkupErrorKind::UnexpectedEndTag(name))),
+            };
+        }
+
+        let attributes = self.attributes()?;
+        let attribute = |name: &str| {
+            attributes
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+
+        let properties = match name.as_str() {
+            "b" | "strong" => vec![Property::Weight(Weight::BOLD)],
+            "i" | "em" => vec![Property::Slant(Slant::Italic)],
+            "u" => vec![Property::Decoration(TextDecoration::UNDERLINE)],
+            "s" | "del" => vec![Property::Decoration(TextDecoration::LINE_THROUGH)],
+            "span" => match attribute("style") {
+                Some(style) => parse_style(style).map_err(|kind| error(start, kind))?,
+                None => Vec::new(),
+            },
+            "a" => {
+                let href = attribute("href")
+                    .ok_or_else(|| error(start, MarkupErrorKind::MissingAttribute("href")))?;
+                self.ops.push(Op::LinkStart(href.to_string()));
+                vec![Property::Decoration(TextDecoration::UNDERLINE)]
+            }
+            "br" => {
+                self.ops.push(Op::Text("\n".into()));
+                return Ok(());
+            }
+            "img" => {
+                let length = |name: &'static str| {
+                    let value = attribute(name)
+                        .ok_or_else(|| error(start, MarkupErrorKind::MissingAttribute(name)))?;
+                    parse_length(value)
+                        .filter(|l| *l >= 0.0)
+                        .ok_or_else(|| error(start, MarkupErrorKind::InvalidAttribute(name.into())))
+                };
+                self.ops.push(Op::Placeholder {
+                    width: length("width")?,
+                    height: length("height")?,
+                    src: attribute("src").map(|s| s.to_string()),
+                });
+                return Ok(());
+            }
+            _ => return Err(error(start, MarkupErrorKind::UnknownElement(name))),
+        };
+
+        self.ops.push(Op::Push(properties));
+        self.open.push((name, start));
+        Ok(())
+    }
+
+    /// Parses the attributes and the end of a start tag.
+    fn attributes(&mut self) -> Result<Vec<(String, String)>, MarkupError> {
+        let mut attributes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('>') => {
+                    self.pos += 1;
+                    return Ok(attributes);
+                }
+                Some('/') => {
+                    self.pos += 1;
+                    self.expect('>')?;
+                    return Ok(attributes);
+                }
+                Some(_) => {}
+                None => return Err(error(self.pos, MarkupErrorKind::UnexpectedEnd)),
+            }
+
+            let name = self.name().to_ascii_lowercase();
+            if name.is_empty() {
+                return Err(self.unexpected());
+            }
+            self.skip_whitespace();
+            let value = if self.eat('=') {
+                self.skip_whitespace();
+                self.attribute_value()?
+            } else {
+                String::new()
+            };
+            attributes.push((name, value));
+        }
+    }
+
+    fn attribute_value(&mut self) -> Result<String, MarkupError> {
+        let quote = match self.peek() {
+            Some(q @ ('"' | '\'')) => {
+                self.pos += 1;
+                Some(q)
+            }
+            _ => None,
+        };
+        let mut value = String::new();
+        loop {
+            match (self.peek(), quote) {
+                (None, _) => return Err(error(self.pos, MarkupErrorKind::UnexpectedEnd)),
+                (Some(c), Some(q)) if c == q => {
+                    self.pos += 1;
+                    return Ok(value);
+                }
+                (Some(c), None) if c.is_whitespace() || c == '>' => return Ok(value),
+                (Some('&'), _) => value.push(self.character_reference()?),
+                (Some(c), _) => {
+                    value.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    fn character_reference(&mut self) -> Result<char, MarkupError> {
+        let start = self.pos;
+        let rest = &self.markup[start + 1..];
+        let Some(end) = rest.find(';') else {
+            return Err(error(start, MarkupErrorKind::UnexpectedEnd));
+        };
+        let reference = &rest[..end];
+        let c = match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => {
+                let code = if let Some(hex) = reference
+                    .strip_prefix("#x")
+                    .or_else(|| reference.strip_prefix("#X"))
+                {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(dec) = reference.strip_prefix('#') {
+                    dec.parse().ok()
+                } else {
+                    None
+                };
+                code.and_then(char::from_u32)
+            }
+        };
+        let c = c.ok_or_else(|| {
+            error(
+                start,
+                MarkupErrorKind::InvalidCharacterReference(reference.into()),
+            )
+        })?;
+        self.pos = start + 1 + end + 1;
+        Ok(c)
+    }
+
+    fn name(&mut self) -> &str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                break;
+            }
+            self.pos += 1;
+        }
+        &self.markup[start..self.pos]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.markup[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let matches = self.peek() == Some(c);
+        if matches {
+            self.pos += c.len_utf8();
+        }
+        matches
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), MarkupError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn unexpected(&self) -> MarkupError {
+        let kind = match self.peek() {
+            Some(c) => MarkupErrorKind::UnexpectedCharacter(c),
+            None => MarkupErrorKind::UnexpectedEnd,
+        };
+        error(self.pos, kind)
+    }
+}
+
+fn error(offset: usize, kind: MarkupErrorKind) -> MarkupError {
+    MarkupError { offset, kind }
+}
+
+/// Parses the declarations of a `style` attribute.
+fn parse_style(style: &str) -> Result<Vec<Property>, MarkupErrorKind> {
+    let mut properties = Vec::new();
+    for declaration in style.split(';') {
+        let declaration = declaration.trim();
+        if declaration.is_empty() {
+            continue;
+        }
+        let invalid = || MarkupErrorKind::InvalidStyle(declaration.into());
+        let (name, value) = declaration.split_once(':').ok_or_else(invalid)?;
+        let (name, value) = (name.trim().to_ascii_lowercase(), value.trim());
+        match name.as_str() {
+            "color" => properties.push(Property::Color(parse_color(value).ok_or_else(invalid)?)),
+            "background-color" => {
+                properties.push(Property::Background(
+                    parse_color(value).ok_or_else(invalid)?,
+                ));
+            }
+            "font-family" => {
+                let families: Vec<String> = value
+                    .split(',')
+                    .map(|f| unquote(f.trim()).to_string())
+                    .filter(|f| !f.is_empty())
+                    .collect();
+                if families.is_empty() {
+                    return Err(invalid());
+                }
+                properties.push(Property::FontFamilies(families));
+            }
+            "font-size" => properties.push(Property::FontSize(
+                parse_length(value)
+                    .filter(|s| *s > 0.0)
+                    .ok_or_else(invalid)?,
+            )),
+            "font-weight" => {
+                let weight = match value {
+                    "normal" => Weight::NORMAL,
+                    "bold" => Weight::BOLD,
+                    _ => Weight::from(
+                        value
+                            .parse::<i32>()
+                            .ok()
+                            .filter(|w| (1..=1000).contains(w))
+                            .ok_or_else(invalid)?,
+                    ),
+                };
+                properties.push(Property::Weight(weight));
+            }
+            "font-style" => properties.push(Property::Slant(match value {
+                "normal" => Slant::Upright,
+                "italic" => Slant::Italic,
+                "oblique" => Slant::Oblique,
+                _ => return Err(invalid()),
+            })),
+            "font-feature-settings" => {
+                for feature in value.split(',') {
+                    let mut parts = feature.split_whitespace();
+                    let tag = parts.next().map(unquote).ok_or_else(invalid)?;
+                    if tag.len() != 4 || !tag.is_ascii() {
+                        return Err(invalid());
+                    }
+                    let value = match parts.next() {
+                        None | Some("on") => 1,
+                        Some("off") => 0,
+                        Some(v) => v.parse().map_err(|_| invalid())?,
+                    };
+                    properties.push(Property::FontFeature(tag.into(), value));
+                }
+            }
+            "letter-spacing" => properties.push(Property::LetterSpacing(
+                parse_spacing(value).ok_or_else(invalid)?,
+            )),
+            "word-spacing" => properties.push(Property::WordSpacing(
+                parse_spacing(value).ok_or_else(invalid)?,
+            )),
+            "text-decoration" | "text-decoration-line" => {
+                let mut decoration = TextDecoration::NO_DECORATION;
+                for line in value.split_whitespace() {
+                    decoration |= match line {
+                        "none" => TextDecoration::NO_DECORATION,
+                        "underline" => TextDecoration::UNDERLINE,
+                        "overline" => TextDecoration::OVERLINE,
+                        "line-through" => TextDecoration::LINE_THROUGH,
+                        _ => return Err(invalid()),
+                    };
+                }
+                properties.push(Property::Decoration(decoration));
+            }
+            "text-decoration-color" => properties.push(Property::DecorationColor(
+                parse_color(value).ok_or_else(invalid)?,
+            )),
+            "text-decoration-style" => properties.push(Property::DecorationStyle(match value {
+                "solid" => TextDecorationStyle::Solid,
+                "double" => TextDecorationStyle::Double,
+                "dotted" => TextDecorationStyle::Dotted,
+                "dashed" => TextDecorationStyle::Dashed,
+                "wavy" => TextDecorationStyle::Wavy,
+                _ => return Err(invalid()),
+            })),
+            _ => {}
+        }
+    }
+    Ok(properties)
+}
+
+fn unquote(s: &str) -> &str {
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .or_else(|| s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')))
+        .unwrap_or(s)
+}
+
+/// Parses a length in pixels, with or without the `px` unit.
+fn parse_length(value: &str) -> Option<scalar> {
+    let value = value.trim();
+    let number = value.strip_suffix("px").unwrap_or(value);
+    number
+        .trim()
+        .parse()
+        .ok()
+        .filter(|n: &scalar| n.is_finite())
+}
+
+fn parse_spacing(value: &str) -> Option<scalar> {
+    match value {
+        "normal" => Some(0.0),
+        _ => parse_length(value),
+    }
+}
+
+/// Parses a color in the `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`, or `rgba()` notation,
+/// or one of a few named colors.
+fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim().to_ascii_lowercase();
+    if let Some(hex) = value.strip_prefix('#') {
+        let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+        let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return match hex.len() {
+            3 | 4 => {
+                let a = if hex.len() == 4 { digit(3)? * 17 } else { 255 };
+                Some(Color::from_argb(
+                    a,
+                    digit(0)? * 17,
+                    digit(1)? * 17,
+                    digit(2)? * 17,
+                ))
+            }
+            6 | 8 => {
+                let a = if hex.len() == 8 { byte(6)? } else { 255 };
+                Some(Color::from_argb(a, byte(0)?, byte(2)?, byte(4)?))
+            }
+            _ => None,
+        };
+    }
+    if let Some(args) = value
+        .strip_prefix("rgba(")
+        .or_else(|| value.strip_prefix("rgb("))
+        .and_then(|args| args.strip_suffix(')'))
+    {
+        let args: Vec<&str> = args.split(',').map(str::trim).collect();
+        let channel = |i: usize| args[i].parse::<u8>().ok();
+        return match args.len() {
+            3 => Some(Color::from_rgb(channel(0)?, channel(1)?, channel(2)?)),
+            4 => {
+                let alpha: scalar = args[3].parse().ok()?;
+                if !(0.0..=1.0).contains(&alpha) {
+                    return None;
+                }
+                let a = (alpha * 255.0).round() as u8;
+                Some(Color::from_argb(a, channel(0)?, channel(1)?, channel(2)?))
+            }
+            _ => None,
+        };
+    }
+    match value.as_str() {
+        "transparent" => Some(Color::TRANSPARENT),
+        "black" => Some(Color::BLACK),
+        "white" => Some(Color::WHITE),
+        "gray" | "grey" => Some(Color::GRAY),
+        "red" => Some(Color::RED),
+        "green" => Some(Color::from_rgb(0, 0x80, 0)),
+        "blue" => Some(Color::BLUE),
+        "yellow" => Some(Color::YELLOW),
+        "cyan" => Some(Color::CYAN),
+        "magenta" => Some(Color::MAGENTA),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_color, MarkupErrorKind, Op, Property};
+    use crate::{
+        font_style::{Slant, Weight},
+        icu,
+        textlayout::{FontCollection, ParagraphBuilder, ParagraphStyle, TextDecoration},
+        Color, FontMgr,
+    };
+
+    #[test]
+    fn parse_elements() {
+        let ops = parse("a <b>b</b>&amp;<br/><span style='color: #f00; font-size: 20px'>c</span>")
+            .unwrap();
+        assert_eq!(
+            ops,
+            [
+                Op::Text("a ".into()),
+                Op::Push(vec![Property::Weight(Weight::BOLD)]),
+                Op::Text("b".into()),
+                Op::Pop,
+                Op::Text("&".into()),
+                Op::Text("\n".into()),
+                Op::Push(vec![Property::Color(Color::RED), Property::FontSize(20.0)]),
+                Op::Text("c".into()),
+                Op::Pop,
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let kind = |markup| parse(markup).unwrap_err().kind;
+        assert_eq!(
+            kind("<b><i>x</b></i>"),
+            MarkupErrorKind::UnexpectedEndTag("b".into())
+        );
+        assert_eq!(kind("<i>x"), MarkupErrorKind::UnclosedElement("i".into()));
+        assert_eq!(
+            kind("<p>x</p>"),
+            MarkupErrorKind::UnknownElement("p".into())
+        );
+        assert_eq!(
+            kind("&bogus;"),
+            MarkupErrorKind::InvalidCharacterReference("bogus".into())
+        );
+        assert_eq!(kind("<a>x</a>"), MarkupErrorKind::MissingAttribute("href"));
+        assert!(matches!(
+            kind("<span style='color: nope'>x</span>"),
+            MarkupErrorKind::InvalidStyle(_)
+        ));
+    }
+
+    #[test]
+    fn parse_colors() {
+        assert_eq!(parse_color("#0f0"), Some(Color::GREEN));
+        assert_eq!(parse_color("#0000FF"), Some(Color::BLUE));
+        assert_eq!(
+            parse_color("rgba(255, 0, 0, 0.5)"),
+            Some(Color::from_argb(128, 255, 0, 0))
+        );
+        assert_eq!(parse_color("#12345"), None);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn add_markup_to_builder() {
+        icu::init();
+
+        let mut font_collection = FontCollection::new();
+        font_collection.set_default_font_manager(FontMgr::new(), None);
+        let mut builder = ParagraphBuilder::new(&ParagraphStyle::new(), font_collection);
+        builder.add_text("\u{1F600} ");
+        let ranges = builder
+            .add_markup(r#"<i>See</i> <a href="https://skia.org">Skia</a><img width=10 height=12>"#)
+            .unwrap();
+
+        assert_eq!(ranges.links.len(), 1);
+        assert_eq!(ranges.links[0].href, "https://skia.org");
+        // The emoji is encoded as two UTF-16 code units.
+        assert_eq!(ranges.links[0].range, 7..11);
+        assert_eq!(ranges.placeholders.len(), 1);
+        assert_eq!(ranges.placeholders[0].range, 11..12);
+        assert_eq!(builder.peek_style().font_style().slant(), Slant::Upright);
+        assert_eq!(
+            builder.peek_style().decoration_type(),
+            TextDecoration::NO_DECORATION
+        );
+
+        assert!(builder.add_markup("<b>unclosed").is_err());
+        let mut paragraph = builder.build();
+        paragraph.layout(300.0);
+        assert_eq!(paragraph.get_rects_for_placeholders().len(), 1);
+    }
+}
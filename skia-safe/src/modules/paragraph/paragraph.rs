@@ -685,7 +685,10 @@ mod tests {
     use super::Paragraph;
     use crate::{
         icu,
-        textlayout::{FontCollection, ParagraphBuilder, ParagraphStyle, TextStyle},
+        textlayout::{
+            FontCollection, LineBreakBefore, LineBreakType, ParagraphBuilder, ParagraphStyle,
+            TextStyle,
+        },
         FontMgr,
     };
 
@@ -765,6 +768,53 @@ mod tests {
         static LOREM_IPSUM: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Curabitur at leo at nulla tincidunt placerat. Proin eget purus augue. Quisque et est ullamcorper, pellentesque felis nec, pulvinar massa. Aliquam imperdiet, nulla ut dictum euismod, purus dui pulvinar risus, eu suscipit elit neque ac est. Nullam eleifend justo quis placerat ultricies. Vestibulum ut elementum velit. Praesent et dolor sit amet purus bibendum mattis. Aliquam erat volutpat.";
     }
 
+    #[test]
+    #[serial_test::serial]
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    fn client_unicode_data() {
+        let text = "Hello World";
+        let line_count = |break_at_space| {
+            let mut font_collection = FontCollection::new();
+            font_collection.set_default_font_manager(FontMgr::new(), None);
+            let mut builder =
+                ParagraphBuilder::new_with_client_unicode(&ParagraphStyle::new(), font_collection);
+            builder.push_style(&TextStyle::new());
+            builder.add_text(text);
+            let graphemes: Vec<usize> = (0..=text.len()).collect();
+            let line_breaks = [
+                LineBreakBefore::new(6, break_at_space),
+                LineBreakBefore::new(text.len(), LineBreakType::Hard),
+            ];
+            builder
+                .set_words_utf8(&[0, 5, 6, text.len()])
+                .set_grapheme_breaks_utf8(&graphemes)
+                .set_line_breaks_utf8(&line_breaks);
+
+            let data = builder.client_icu_data();
+            assert_eq!(data.grapheme_breaks, graphemes);
+            assert_eq!(data.line_breaks, line_breaks);
+
+            let mut paragraph = builder.build();
+            paragraph.layout(1000.0);
+            paragraph.line_number()
+        };
+        assert_eq!(line_count(LineBreakType::Soft), 1);
+        assert_eq!(line_count(LineBreakType::Hard), 2);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    #[should_panic(expected = "grapheme and line breaks")]
+    fn client_unicode_without_data_panics() {
+        let mut font_collection = FontCollection::new();
+        font_collection.set_default_font_manager(FontMgr::new(), None);
+        let mut builder =
+            ParagraphBuilder::new_with_client_unicode(&ParagraphStyle::new(), font_collection);
+        builder.add_text("Hello World");
+        builder.build();
+    }
+
     /// <https://github.com/rust-skia/rust-skia/issues/984>
     #[test]
     #[serial_test::serial]
@@ -3,7 +3,7 @@ use std::{fmt, os::raw, ptr, str};
 use skia_bindings as sb;
 
 use super::{FontCollection, Paragraph, ParagraphStyle, PlaceholderStyle, TextStyle};
-use crate::prelude::*;
+use crate::{prelude::*, shapers::unicode::Unicode};
 
 pub type ParagraphBuilder = RefHandle<sb::skia_textlayout_ParagraphBuilder>;
 unsafe_send_sync!(ParagraphBuilder);
@@ -56,7 +56,19 @@ impl ParagraphBuilder {
         self
     }
 
+    /// Builds the paragraph.
+    ///
+    /// # Panics
+    ///
+    /// If the builder was created with [`Self::new_with_client_unicode()`] and neither the
+    /// grapheme and line breaks of its text nor a Unicode implementation were set.
     pub fn build(&mut self) -> Paragraph {
+        #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+        assert!(
+            unsafe { sb::C_ParagraphBuilder_canBuild(self.native()) },
+            "ParagraphBuilder::build(): the grapheme and line breaks of the text or a Unicode \
+             implementation must be set before a paragraph is built"
+        );
         Paragraph::from_ptr(unsafe { sb::C_ParagraphBuilder_Build(self.native_mut()) }).unwrap()
     }
 
@@ -75,19 +87,229 @@ impl ParagraphBuilder {
             .unwrap()
     }
 
-    // TODO: Wrap SetWords*, SetGraphemeBreaks*, setLineBreaks*, getClientICUData, setUnicode.
+    /// Sets the UTF-8 offsets of the word boundaries of the text.
+    ///
+    /// Together with [`Self::set_grapheme_breaks_utf8()`] and [`Self::set_line_breaks_utf8()`]
+    /// this allows the text to be segmented by the client instead of by ICU. The data is only
+    /// used by builders created with [`Self::new_with_client_unicode()`].
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    pub fn set_words_utf8(&mut self, words: &[usize]) -> &mut Self {
+        unsafe {
+            sb::C_ParagraphBuilder_SetWordsUtf8(self.native_mut(), words.as_ptr(), words.len())
+        }
+        self
+    }
+
+    /// Sets the UTF-16 offsets of the word boundaries of the text.
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    pub fn set_words_utf16(&mut self, words: &[usize]) -> &mut Self {
+        unsafe {
+            sb::C_ParagraphBuilder_SetWordsUtf16(self.native_mut(), words.as_ptr(), words.len())
+        }
+        self
+    }
+
+    /// Sets the UTF-8 offsets of the grapheme cluster boundaries of the text.
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    pub fn set_grapheme_breaks_utf8(&mut self, graphemes: &[usize]) -> &mut Self {
+        unsafe {
+            sb::C_ParagraphBuilder_SetGraphemeBreaksUtf8(
+                self.native_mut(),
+                graphemes.as_ptr(),
+                graphemes.len(),
+            )
+        }
+        self
+    }
+
+    /// Sets the UTF-16 offsets of the grapheme cluster boundaries of the text.
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    pub fn set_grapheme_breaks_utf16(&mut self, graphemes: &[usize]) -> &mut Self {
+        unsafe {
+            sb::C_ParagraphBuilder_SetGraphemeBreaksUtf16(
+                self.native_mut(),
+                graphemes.as_ptr(),
+                graphemes.len(),
+            )
+        }
+        self
+    }
+
+    /// Sets the line break opportunities of the text, with positions as UTF-8 offsets.
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    pub fn set_line_breaks_utf8(&mut self, line_breaks: &[LineBreakBefore]) -> &mut Self {
+        let (positions, hard) = LineBreakBefore::split(line_breaks);
+        unsafe {
+            sb::C_ParagraphBuilder_SetLineBreaksUtf8(
+                self.native_mut(),
+                positions.as_ptr(),
+                hard.as_ptr(),
+                line_breaks.len(),
+            )
+        }
+        self
+    }
+
+    /// Sets the line break opportunities of the text, with positions as UTF-16 offsets.
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    pub fn set_line_breaks_utf16(&mut self, line_breaks: &[LineBreakBefore]) -> &mut Self {
+        let (positions, hard) = LineBreakBefore::split(line_breaks);
+        unsafe {
+            sb::C_ParagraphBuilder_SetLineBreaksUtf16(
+                self.native_mut(),
+                positions.as_ptr(),
+                hard.as_ptr(),
+                line_breaks.len(),
+            )
+        }
+        self
+    }
+
+    /// Returns the Unicode data set by the client.
+    ///
+    /// Word boundaries are returned as UTF-16 offsets, grapheme and line breaks as UTF-8 offsets.
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    pub fn client_icu_data(&self) -> ClientICUData {
+        use crate::interop::VecSink;
+
+        let mut data = ClientICUData::default();
+        let mut hard = Vec::new();
+
+        let mut set_words = |words: &[usize]| data.words = words.to_vec();
+        let mut set_graphemes = |graphemes: &[usize]| data.grapheme_breaks = graphemes.to_vec();
+        let mut positions = Vec::new();
+        let mut set_positions = |p: &[usize]| positions = p.to_vec();
+        let mut set_hard = |h: &[bool]| hard = h.to_vec();
+
+        unsafe {
+            sb::C_ParagraphBuilder_getClientICUData(
+                self.native(),
+                VecSink::new(&mut set_words).native_mut(),
+                VecSink::new(&mut set_graphemes).native_mut(),
+                VecSink::new(&mut set_positions).native_mut(),
+                VecSink::new(&mut set_hard).native_mut(),
+            )
+        }
+
+        data.line_breaks = positions
+            .into_iter()
+            .zip(hard)
+            .map(|(pos, hard)| LineBreakBefore::new(pos, LineBreakType::from_hard(hard)))
+            .collect();
+        data
+    }
+
+    /// Replaces the Unicode implementation that is used to segment the text when the paragraph
+    /// is built.
+    pub fn set_unicode(&mut self, unicode: Unicode) -> &mut Self {
+        unsafe { sb::C_ParagraphBuilder_setUnicode(self.native_mut(), unicode.into_ptr()) }
+        self
+    }
 
     pub fn reset(&mut self) {
         unsafe { sb::C_ParagraphBuilder_Reset(self.native_mut()) }
     }
 
     pub fn new(style: &ParagraphStyle, font_collection: impl Into<FontCollection>) -> Self {
-        #[cfg(feature = "embed-icudtl")]
+        #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
         crate::icu::init();
 
         Self::from_ptr(unsafe {
-            sb::C_ParagraphBuilder_make(style.native(), font_collection.into().into_ptr())
+            sb::C_ParagraphBuilder_make(
+                style.native(),
+                font_collection.into().into_ptr(),
+                ptr::null_mut(),
+            )
         })
         .expect("Unicode initialization error")
     }
+
+    /// Creates a [`ParagraphBuilder`] without a Unicode implementation. When the paragraph is
+    /// built, the text is segmented with the data set by [`Self::set_words_utf8()`],
+    /// [`Self::set_grapheme_breaks_utf8()`], [`Self::set_line_breaks_utf8()`] and their UTF-16
+    /// variants, so no ICU data is needed.
+    ///
+    /// At least the grapheme and line breaks must be set before [`Self::build()`] is called,
+    /// unless a Unicode implementation is set with [`Self::set_unicode()`]. `build()` panics
+    /// otherwise.
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    pub fn new_with_client_unicode(
+        style: &ParagraphStyle,
+        font_collection: impl Into<FontCollection>,
+    ) -> Self {
+        Self::from_ptr(unsafe {
+            sb::C_ParagraphBuilder_makeWithoutUnicode(
+                style.native(),
+                font_collection.into().into_ptr(),
+            )
+        })
+        .unwrap()
+    }
+
+    /// Creates a [`ParagraphBuilder`] that uses the given Unicode implementation instead of the
+    /// default one.
+    pub fn new_with_unicode(
+        style: &ParagraphStyle,
+        font_collection: impl Into<FontCollection>,
+        unicode: Unicode,
+    ) -> Self {
+        Self::from_ptr(unsafe {
+            sb::C_ParagraphBuilder_make(
+                style.native(),
+                font_collection.into().into_ptr(),
+                unicode.into_ptr(),
+            )
+        })
+        .unwrap()
+    }
+}
+
+/// The kind of a line break opportunity.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum LineBreakType {
+    /// A position at which the line may be broken.
+    #[default]
+    Soft,
+    /// A position at which the line must be broken, for example after a newline.
+    Hard,
+}
+
+impl LineBreakType {
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    fn from_hard(hard: bool) -> Self {
+        if hard {
+            Self::Hard
+        } else {
+            Self::Soft
+        }
+    }
+}
+
+/// A line break opportunity before the character at `pos`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct LineBreakBefore {
+    pub pos: usize,
+    pub break_type: LineBreakType,
+}
+
+impl LineBreakBefore {
+    pub fn new(pos: usize, break_type: LineBreakType) -> Self {
+        Self { pos, break_type }
+    }
+
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    fn split(line_breaks: &[LineBreakBefore]) -> (Vec<usize>, Vec<bool>) {
+        line_breaks
+            .iter()
+            .map(|lb| (lb.pos, lb.break_type == LineBreakType::Hard))
+            .unzip()
+    }
+}
+
+/// The Unicode data that was set by the client, see [`ParagraphBuilder::client_icu_data()`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ClientICUData {
+    pub words: Vec<usize>,
+    pub grapheme_breaks: Vec<usize>,
+    pub line_breaks: Vec<LineBreakBefore>,
 }
@@ -0,0 +1,294 @@
+use std::ops::Range;
+
+use super::{FontCollection, Paragraph, ParagraphBuilder, ParagraphStyle, TextStyle};
+use crate::{scalar, Canvas, Rect};
+
+/// Flows one styled text through a sequence of frames, for example the columns or pages of a
+/// document.
+///
+/// The text is split at line boundaries. Consecutive frames of the same width share one laid out
+/// [`Paragraph`], so the text is shaped and broken into lines again only when the width changes.
+#[derive(Debug)]
+pub struct TextFlow {
+    paragraph_style: ParagraphStyle,
+    font_collection: FontCollection,
+    text: String,
+    runs: Vec<(Range<usize>, TextStyle)>,
+}
+
+impl TextFlow {
+    pub fn new(
+        paragraph_style: &ParagraphStyle,
+        font_collection: impl Into<FontCollection>,
+    ) -> Self {
+        Self {
+            paragraph_style: paragraph_style.clone(),
+            font_collection: font_collection.into(),
+            text: String::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    /// Appends `text` in `style`.
+    pub fn add_text(&mut self, text: impl AsRef<str>, style: &TextStyle) -> &mut Self {
+        let text = text.as_ref();
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.runs.push((start..self.text.len(), style.clone()));
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Lays out the text in `frames`, in order.
+    ///
+    /// A frame that is not high enough for the next line stays empty and the text continues in the
+    /// following frame.
+    pub fn layout(&self, frames: &[Rect]) -> TextFlowLayout {
+        let mut paragraphs: Vec<Paragraph> = Vec::new();
+        let mut placed = Vec::with_capacity(frames.len());
+
+        // The UTF-8 offset of the first line of the current paragraph in the text, the width the
+        // current paragraph is laid out with, and the next line in it that is not placed yet.
+        let mut paragraph_start = 0;
+        let mut width = None;
+        let mut line = 0;
+        let mut start = 0;
+
+        for frame in frames {
+            if start >= self.text.len() {
+                placed.push(TextFlowFrame::empty(*frame, start));
+                continue;
+            }
+
+            if width != Some(frame.width()) {
+                let mut paragraph = self.build(start);
+                paragraph.layout(frame.width());
+                paragraphs.push(paragraph);
+                paragraph_start = start;
+                width = Some(frame.width());
+                line = 0;
+            }
+            let paragraph = paragraphs.last().unwrap();
+            let lines = paragraph.get_line_metrics();
+
+            let top = lines
+                .get(line)
+                .map_or(0.0, |l| (l.baseline - l.ascent) as scalar);
+            let mut end_line = line;
+            while let Some(l) = lines.get(end_line) {
+                let bottom = (l.baseline + l.descent) as scalar - top;
+                if bottom > frame.height() {
+                    break;
+                }
+                end_line += 1;
+            }
+
+            if end_line == line {
+                placed.push(TextFlowFrame::empty(*frame, start));
+                continue;
+            }
+
+            let end = match lines.get(end_line) {
+                Some(_) => paragraph_start + paragraph.get_actual_text_range(end_line, true).start,
+                None => self.text.len(),
+            };
+            let last = &lines[end_line - 1];
+            let bottom = (last.baseline + last.descent) as scalar;
+
+            placed.push(TextFlowFrame {
+                rect: *frame,
+                text_range: start..end,
+                lines: line..end_line,
+                paragraph: Some(paragraphs.len() - 1),
+                band: top..bottom,
+            });
+            line = end_line;
+            start = end;
+        }
+
+        TextFlowLayout {
+            paragraphs,
+            frames: placed,
+            overflow: (start < self.text.len()).then(|| start..self.text.len()),
+        }
+    }
+
+    /// Builds a paragraph from the text that starts at the UTF-8 offset `start`.
+    fn build(&self, start: usize) -> Paragraph {
+        let mut builder =
+            ParagraphBuilder::new(&self.paragraph_style, self.font_collection.clone());
+        for (range, style) in &self.runs {
+            if range.end <= start {
+                continue;
+            }
+            builder.push_style(style);
+            builder.add_text(&self.text[range.start.max(start)..range.end]);
+            builder.pop();
+        }
+        builder.build()
+    }
+}
+
+/// The text that was placed in one frame of a [`TextFlowLayout`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct TextFlowFrame {
+    rect: Rect,
+    text_range: Range<usize>,
+    lines: Range<usize>,
+    paragraph: Option<usize>,
+    /// The vertical range of the lines in the paragraph.
+    band: Range<scalar>,
+}
+
+impl TextFlowFrame {
+    fn empty(rect: Rect, offset: usize) -> Self {
+        Self {
+            rect,
+            text_range: offset..offset,
+            lines: 0..0,
+            paragraph: None,
+            band: 0.0..0.0,
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// The UTF-8 range of the text in the frame.
+    pub fn text_range(&self) -> Range<usize> {
+        self.text_range.clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text_range.is_empty()
+    }
+
+    /// The number of lines in the frame.
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// The height of the lines in the frame.
+    pub fn content_height(&self) -> scalar {
+        self.band.end - self.band.start
+    }
+}
+
+/// The result of [`TextFlow::layout()`].
+#[derive(Debug)]
+pub struct TextFlowLayout {
+    paragraphs: Vec<Paragraph>,
+    frames: Vec<TextFlowFrame>,
+    overflow: Option<Range<usize>>,
+}
+
+impl TextFlowLayout {
+    /// The frames in the order they were passed to [`TextFlow::layout()`].
+    pub fn frames(&self) -> &[TextFlowFrame] {
+        &self.frames
+    }
+
+    /// The UTF-8 range of the text that did not fit into the frames.
+    pub fn overflow(&self) -> Option<Range<usize>> {
+        self.overflow.clone()
+    }
+
+    pub fn is_overflowing(&self) -> bool {
+        self.overflow.is_some()
+    }
+
+    /// Returns the laid out paragraph that contains the lines of `frame` and the vertical offset of
+    /// its first line in it.
+    ///
+    /// The paragraph may contain the lines of other frames, too.
+    pub fn paragraph(&self, frame: usize) -> Option<(&Paragraph, scalar)> {
+        let frame = self.frames.get(frame)?;
+        Some((&self.paragraphs[frame.paragraph?], frame.band.start))
+    }
+
+    /// Paints the text of all frames.
+    pub fn paint(&self, canvas: &Canvas) {
+        for i in 0..self.frames.len() {
+            self.paint_frame(canvas, i);
+        }
+    }
+
+    /// Paints the text of the frame at `index`.
+    pub fn paint_frame(&self, canvas: &Canvas, index: usize) {
+        let Some(frame) = self.frames.get(index) else {
+            return;
+        };
+        let Some(paragraph) = frame.paragraph.map(|i| &self.paragraphs[i]) else {
+            return;
+        };
+        let rect = frame.rect;
+        // The clip separates the lines of this frame from the lines of the other frames that share
+        // the paragraph. Horizontally, glyphs may extend beyond the frame.
+        let clip = Rect::new(
+            rect.left - rect.width(),
+            rect.top,
+            rect.right + rect.width(),
+            rect.top + frame.content_height(),
+        );
+        canvas.save();
+        canvas.clip_rect(clip, None, None);
+        paragraph.paint(canvas, (rect.left, rect.top - frame.band.start));
+        canvas.restore();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextFlow;
+    use crate::{
+        icu,
+        textlayout::{FontCollection, ParagraphStyle, TextStyle},
+        FontMgr, Rect,
+    };
+
+    #[test]
+    #[serial_test::serial]
+    fn flow_through_columns() {
+        icu::init();
+
+        let mut font_collection = FontCollection::new();
+        font_collection.set_default_font_manager(FontMgr::new(), None);
+        let mut style = TextStyle::new();
+        style.set_font_size(20.0);
+        let mut flow = TextFlow::new(&ParagraphStyle::new(), font_collection);
+        for _ in 0..20 {
+            flow.add_text("Lorem ipsum dolor sit amet. ", &style);
+        }
+
+        let columns = [
+            Rect::from_xywh(0.0, 0.0, 100.0, 100.0),
+            Rect::from_xywh(120.0, 0.0, 100.0, 100.0),
+            Rect::from_xywh(0.0, 120.0, 200.0, 1.0),
+            Rect::from_xywh(0.0, 140.0, 200.0, 200.0),
+        ];
+        let layout = flow.layout(&columns);
+        let frames = layout.frames();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0].text_range().start, 0);
+        assert!(!frames[0].is_empty());
+        assert_eq!(frames[0].text_range().end, frames[1].text_range().start);
+        assert!(frames[2].is_empty());
+        assert_eq!(frames[1].text_range().end, frames[3].text_range().start);
+        for frame in frames {
+            assert!(frame.content_height() <= frame.rect().height());
+        }
+        assert!(layout.is_overflowing());
+        assert_eq!(
+            layout.overflow().unwrap(),
+            frames[3].text_range().end..flow.text().len()
+        );
+
+        let layout = flow.layout(&[Rect::from_xywh(0.0, 0.0, 10000.0, 10000.0)]);
+        assert!(!layout.is_overflowing());
+        assert_eq!(layout.frames()[0].text_range(), 0..flow.text().len());
+    }
+}
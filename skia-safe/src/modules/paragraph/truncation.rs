@@ -0,0 +1,418 @@
+use std::ops::Range;
+
+use super::{FontCollection, Paragraph, ParagraphBuilder, ParagraphStyle, TextStyle};
+use crate::scalar;
+
+const DEFAULT_ELLIPSIS: &str = "\u{2026}";
+
+/// Where [`TruncatingParagraphBuilder`] elides text that does not fit.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum TextTruncation {
+    /// Elides the start of the text: "…name.pdf".
+    Start,
+    /// Elides the middle of the text: "very…name.pdf".
+    Middle,
+    /// Elides the end of the text: "verylong…".
+    #[default]
+    End,
+}
+
+/// Builds a single line paragraph that is truncated with an ellipsis at the start, in the middle,
+/// or at the end if the text is wider than the layout width.
+///
+/// The text is measured with the widths of its shaped grapheme clusters, including the ones set in
+/// fallback fonts. Start, middle and end refer to the logical order of the text, so in right to
+/// left text, the start is on the right. Hard line breaks in the text are laid out as spaces.
+#[derive(Debug)]
+pub struct TruncatingParagraphBuilder {
+    paragraph_style: ParagraphStyle,
+    font_collection: FontCollection,
+    truncation: TextTruncation,
+    ellipsis: String,
+    runs: Vec<(Range<usize>, TextStyle)>,
+    text: String,
+}
+
+/// A paragraph built by a [`TruncatingParagraphBuilder`].
+#[derive(Debug)]
+pub struct TruncatedParagraph {
+    paragraph: Paragraph,
+    visible: Vec<Range<usize>>,
+    elided: Option<Range<usize>>,
+}
+
+impl TruncatedParagraph {
+    /// The laid out paragraph, which contains the visible text and the ellipsis.
+    pub fn paragraph(&self) -> &Paragraph {
+        &self.paragraph
+    }
+
+    pub fn into_paragraph(self) -> Paragraph {
+        self.paragraph
+    }
+
+    /// The UTF-8 ranges of the text that is visible, in logical order.
+    pub fn visible_ranges(&self) -> &[Range<usize>] {
+        &self.visible
+    }
+
+    /// The UTF-8 range of the text that was replaced by the ellipsis, `None` if the text fits.
+    pub fn elided_range(&self) -> Option<Range<usize>> {
+        self.elided.clone()
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.elided.is_some()
+    }
+}
+
+impl TruncatingParagraphBuilder {
+    /// Creates a builder that truncates at the end. The ellipsis is the one of `paragraph_style`,
+    /// or "…" if it has none.
+    pub fn new(
+        paragraph_style: &ParagraphStyle,
+        font_collection: impl Into<FontCollection>,
+    ) -> Self {
+        let ellipsis = match paragraph_style.ellipsis() {
+            "" => DEFAULT_ELLIPSIS,
+            ellipsis => ellipsis,
+        }
+        .to_string();
+        let mut paragraph_style = paragraph_style.clone();
+        paragraph_style.set_ellipsis("").set_max_lines(1);
+        Self {
+            paragraph_style,
+            font_collection: font_collection.into(),
+            truncation: TextTruncation::default(),
+            ellipsis,
+            runs: Vec::new(),
+            text: String::new(),
+        }
+    }
+
+    pub fn set_truncation(&mut self, truncation: TextTruncation) -> &mut Self {
+        self.truncation = truncation;
+        self
+    }
+
+    pub fn set_ellipsis(&mut self, ellipsis: impl Into<String>) -> &mut Self {
+        self.ellipsis = ellipsis.into();
+        self
+    }
+
+    pub fn add_text(&mut self, text: impl AsRef<str>, style: &TextStyle) -> &mut Self {
+        let start = self.text.len();
+        self.text.push_str(text.as_ref());
+        self.runs.push((start..self.text.len(), style.clone()));
+        self
+    }
+
+    /// Builds the paragraph and lays it out with `width`, truncating the text if it is wider.
+    pub fn build(&self, width: scalar) -> TruncatedParagraph {
+        let len = self.text.len();
+        let mut paragraph = self.build_text(&[0..len], None);
+        paragraph.layout(scalar::INFINITY);
+        if paragraph.max_intrinsic_width() <= width {
+            paragraph.layout(width);
+            return TruncatedParagraph {
+                paragraph,
+                visible: vec![0..len],
+                elided: None,
+            };
+        }
+
+        let graphemes = self.graphemes(&mut paragraph);
+        let ellipsis_width = self.ellipsis_width();
+
+        // The number of graphemes kept at the start and at the end.
+        let (mut head, mut tail) = self.fit(&graphemes, width - ellipsis_width);
+        loop {
+            let head_end = graphemes.get(head).map_or(len, |(r, _)| r.start);
+            let tail_start = graphemes
+                .len()
+                .checked_sub(tail)
+                .and_then(|i| graphemes.get(i))
+                .map_or(len, |(r, _)| r.start);
+            let visible: Vec<Range<usize>> = [0..head_end, tail_start..len]
+                .into_iter()
+                .filter(|r| !r.is_empty())
+                .collect();
+            let elided = head_end..tail_start;
+
+            let mut paragraph = self.build_text(&visible, Some(elided.start));
+            paragraph.layout(width);
+            // Shaping across the cut may make the text wider than measured.
+            let fits = !paragraph.did_exceed_max_lines() && paragraph.longest_line() <= width;
+            if fits || head + tail == 0 {
+                return TruncatedParagraph {
+                    paragraph,
+                    visible,
+                    elided: Some(elided),
+                };
+            }
+            if head > tail || (head == tail && self.truncation != TextTruncation::End) {
+                head -= 1;
+            } else {
+                tail -= 1;
+            }
+        }
+    }
+
+    /// Returns the number of graphemes that are kept at the start and at the end of the text to
+    /// fit into `available`.
+    fn fit(&self, graphemes: &[(Range<usize>, scalar)], available: scalar) -> (usize, usize) {
+        let mut used = 0.0;
+        let mut fits = |width: scalar| {
+            let fits = used + width <= available;
+            if fits {
+                used += width;
+            }
+            fits
+        };
+        let count = graphemes.len();
+        match self.truncation {
+            TextTruncation::End => (graphemes.iter().take_while(|(_, w)| fits(*w)).count(), 0),
+            TextTruncation::Start => (
+                0,
+                graphemes.iter().rev().take_while(|(_, w)| fits(*w)).count(),
+            ),
+            TextTruncation::Middle => {
+                let (mut head, mut tail) = (0, 0);
+                let (mut head_width, mut tail_width) = (0.0, 0.0);
+                while head + tail < count {
+                    // Extends the side that is narrower, preferring the start.
+                    let (index, at_head) = if head_width <= tail_width {
+                        (head, true)
+                    } else {
+                        (count - 1 - tail, false)
+                    };
+                    let width = graphemes[index].1;
+                    if !fits(width) {
+                        break;
+                    }
+                    if at_head {
+                        head += 1;
+                        head_width += width;
+                    } else {
+                        tail += 1;
+                        tail_width += width;
+                    }
+                }
+                (head, tail)
+            }
+        }
+    }
+
+    /// Returns the UTF-8 ranges and widths of the grapheme clusters of the text.
+    fn graphemes(&self, paragraph: &mut Paragraph) -> Vec<(Range<usize>, scalar)> {
+        let mut graphemes = Vec::new();
+        let mut utf16 = 0;
+        let mut utf8 = 0;
+        let mut chars = self.text.chars();
+        while let Some(c) = chars.next() {
+            let start = utf8;
+            let (end16, width) = match paragraph.get_glyph_info_at_utf16_offset(utf16) {
+                Some(info) if info.grapheme_cluster_text_range.end > utf16 => (
+                    info.grapheme_cluster_text_range.end,
+                    info.grapheme_layout_bounds.width(),
+                ),
+                _ => (utf16 + c.len_utf16(), 0.0),
+            };
+            utf16 += c.len_utf16();
+            utf8 += c.len_utf8();
+            while utf16 < end16 {
+                let Some(c) = chars.next() else { break };
+                utf16 += c.len_utf16();
+                utf8 += c.len_utf8();
+            }
+            graphemes.push((start..utf8, width));
+        }
+        graphemes
+    }
+
+    fn ellipsis_width(&self) -> scalar {
+        let mut builder =
+            ParagraphBuilder::new(&self.paragraph_style, self.font_collection.clone());
+        if let Some((_, style)) = self.runs.first() {
+            builder.push_style(style);
+        }
+        builder.add_text(&self.ellipsis);
+        let mut paragraph = builder.build();
+        paragraph.layout(scalar::INFINITY);
+        paragraph.max_intrinsic_width()
+    }
+
+    /// Builds a paragraph from the `visible` ranges of the text and inserts the ellipsis at the
+    /// UTF-8 offset `ellipsis`.
+    fn build_text(&self, visible: &[Range<usize>], ellipsis: Option<usize>) -> Paragraph {
+        let mut builder =
+            ParagraphBuilder::new(&self.paragraph_style, self.font_collection.clone());
+        let mut ellipsis = ellipsis;
+        for visible in visible {
+            if let Some(offset) = ellipsis.filter(|offset| *offset <= visible.start) {
+                self.add_ellipsis(&mut builder, offset);
+                ellipsis = None;
+            }
+            for (range, style) in &self.runs {
+                let start = range.start.max(visible.start);
+                let end = range.end.min(visible.end);
+                if start < end {
+                    builder.push_style(style);
+                    builder.add_text(without_hard_breaks(&self.text[start..end]));
+                    builder.pop();
+                }
+            }
+        }
+        if let Some(offset) = ellipsis {
+            self.add_ellipsis(&mut builder, offset);
+        }
+        builder.build()
+    }
+
+    /// Adds the ellipsis in the style of the text it replaces at `offset`.
+    fn add_ellipsis(&self, builder: &mut ParagraphBuilder, offset: usize) {
+        let style = self
+            .runs
+            .iter()
+            .find(|(range, _)| range.contains(&offset))
+            .or_else(|| self.runs.last())
+            .map(|(_, style)| style);
+        if let Some(style) = style {
+            builder.push_style(style);
+        }
+        builder.add_text(&self.ellipsis);
+        if style.is_some() {
+            builder.pop();
+        }
+    }
+}
+
+/// Replaces the characters that break lines with spaces. All of them are one UTF-16 code unit
+/// long, so the UTF-16 offsets of the text stay the same.
+fn without_hard_breaks(text: &str) -> String {
+    text.replace(
+        [
+            '\n', '\r', '\u{b}', '\u{c}', '\u{85}', '\u{2028}', '\u{2029}',
+        ],
+        " ",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TextTruncation, TruncatingParagraphBuilder};
+    use crate::{
+        icu,
+        textlayout::{
+            FontCollection, ParagraphStyle, RectHeightStyle, RectWidthStyle, TextDirection,
+            TextStyle,
+        },
+        FontMgr,
+    };
+
+    fn builder(paragraph_style: &ParagraphStyle, text: &str) -> TruncatingParagraphBuilder {
+        icu::init();
+
+        let mut font_collection = FontCollection::new();
+        font_collection.set_default_font_manager(FontMgr::new(), None);
+        let mut style = TextStyle::new();
+        style.set_font_size(16.0);
+        let mut builder = TruncatingParagraphBuilder::new(paragraph_style, font_collection);
+        builder.add_text(text, &style);
+        builder
+    }
+
+    fn truncate(truncation: TextTruncation, width: f32) -> (Vec<String>, Option<String>) {
+        let text = "a-very-long-file-name.pdf";
+        let mut builder = builder(&ParagraphStyle::new(), text);
+        builder.set_truncation(truncation);
+        let truncated = builder.build(width);
+        assert!(truncated.paragraph().longest_line() <= width);
+        (
+            truncated
+                .visible_ranges()
+                .iter()
+                .map(|r| text[r.clone()].to_string())
+                .collect(),
+            truncated.elided_range().map(|r| text[r].to_string()),
+        )
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn truncation_modes() {
+        let (visible, elided) = truncate(TextTruncation::End, 1000.0);
+        assert_eq!(visible, ["a-very-long-file-name.pdf"]);
+        assert_eq!(elided, None);
+
+        let (visible, elided) = truncate(TextTruncation::End, 80.0);
+        assert_eq!(visible.len(), 1);
+        assert!("a-very-long-file-name.pdf".starts_with(&visible[0]));
+        assert!(elided.unwrap().ends_with(".pdf"));
+
+        let (visible, _) = truncate(TextTruncation::Start, 80.0);
+        assert_eq!(visible.len(), 1);
+        assert!(visible[0].ends_with(".pdf"));
+
+        let (visible, elided) = truncate(TextTruncation::Middle, 100.0);
+        assert_eq!(visible.len(), 2);
+        assert!(visible[0].starts_with('a'));
+        assert!(visible[1].ends_with('f'));
+        assert!(!elided.unwrap().is_empty());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn truncate_right_to_left_text_in_the_middle() {
+        // "A very long file name" in Hebrew.
+        let text = "\u{5e7}\u{5d5}\u{5d1}\u{5e5} \u{5e2}\u{5dd} \u{5e9}\u{5dd} \u{5d0}\u{5e8}\u{5d5}\u{5da} \u{5de}\u{5d0}\u{5d5}\u{5d3}";
+        let mut paragraph_style = ParagraphStyle::new();
+        paragraph_style.set_text_direction(TextDirection::RTL);
+        let mut builder = builder(&paragraph_style, text);
+        builder.set_truncation(TextTruncation::Middle);
+        let width = 60.0;
+        let truncated = builder.build(width);
+        let paragraph = truncated.paragraph();
+        assert_eq!(paragraph.line_number(), 1);
+        assert!(paragraph.longest_line() <= width);
+
+        let visible = truncated.visible_ranges();
+        assert_eq!(visible.len(), 2);
+        assert_eq!(visible[0].start, 0);
+        assert_eq!(visible[1].end, text.len());
+        let elided = truncated.elided_range().unwrap();
+        assert_eq!(elided, visible[0].end..visible[1].start);
+
+        // The start of the text is on the right of the ellipsis, the end on its left.
+        let utf16_len = |s: &str| s.encode_utf16().count();
+        let head = utf16_len(&text[visible[0].clone()]);
+        let rect = |start: usize| {
+            paragraph.get_rects_for_range(
+                start..start + 1,
+                RectHeightStyle::Tight,
+                RectWidthStyle::Tight,
+            )[0]
+            .rect
+        };
+        let first = rect(0);
+        let ellipsis = rect(head);
+        let last = rect(head + 1 + utf16_len(&text[visible[1].clone()]) - 1);
+        assert!(first.left >= ellipsis.right - 0.5);
+        assert!(last.right <= ellipsis.left + 0.5);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn hard_line_breaks_are_laid_out_as_spaces() {
+        let text = "first\nsecond\u{2029}third";
+        let truncated = builder(&ParagraphStyle::new(), text).build(1000.0);
+        assert!(!truncated.is_truncated());
+        assert_eq!(truncated.paragraph().line_number(), 1);
+
+        let mut builder = builder(&ParagraphStyle::new(), text);
+        builder.set_truncation(TextTruncation::Start);
+        let truncated = builder.build(80.0);
+        assert_eq!(truncated.paragraph().line_number(), 1);
+        assert!(text[truncated.visible_ranges()[0].clone()].ends_with("third"));
+    }
+}
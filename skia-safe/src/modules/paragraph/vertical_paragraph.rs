@@ -0,0 +1,880 @@
+use std::{collections::HashMap, ops::Range, rc::Rc};
+
+use super::{FontCollection, TextStyle};
+use crate::{
+    scalar,
+    shaper::{Feature, ShapedRun},
+    shapers, Canvas, Font, FontMgr, FourByteTag, GlyphId, Paint, Point, RSXform, Rect, Shaper,
+    TextBlob, TextBlobBuilder, Typeface, TypefaceId,
+};
+
+/// Builds a [`VerticalParagraph`], text that is set in columns from top to bottom, with the
+/// columns running from right to left.
+///
+/// CJK characters are set upright with their vertical glyph forms (the OpenType features `vert`
+/// and `vrt2`) and vertical metrics (the `vhea` and `vmtx` tables). Other text, for example Latin
+/// words, is rotated 90° clockwise. Short runs of digits can optionally be set horizontally within
+/// the column (tate-chu-yoko).
+#[derive(Debug)]
+pub struct VerticalParagraphBuilder {
+    font_collection: FontCollection,
+    runs: Vec<(String, TextStyle)>,
+    tate_chu_yoko: usize,
+}
+
+impl VerticalParagraphBuilder {
+    pub fn new(font_collection: impl Into<FontCollection>) -> Self {
+        Self {
+            font_collection: font_collection.into(),
+            runs: Vec::new(),
+            tate_chu_yoko: 0,
+        }
+    }
+
+    /// Sets runs of up to `max_digits` ASCII digits horizontally, `0` (the default) disables
+    /// tate-chu-yoko.
+    pub fn set_tate_chu_yoko(&mut self, max_digits: usize) -> &mut Self {
+        self.tate_chu_yoko = max_digits;
+        self
+    }
+
+    pub fn add_text(&mut self, text: impl Into<String>, style: &TextStyle) -> &mut Self {
+        self.runs.push((text.into(), style.clone()));
+        self
+    }
+
+    /// Shapes the text. The returned paragraph needs to be laid out before it can be painted.
+    pub fn build(&mut self) -> VerticalParagraph {
+        let fallback = self.font_collection.fallback_manager();
+        let shaper = Shaper::new(fallback.clone());
+        let mut vertical_metrics = VerticalMetricsCache::default();
+
+        let mut units = Vec::new();
+        let mut paints = Vec::with_capacity(self.runs.len());
+        let mut text_offset = 0;
+
+        for (text, style) in &self.runs {
+            let font = resolve_font(&mut self.font_collection, style);
+            let paint_index = paints.len();
+            paints.push(if style.has_foreground() {
+                style.foreground()
+            } else {
+                let mut paint = Paint::default();
+                paint.set_color(style.color()).set_anti_alias(true);
+                paint
+            });
+
+            for (range, orientation) in segments(text, self.tate_chu_yoko) {
+                let utf8 = &text[range.clone()];
+                let offset = text_offset + range.start;
+                if orientation == Orientation::HardBreak {
+                    units.push(Unit::hard_break(offset..offset + utf8.len()));
+                    continue;
+                }
+                let runs = shape(
+                    &shaper,
+                    utf8,
+                    &font,
+                    fallback.clone(),
+                    style.locale(),
+                    orientation == Orientation::Upright,
+                );
+                let context = UnitContext {
+                    text: utf8,
+                    offset,
+                    paint: paint_index,
+                };
+                match orientation {
+                    Orientation::Upright => {
+                        upright_units(&context, &runs, &mut vertical_metrics, &mut units)
+                    }
+                    Orientation::Rotated => rotated_units(&context, &runs, &mut units),
+                    Orientation::TateChuYoko => {
+                        units.extend(tate_chu_yoko_unit(&context, &runs, &font))
+                    }
+                    Orientation::HardBreak => unreachable!(),
+                }
+            }
+            text_offset += text.len();
+        }
+
+        mark_breaks(&mut units, &self.text());
+
+        VerticalParagraph {
+            units,
+            paints,
+            max_height: 0.0,
+            lines: Vec::new(),
+            blobs: Vec::new(),
+            width: 0.0,
+            height: 0.0,
+        }
+    }
+
+    fn text(&self) -> String {
+        self.runs.iter().map(|(text, _)| text.as_str()).collect()
+    }
+}
+
+fn resolve_font(font_collection: &mut FontCollection, style: &TextStyle) -> Font {
+    let families = style.font_families();
+    let families: Vec<&str> = families.iter().collect();
+    let typeface = font_collection
+        .find_typefaces(&families, style.font_style())
+        .into_iter()
+        .next()
+        .or_else(|| font_collection.default_fallback());
+    let mut font = match typeface {
+        Some(typeface) => Font::new(typeface, style.font_size()),
+        None => Font::default().with_size(style.font_size()).unwrap(),
+    };
+    font.set_subpixel(true);
+    font
+}
+
+/// The metrics of one column of a [`VerticalParagraph`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct VerticalLineMetrics {
+    pub line_number: usize,
+    /// The UTF-8 range of the text in the column.
+    pub text_range: Range<usize>,
+    /// The left edge of the column.
+    pub left: scalar,
+    /// The width of the column.
+    pub width: scalar,
+    /// The horizontal center of the column, which upright glyphs are centered on.
+    pub center: scalar,
+    /// The vertical advance of the text in the column.
+    pub height: scalar,
+    /// `true` if the column ends with a line break in the text.
+    pub hard_break: bool,
+}
+
+/// Text laid out in vertical columns, created by a [`VerticalParagraphBuilder`].
+#[derive(Debug)]
+pub struct VerticalParagraph {
+    units: Vec<Unit>,
+    paints: Vec<Paint>,
+    max_height: scalar,
+    lines: Vec<VerticalLineMetrics>,
+    blobs: Vec<(TextBlob, usize)>,
+    width: scalar,
+    height: scalar,
+}
+
+impl VerticalParagraph {
+    /// Breaks the text into columns of at most `height`.
+    pub fn layout(&mut self, height: scalar) {
+        self.max_height = height;
+        let columns = break_columns(&self.units, height);
+
+        let thicknesses: Vec<scalar> = columns
+            .iter()
+            .map(|c| {
+                self.units[c.units.clone()]
+                    .iter()
+                    .map(|u| u.thickness)
+                    .fold(0.0, scalar::max)
+            })
+            .collect();
+        self.width = thicknesses.iter().sum();
+
+        let mut builders: HashMap<usize, TextBlobBuilder> = HashMap::new();
+        self.lines.clear();
+        let mut right = self.width;
+        for (line_number, (column, thickness)) in columns.iter().zip(&thicknesses).enumerate() {
+            let left = right - thickness;
+            let center = left + thickness / 2.0;
+            let mut top = 0.0;
+            for unit in &self.units[column.units.clone()] {
+                for run in &unit.runs {
+                    let builder = builders
+                        .entry(unit.paint)
+                        .or_insert_with(TextBlobBuilder::new);
+                    let (glyphs, xforms) = builder.alloc_run_rsxform(&run.font, run.glyphs.len());
+                    glyphs.copy_from_slice(&run.glyphs);
+                    for (xform, local) in xforms.iter_mut().zip(&run.xforms) {
+                        *xform = RSXform {
+                            tx: local.tx + center,
+                            ty: local.ty + top,
+                            ..*local
+                        };
+                    }
+                }
+                top += unit.advance;
+            }
+            let text_range = match (
+                self.units[column.units.clone()].first(),
+                self.units[column.units.clone()].last(),
+            ) {
+                (Some(first), Some(last)) => first.text_range.start..last.text_range.end,
+                _ => 0..0,
+            };
+            self.lines.push(VerticalLineMetrics {
+                line_number,
+                text_range,
+                left,
+                width: *thickness,
+                center,
+                height: column.advance,
+                hard_break: column.hard_break,
+            });
+            right = left;
+        }
+        self.height = columns.iter().map(|c| c.advance).fold(0.0, scalar::max);
+
+        let mut blobs: Vec<(TextBlob, usize)> = builders
+            .into_iter()
+            .filter_map(|(paint, mut builder)| builder.make().map(|blob| (blob, paint)))
+            .collect();
+        blobs.sort_by_key(|(_, paint)| *paint);
+        self.blobs = blobs;
+    }
+
+    /// The height the paragraph was laid out with.
+    pub fn max_height(&self) -> scalar {
+        self.max_height
+    }
+
+    /// The sum of the widths of all columns.
+    pub fn width(&self) -> scalar {
+        self.width
+    }
+
+    /// The height of the highest column.
+    pub fn height(&self) -> scalar {
+        self.height
+    }
+
+    /// The columns from right to left.
+    pub fn line_metrics(&self) -> &[VerticalLineMetrics] {
+        &self.lines
+    }
+
+    pub fn line_number(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Paints the paragraph with its top left corner at `p`.
+    pub fn paint(&self, canvas: &Canvas, p: impl Into<Point>) {
+        let p = p.into();
+        for (blob, paint) in &self.blobs {
+            canvas.draw_text_blob(blob, p, &self.paints[*paint]);
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Orientation {
+    Upright,
+    Rotated,
+    TateChuYoko,
+    HardBreak,
+}
+
+/// Glyphs of one font, positioned relative to the column center and the top of their unit.
+#[derive(Debug)]
+struct GlyphRun {
+    font: Font,
+    glyphs: Vec<GlyphId>,
+    xforms: Vec<RSXform>,
+}
+
+/// The smallest piece of text that is placed in a column: an upright grapheme cluster, a rotated
+/// word, or a tate-chu-yoko group.
+#[derive(Debug)]
+struct Unit {
+    text_range: Range<usize>,
+    advance: scalar,
+    thickness: scalar,
+    runs: Vec<GlyphRun>,
+    paint: usize,
+    break_before: bool,
+    hard_break: bool,
+    whitespace: bool,
+}
+
+impl Unit {
+    fn hard_break(text_range: Range<usize>) -> Self {
+        Self {
+            text_range,
+            advance: 0.0,
+            thickness: 0.0,
+            runs: Vec::new(),
+            paint: 0,
+            break_before: true,
+            hard_break: true,
+            whitespace: true,
+        }
+    }
+}
+
+struct UnitContext<'a> {
+    text: &'a str,
+    offset: usize,
+    paint: usize,
+}
+
+impl UnitContext<'_> {
+    fn unit(&self, range: Range<usize>, advance: scalar, thickness: scalar) -> Unit {
+        Unit {
+            whitespace: self.text[range.clone()].chars().all(char::is_whitespace),
+            text_range: self.offset + range.start..self.offset + range.end,
+            advance,
+            thickness,
+            runs: Vec::new(),
+            paint: self.paint,
+            break_before: true,
+            hard_break: false,
+        }
+    }
+}
+
+/// Splits `text` into runs of the same orientation.
+fn segments(text: &str, tate_chu_yoko: usize) -> Vec<(Range<usize>, Orientation)> {
+    let mut segments: Vec<(Range<usize>, Orientation)> = Vec::new();
+    for (i, c) in text.char_indices() {
+        let end = i + c.len_utf8();
+        let orientation = match c {
+            '\n' | '\u{2028}' | '\u{2029}' => Orientation::HardBreak,
+            c if is_upright(c) => Orientation::Upright,
+            _ => Orientation::Rotated,
+        };
+        match segments.last_mut() {
+            Some((range, o))
+                if *o != Orientation::HardBreak && (*o == orientation || is_extending(c)) =>
+            {
+                range.end = end
+            }
+            _ => segments.push((i..end, orientation)),
+        }
+    }
+
+    if tate_chu_yoko == 0 {
+        return segments;
+    }
+
+    // Splits short runs of digits out of rotated segments.
+    let mut result = Vec::with_capacity(segments.len());
+    for (range, orientation) in segments {
+        if orientation != Orientation::Rotated {
+            result.push((range, orientation));
+            continue;
+        }
+        let segment = &text[range.clone()];
+        let mut last = 0;
+        let mut i = 0;
+        let bytes = segment.as_bytes();
+        while i < bytes.len() {
+            if !bytes[i].is_ascii_digit() {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            let alphanumeric_before = start > 0 && bytes[start - 1].is_ascii_alphanumeric();
+            let alphanumeric_after = i < bytes.len() && bytes[i].is_ascii_alphanumeric();
+            if i - start <= tate_chu_yoko && !alphanumeric_before && !alphanumeric_after {
+                if start > last {
+                    result.push((range.start + last..range.start + start, orientation));
+                }
+                result.push((
+                    range.start + start..range.start + i,
+                    Orientation::TateChuYoko,
+                ));
+                last = i;
+            }
+        }
+        if last < segment.len() {
+            result.push((range.start + last..range.end, orientation));
+        }
+    }
+    result
+}
+
+/// An approximation of the characters with the `Vertical_Orientation` property `U` (upright) or
+/// `Tu` (transformed upright) defined in Unicode Standard Annex #50.
+fn is_upright(c: char) -> bool {
+    matches!(
+        c as u32,
+        0xa7 | 0xa9 | 0xae | 0xb1 | 0xbc..=0xbe | 0xd7 | 0xf7
+            | 0x1100..=0x11ff
+            | 0x2460..=0x24ff
+            | 0x25a0..=0x27bf
+            | 0x2e80..=0x4dbf
+            | 0x4dc0..=0x9fff
+            | 0xa000..=0xa4cf
+            | 0xa960..=0xa97f
+            | 0xac00..=0xd7ff
+            | 0xe000..=0xfaff
+            | 0xfe10..=0xfe1f
+            | 0xfe30..=0xfe4f
+            | 0xff00..=0xff60
+            | 0xffe0..=0xffe7
+            | 0x1f000..=0x1faff
+            | 0x20000..=0x3ffff
+    )
+}
+
+/// Characters that continue the previous segment, like combining marks and variation selectors.
+fn is_extending(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x300..=0x36f
+            | 0x200c..=0x200d
+            | 0x3099..=0x309a
+            | 0xfe00..=0xfe0f
+            | 0x1f3fb..=0x1f3ff
+            | 0xe0100..=0xe01ef
+    )
+}
+
+/// Characters that must not start a column.
+fn is_no_start(c: char) -> bool {
+    "、。，．・：；？！ー）］｝」』】〕〉》〙〗｠’”ゝゞぁぃぅぇぉっゃゅょゎゕゖァィゥェォッャュョヮヵヶ々〻"
+        .contains(c)
+}
+
+/// Characters that must not end a column.
+fn is_no_end(c: char) -> bool {
+    "（［｛「『【〔〈《〘〖｟‘“".contains(c)
+}
+
+/// Disallows breaks between units according to the kinsoku rules.
+fn mark_breaks(units: &mut [Unit], text: &str) {
+    for i in 1..units.len() {
+        let first = text[units[i].text_range.clone()].chars().next();
+        let previous_last = text[units[i - 1].text_range.clone()].chars().next_back();
+        if first.is_some_and(is_no_start) || previous_last.is_some_and(is_no_end) {
+            units[i].break_before = false;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Column {
+    units: Range<usize>,
+    advance: scalar,
+    hard_break: bool,
+}
+
+/// Breaks `units` greedily into columns of at most `height`.
+fn break_columns(units: &[Unit], height: scalar) -> Vec<Column> {
+    let mut columns = Vec::new();
+    let mut start = 0;
+    while start < units.len() {
+        let mut advance = 0.0;
+        let mut end = start;
+        let mut hard_break = false;
+        while end < units.len() {
+            let unit = &units[end];
+            if unit.hard_break {
+                end += 1;
+                hard_break = true;
+                break;
+            }
+            // Whitespace may hang over the end of the column.
+            if end > start && !unit.whitespace && advance + unit.advance > height {
+                // Break at the last allowed position, or here if there is none.
+                if let Some(b) = (start + 1..=end).rev().find(|&b| units[b].break_before) {
+                    end = b;
+                }
+                break;
+            }
+            advance += unit.advance;
+            end += 1;
+        }
+        // Whitespace at the end of the column does not count.
+        let advance = units[start..end]
+            .iter()
+            .rposition(|u| !u.whitespace)
+            .map_or(0.0, |last| {
+                units[start..=start + last].iter().map(|u| u.advance).sum()
+            });
+        columns.push(Column {
+            units: start..end,
+            advance,
+            hard_break,
+        });
+        start = end;
+    }
+    columns
+}
+
+fn shape(
+    shaper: &Shaper,
+    utf8: &str,
+    font: &Font,
+    fallback: Option<FontMgr>,
+    locale: &str,
+    vertical: bool,
+) -> Vec<ShapedRun> {
+    let mut font_runs = Shaper::new_font_mgr_run_iterator(utf8, font, fallback);
+    let mut bidi_runs = shapers::primitive::trivial_bidi_run_iterator(0, utf8.len());
+    let mut script_runs =
+        Shaper::new_script_run_iterator(utf8, FourByteTag::from_chars('Z', 'y', 'y', 'y'));
+    let mut language_runs = Shaper::new_trivial_language_run_iterator(locale, utf8.len());
+    let features: Vec<Feature> = if vertical {
+        [('v', 'e', 'r', 't'), ('v', 'r', 't', '2')]
+            .into_iter()
+            .map(|(a, b, c, d)| Feature {
+                tag: *FourByteTag::from_chars(a, b, c, d),
+                value: 1,
+                start: 0,
+                end: utf8.len(),
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let lines = shaper.shape_to_runs_with_iterators_and_features(
+        utf8,
+        &mut font_runs,
+        &mut bidi_runs,
+        &mut script_runs,
+        &mut language_runs,
+        &features,
+        scalar::INFINITY,
+    );
+    // The text is not broken, so there is one line. The glyphs are positioned relative to its
+    // baseline.
+    lines
+        .into_iter()
+        .flat_map(|line| {
+            let baseline = line.baseline;
+            line.runs.into_iter().map(move |mut run| {
+                for position in &mut run.positions {
+                    position.y -= baseline;
+                }
+                run
+            })
+        })
+        .collect()
+}
+
+/// Splits the glyphs of `run` into clusters and returns the glyph index ranges and the UTF-8
+/// ranges of the clusters.
+fn clusters(
+    run: &ShapedRun,
+    text_len: usize,
+    next_cluster: usize,
+) -> Vec<(Range<usize>, Range<usize>)> {
+    let mut clusters: Vec<(Range<usize>, Range<usize>)> = Vec::new();
+    for (i, cluster) in run.clusters.iter().enumerate() {
+        let cluster = *cluster as usize;
+        match clusters.last_mut() {
+            Some((glyphs, text)) if text.start == cluster => glyphs.end = i + 1,
+            _ => {
+                if let Some((_, text)) = clusters.last_mut() {
+                    text.end = cluster;
+                }
+                clusters.push((i..i + 1, cluster..cluster));
+            }
+        }
+    }
+    if let Some((_, text)) = clusters.last_mut() {
+        text.end = next_cluster.min(text_len).max(text.start);
+    }
+    clusters
+}
+
+/// The first cluster of the run after `index`, or the end of the text.
+fn next_cluster(runs: &[ShapedRun], index: usize, text_len: usize) -> usize {
+    runs.get(index + 1)
+        .and_then(|run| run.clusters.first())
+        .map_or(text_len, |c| *c as usize)
+}
+
+fn upright_units(
+    context: &UnitContext,
+    runs: &[ShapedRun],
+    vertical_metrics: &mut VerticalMetricsCache,
+    units: &mut Vec<Unit>,
+) {
+    for (index, run) in runs.iter().enumerate() {
+        let font = &run.font;
+        let metrics = vertical_metrics.get(&font.typeface());
+        let scale = metrics
+            .as_ref()
+            .map_or(0.0, |m| font.size() / m.units_per_em);
+        let em_ascent = em_box_ascent(font);
+
+        let mut widths = vec![0.0; run.glyphs.len()];
+        let mut bounds = vec![Rect::default(); run.glyphs.len()];
+        font.get_widths_bounds(&run.glyphs, Some(&mut widths), Some(&mut bounds), None);
+
+        let text_len = context.text.len();
+        let next = next_cluster(runs, index, text_len);
+        for (glyphs, text) in clusters(run, text_len, next) {
+            let first = glyphs.start;
+            let vertical = metrics.as_ref().and_then(|m| m.get(run.glyphs[first]));
+            let (advance, baseline) = match vertical {
+                Some((advance, top_side_bearing)) => (
+                    advance * scale,
+                    top_side_bearing * scale - bounds[first].top,
+                ),
+                None => (font.size(), em_ascent),
+            };
+            let mut unit = context.unit(text, advance, font.spacing());
+            let origin_x = run.positions[first].x;
+            let mut glyph_run = GlyphRun {
+                font: font.clone(),
+                glyphs: Vec::with_capacity(glyphs.len()),
+                xforms: Vec::with_capacity(glyphs.len()),
+            };
+            for i in glyphs {
+                glyph_run.glyphs.push(run.glyphs[i]);
+                glyph_run.xforms.push(RSXform {
+                    scos: 1.0,
+                    ssin: 0.0,
+                    tx: run.positions[i].x - origin_x - widths[first] / 2.0,
+                    ty: baseline + run.positions[i].y,
+                });
+            }
+            unit.runs.push(glyph_run);
+            units.push(unit);
+        }
+    }
+}
+
+fn rotated_units(context: &UnitContext, runs: &[ShapedRun], units: &mut Vec<Unit>) {
+    let text = context.text;
+    let text_len = text.len();
+    let first_unit = units.len();
+
+    // A word starts after whitespace.
+    let mut word_start = true;
+    let mut unit_x = 0.0;
+    for (index, run) in runs.iter().enumerate() {
+        let font = &run.font;
+        let (_, metrics) = font.metrics();
+        // Centers the ascent and descent of the font on the column center.
+        let baseline_x = (metrics.ascent + metrics.descent) / 2.0;
+        let next = next_cluster(runs, index, text_len);
+        let clusters = clusters(run, text_len, next);
+        for (n, (glyphs, range)) in clusters.iter().enumerate() {
+            let is_whitespace = text[range.clone()].chars().all(char::is_whitespace);
+            let x = run.positions[glyphs.start].x;
+            if (word_start && !is_whitespace) || units.len() == first_unit {
+                unit_x = x;
+                units.push(context.unit(range.clone(), 0.0, font.spacing()));
+            }
+            word_start = is_whitespace;
+
+            let unit = units.last_mut().unwrap();
+            unit.text_range.end = context.offset + range.end;
+            unit.thickness = unit.thickness.max(font.spacing());
+            unit.whitespace = unit.whitespace && is_whitespace;
+            let end_x = clusters
+                .get(n + 1)
+                .map_or_else(|| run_end(runs, index), |(g, _)| run.positions[g.start].x);
+            unit.advance = end_x - unit_x;
+
+            if !unit.runs.last().is_some_and(|r| r.font == *font) {
+                unit.runs.push(GlyphRun {
+                    font: font.clone(),
+                    glyphs: Vec::new(),
+                    xforms: Vec::new(),
+                });
+            }
+            let glyph_run = unit.runs.last_mut().unwrap();
+            for i in glyphs.clone() {
+                let p = run.positions[i];
+                glyph_run.glyphs.push(run.glyphs[i]);
+                glyph_run.xforms.push(RSXform {
+                    scos: 0.0,
+                    ssin: 1.0,
+                    tx: baseline_x - p.y,
+                    ty: p.x - unit_x,
+                });
+            }
+        }
+    }
+}
+
+/// The horizontal end of the run at `index`.
+fn run_end(runs: &[ShapedRun], index: usize) -> scalar {
+    match runs.get(index + 1) {
+        Some(next) => next.positions.first().map_or(0.0, |p| p.x),
+        None => {
+            let run = &runs[index];
+            run.positions.first().map_or(0.0, |p| p.x) + run.advance.x
+        }
+    }
+}
+
+fn tate_chu_yoko_unit(context: &UnitContext, runs: &[ShapedRun], font: &Font) -> Option<Unit> {
+    let width = run_end(runs, runs.len().checked_sub(1)?);
+    let em = font.size();
+    // Groups that are wider than an em are condensed.
+    let scale = if width > em { em / width } else { 1.0 };
+    let baseline = em_box_ascent(font);
+
+    let mut unit = context.unit(0..context.text.len(), em, font.spacing());
+    for run in runs {
+        unit.runs.push(GlyphRun {
+            font: run.font.clone(),
+            glyphs: run.glyphs.clone(),
+            xforms: run
+                .positions
+                .iter()
+                .map(|p| RSXform {
+                    scos: scale,
+                    ssin: 0.0,
+                    tx: (p.x - width / 2.0) * scale,
+                    ty: baseline,
+                })
+                .collect(),
+        });
+    }
+    Some(unit)
+}
+
+/// The distance from the top of the em box to the baseline, if the em box is placed like the
+/// ascent and descent of the font.
+fn em_box_ascent(font: &Font) -> scalar {
+    let (_, metrics) = font.metrics();
+    let height = metrics.descent - metrics.ascent;
+    if height > 0.0 {
+        font.size() * -metrics.ascent / height
+    } else {
+        font.size() * 0.88
+    }
+}
+
+/// The vertical advances and top side bearings of a typeface, read from its `vhea` and `vmtx`
+/// tables.
+#[derive(Debug)]
+struct VerticalMetrics {
+    units_per_em: scalar,
+    /// The advance height and top side bearing of each glyph, in font units.
+    metrics: Vec<(u16, i16)>,
+    /// The top side bearings of the glyphs after the last glyph with an advance height.
+    bearings: Vec<i16>,
+}
+
+impl VerticalMetrics {
+    fn new(typeface: &Typeface) -> Option<Self> {
+        let vhea = typeface.copy_table_data(*FourByteTag::from_chars('v', 'h', 'e', 'a'))?;
+        let vmtx = typeface.copy_table_data(*FourByteTag::from_chars('v', 'm', 't', 'x'))?;
+        let vhea = vhea.as_bytes();
+        let vmtx = vmtx.as_bytes();
+        let long_metrics = u16::from_be_bytes(vhea.get(34..36)?.try_into().ok()?) as usize;
+        if long_metrics == 0 || vmtx.len() < long_metrics * 4 {
+            return None;
+        }
+        let metrics = vmtx[..long_metrics * 4]
+            .chunks_exact(4)
+            .map(|m| {
+                (
+                    u16::from_be_bytes([m[0], m[1]]),
+                    i16::from_be_bytes([m[2], m[3]]),
+                )
+            })
+            .collect();
+        let bearings = vmtx[long_metrics * 4..]
+            .chunks_exact(2)
+            .map(|b| i16::from_be_bytes([b[0], b[1]]))
+            .collect();
+        Some(Self {
+            units_per_em: typeface.units_per_em()? as scalar,
+            metrics,
+            bearings,
+        })
+    }
+
+    /// Returns the advance height and the top side bearing of `glyph` in font units.
+    fn get(&self, glyph: GlyphId) -> Option<(scalar, scalar)> {
+        let glyph = glyph as usize;
+        match self.metrics.get(glyph) {
+            Some((advance, bearing)) => Some((*advance as scalar, *bearing as scalar)),
+            None => {
+                let (advance, _) = self.metrics.last()?;
+                let bearing = self.bearings.get(glyph - self.metrics.len())?;
+                Some((*advance as scalar, *bearing as scalar))
+            }
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+struct VerticalMetricsCache(HashMap<TypefaceId, Option<Rc<VerticalMetrics>>>);
+
+impl VerticalMetricsCache {
+    fn get(&mut self, typeface: &Typeface) -> Option<Rc<VerticalMetrics>> {
+        self.0
+            .entry(typeface.unique_id())
+            .or_insert_with(|| VerticalMetrics::new(typeface).map(Rc::new))
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{segments, Orientation, VerticalParagraphBuilder};
+    use crate::{
+        icu,
+        textlayout::{FontCollection, TextStyle},
+        FontMgr, FontStyle,
+    };
+
+    #[test]
+    fn orientation_segments() {
+        let text = "縦書きのSkia 12月\n";
+        let orientations: Vec<(&str, Orientation)> = segments(text, 2)
+            .into_iter()
+            .map(|(range, o)| (&text[range], o))
+            .collect();
+        assert_eq!(
+            orientations,
+            [
+                ("縦書きの", Orientation::Upright),
+                ("Skia ", Orientation::Rotated),
+                ("12", Orientation::TateChuYoko),
+                ("月", Orientation::Upright),
+                ("\n", Orientation::HardBreak),
+            ]
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn columns_run_right_to_left() {
+        icu::init();
+
+        let font_mgr = FontMgr::new();
+        // The layout of the upright columns depends on a CJK font, which is not bundled.
+        if font_mgr
+            .match_family_style_character("", FontStyle::default(), &["ja"], '縦' as i32)
+            .is_none()
+        {
+            println!("Skipping columns_run_right_to_left, no font covers Japanese text");
+            return;
+        }
+
+        let mut font_collection = FontCollection::new();
+        font_collection.set_default_font_manager(font_mgr, None);
+        let mut style = TextStyle::new();
+        style.set_font_size(20.0);
+
+        let mut builder = VerticalParagraphBuilder::new(font_collection);
+        builder.add_text("縦書きのテキスト\nSkia", &style);
+        let mut paragraph = builder.build();
+        paragraph.layout(100.0);
+
+        let lines = paragraph.line_metrics();
+        assert!(lines.len() >= 3);
+        assert!(lines.iter().any(|line| line.hard_break));
+        for pair in lines.windows(2) {
+            assert!(pair[1].left < pair[0].left);
+            assert_eq!(pair[0].text_range.end, pair[1].text_range.start);
+        }
+        for line in lines {
+            assert!(line.height <= 100.0);
+        }
+        assert!(paragraph.width() > 0.0);
+    }
+}
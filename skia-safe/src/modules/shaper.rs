@@ -2,7 +2,9 @@ use std::{
     ffi::{CStr, CString},
     fmt,
     marker::PhantomData,
+    ops::Range,
     os::raw,
+    ptr,
 };
 
 use skia_bindings::{
@@ -11,14 +13,20 @@ use skia_bindings::{
     SkShaper_ScriptRunIterator, SkTextBlobBuilderRunHandler,
 };
 
-use crate::{prelude::*, scalar, Font, FontMgr, FourByteTag, Point, TextBlob};
+use crate::{
+    interop::VecSink, prelude::*, scalar, Font, FontMgr, FontStyle, FourByteTag, GlyphId, Point,
+    TextBlob, Vector,
+};
 
 // The following three are re-exported in `modules.rs` via `mod shapers {}`.
 pub(crate) mod core_text;
+mod font_fallback;
 pub(crate) mod harfbuzz;
 pub(crate) mod unicode;
 
+pub use font_fallback::*;
 pub use run_handler::RunHandler;
+use run_handler::{Buffer, RunInfo};
 
 pub type Shaper = RefHandle<SkShaper>;
 unsafe_send_sync!(Shaper);
@@ -161,8 +169,35 @@ impl Shaper {
         .borrows(utf8)
     }
 
-    // TODO: m79: wrap MakeFontMgrRunIterator with requestName (borrowed), requestStyle and
-    //       a LanguageRunIterator.
+    /// Creates a [`FontRunIterator`] that resolves characters `font` does not cover with
+    /// `fallback`, preferring the family `request_name` in `request_style`.
+    pub fn new_font_mgr_run_iterator_with_request<'a>(
+        utf8: &'a str,
+        font: &Font,
+        fallback: impl Into<Option<FontMgr>>,
+        request_name: Option<&str>,
+        request_style: FontStyle,
+    ) -> Borrows<'a, FontRunIterator> {
+        let bytes = utf8.as_bytes();
+        let (name_ptr, name_len) = request_name.map_or((ptr::null(), 0), |name| {
+            (name.as_ptr() as *const raw::c_char, name.len())
+        });
+        FontRunIterator::from_ptr(unsafe {
+            sb::C_SkShaper_MakeFontMgrRunIterator2(
+                bytes.as_ptr() as _,
+                bytes.len(),
+                font.native(),
+                fallback.into().into_ptr_or_null(),
+                name_ptr,
+                name_len,
+                request_style.native(),
+            )
+        })
+        .unwrap()
+        .borrows(utf8)
+    }
+
+    // TODO: m79: wrap MakeFontMgrRunIterator with a LanguageRunIterator.
 
     pub fn new_trivial_font_run_iterator(font: &Font, utf8_bytes: usize) -> FontRunIterator {
         FontRunIterator::from_ptr(unsafe {
@@ -262,7 +297,24 @@ impl Shaper {
         .borrows(utf8)
     }
 
-    // TODO: wrap MakeSkUnicodeHbScriptRunIterator (m88: uses type SkUnicode defined in src/).
+    /// Creates a script run iterator that uses the [`crate::shapers::unicode::Unicode`]
+    /// implementation selected by the cargo features to resolve the scripts of `utf8`. `script`
+    /// is the script of the runs before the first character with a resolved script.
+    pub fn new_sk_unicode_hb_script_run_iterator(
+        utf8: &str,
+        script: FourByteTag,
+    ) -> Borrows<ScriptRunIterator> {
+        let bytes = utf8.as_bytes();
+        ScriptRunIterator::from_ptr(unsafe {
+            sb::C_SkShaper_MakeSkUnicodeHbScriptRunIterator(
+                bytes.as_ptr() as _,
+                bytes.len(),
+                script.into_native(),
+            )
+        })
+        .unwrap()
+        .borrows(utf8)
+    }
 
     pub fn new_hb_icu_script_run_iterator(utf8: &str) -> Borrows<ScriptRunIterator> {
         let bytes = utf8.as_bytes();
@@ -738,6 +790,241 @@ impl Shaper {
     }
 }
 
+/// A run of glyphs that were shaped with one font, bidi level and script.
+#[derive(Clone, Debug)]
+pub struct ShapedRun {
+    pub font: Font,
+    pub bidi_level: u8,
+    /// The script of the script run the run is part of, as reported by the script run iterator
+    /// the text was shaped with.
+    pub script: FourByteTag,
+    /// The UTF-8 range of the text the run was shaped from.
+    pub utf8_range: Range<usize>,
+    pub glyphs: Vec<GlyphId>,
+    /// The positions of the glyphs, relative to the origin of the text passed to
+    /// [`Shaper::shape_to_runs()`].
+    pub positions: Vec<Point>,
+    pub advances: Vec<Vector>,
+    /// The UTF-8 offsets of the clusters of the glyphs in the text.
+    pub clusters: Vec<u32>,
+    pub advance: Vector,
+}
+
+impl ShapedRun {
+    /// Returns `true` if the run is right to left.
+    pub fn is_rtl(&self) -> bool {
+        self.bidi_level & 1 == 1
+    }
+}
+
+/// A line of [`ShapedRun`]s in visual order.
+#[derive(Clone, Default, Debug)]
+pub struct ShapedLine {
+    pub runs: Vec<ShapedRun>,
+    /// The vertical position of the baseline.
+    pub baseline: scalar,
+    /// The largest ascent of the fonts of the runs, negative above the baseline.
+    pub ascent: scalar,
+    pub descent: scalar,
+    pub leading: scalar,
+    pub width: scalar,
+}
+
+/// Collects the output of a [`Shaper`] in [`ShapedLine`]s, positioned like
+/// [`TextBlobBuilderRunHandler`] does.
+#[derive(Debug, Default)]
+struct ShapedRunCollector {
+    lines: Vec<ShapedLine>,
+    line: Option<ShapedLine>,
+    /// The top of the current line.
+    y: scalar,
+    /// The pen position of the next run.
+    x: scalar,
+    /// The pen position after the current run.
+    end: Point,
+    offsets: Vec<Point>,
+}
+
+impl ShapedRunCollector {
+    fn line(&mut self) -> &mut ShapedLine {
+        self.line.get_or_insert_with(ShapedLine::default)
+    }
+}
+
+impl RunHandler for ShapedRunCollector {
+    fn begin_line(&mut self) {
+        self.line = Some(ShapedLine::default());
+        self.x = 0.0;
+    }
+
+    fn run_info(&mut self, info: &RunInfo) {
+        let (_, metrics) = info.font.metrics();
+        let line = self.line();
+        line.ascent = line.ascent.min(metrics.ascent);
+        line.descent = line.descent.max(metrics.descent);
+        line.leading = line.leading.max(metrics.leading);
+        line.width += info.advance.x;
+    }
+
+    fn commit_run_info(&mut self) {
+        let y = self.y;
+        let line = self.line();
+        line.baseline = y - line.ascent;
+    }
+
+    fn run_buffer(&mut self, info: &RunInfo) -> Buffer {
+        let count = info.glyph_count;
+        let point = Point::new(self.x, self.line().baseline);
+        self.x += info.advance.x;
+        self.end = point + info.advance;
+        self.offsets.clear();
+        self.offsets.resize(count, Point::default());
+
+        let line = self.line.as_mut().unwrap();
+        line.runs.push(ShapedRun {
+            font: info.font.clone(),
+            bidi_level: info.bidi_level,
+            // Set by `assign_scripts()` after shaping, the run info does not contain the script.
+            script: FourByteTag::default(),
+            utf8_range: info.utf8_range.clone(),
+            glyphs: vec![0; count],
+            positions: vec![Point::default(); count],
+            advances: Vec::with_capacity(count),
+            clusters: vec![0; count],
+            advance: info.advance,
+        });
+        let run = line.runs.last_mut().unwrap();
+        Buffer {
+            glyphs: &mut run.glyphs,
+            positions: &mut run.positions,
+            offsets: Some(&mut self.offsets),
+            clusters: Some(&mut run.clusters),
+            point,
+        }
+    }
+
+    fn commit_run_buffer(&mut self, _info: &RunInfo) {
+        let end = self.end;
+        let Some(run) = self.line.as_mut().and_then(|line| line.runs.last_mut()) else {
+            return;
+        };
+        // Without the offsets, the positions are the pen positions, so their distances are the
+        // advances of the glyphs.
+        run.advances = run
+            .positions
+            .iter()
+            .zip(run.positions.iter().skip(1).chain(Some(&end)))
+            .map(|(p, next)| *next - *p)
+            .collect();
+        for (position, offset) in run.positions.iter_mut().zip(&self.offsets) {
+            *position += *offset;
+        }
+    }
+
+    fn commit_line(&mut self) {
+        if let Some(line) = self.line.take() {
+            self.y = line.baseline + line.descent + line.leading;
+            self.lines.push(line);
+        }
+    }
+}
+
+impl ShapedRunCollector {
+    /// Sets the script of every run to the script of the script run, given as its end and script,
+    /// the run starts in.
+    fn assign_scripts(lines: &mut [ShapedLine], script_runs: &[(usize, FourByteTag)]) {
+        for run in lines.iter_mut().flat_map(|line| line.runs.iter_mut()) {
+            let start = run.utf8_range.start;
+            if let Some((_, script)) = script_runs
+                .iter()
+                .find(|(end, _)| start < *end)
+                .or(script_runs.last())
+            {
+                run.script = *script;
+            }
+        }
+    }
+}
+
+impl Shaper {
+    /// Shapes `text` and returns the glyphs of the lines it was broken into at `width`.
+    ///
+    /// In contrast to [`Shaper::shape()`], no [`RunHandler`] has to be implemented, the runs own
+    /// their glyphs, positions, advances and clusters.
+    pub fn shape_to_runs(
+        &self,
+        text: &str,
+        font: &Font,
+        left_to_right: bool,
+        width: scalar,
+    ) -> Vec<ShapedLine> {
+        let mut collector = ShapedRunCollector::default();
+        self.shape(text, font, left_to_right, width, &mut collector);
+
+        // `shape()` creates its script run iterator internally, so the scripts are found by a
+        // separate one.
+        let mut script_runs = Vec::new();
+        let mut iterator =
+            Shaper::new_script_run_iterator(text, FourByteTag::from_chars('Z', 'y', 'y', 'y'));
+        while !iterator.at_end() {
+            iterator.consume();
+            script_runs.push((iterator.end_of_current_run(), iterator.current_script()));
+        }
+        ShapedRunCollector::assign_scripts(&mut collector.lines, &script_runs);
+        collector.lines
+    }
+
+    /// Shapes `utf8` with the given run iterators and features like
+    /// [`Shaper::shape_with_iterators_and_features()`] and returns the glyphs like
+    /// [`Shaper::shape_to_runs()`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn shape_to_runs_with_iterators_and_features(
+        &self,
+        utf8: &str,
+        font_run_iterator: &mut FontRunIterator,
+        bidi_run_iterator: &mut BiDiRunIterator,
+        script_run_iterator: &mut ScriptRunIterator,
+        language_run_iterator: &mut LanguageRunIterator,
+        features: &[Feature],
+        width: scalar,
+    ) -> Vec<ShapedLine> {
+        let mut collector = ShapedRunCollector::default();
+        // Records the script runs of `script_run_iterator` while the shaper consumes them.
+        let mut recording_iterator = ScriptRunIterator::from_ptr(unsafe {
+            sb::C_SkShaper_RecordingScriptRunIterator_new(script_run_iterator.native_mut())
+        })
+        .unwrap();
+        self.shape_with_iterators_and_features(
+            utf8,
+            font_run_iterator,
+            bidi_run_iterator,
+            &mut recording_iterator,
+            language_run_iterator,
+            features,
+            width,
+            &mut collector,
+        );
+
+        let mut ends = Vec::new();
+        let mut scripts = Vec::new();
+        let mut set_ends = |e: &[usize]| ends = e.to_vec();
+        let mut set_scripts = |s: &[sb::SkFourByteTag]| scripts = s.to_vec();
+        unsafe {
+            sb::C_SkShaper_RecordingScriptRunIterator_getRuns(
+                recording_iterator.native_mut(),
+                VecSink::new(&mut set_ends).native_mut(),
+                VecSink::new(&mut set_scripts).native_mut(),
+            )
+        }
+        let script_runs: Vec<_> = ends
+            .into_iter()
+            .zip(scripts.into_iter().map(FourByteTag::new))
+            .collect();
+        ShapedRunCollector::assign_scripts(&mut collector.lines, &script_runs);
+        collector.lines
+    }
+}
+
 pub(crate) mod shapers {
     use super::{BiDiRunIterator, ScriptRunIterator, Shaper};
 
@@ -799,7 +1086,10 @@ pub mod icu {
     ///
     /// If the default feature "embed-icudtl" is set, the `icudtl.dat` file is directly used from
     /// memory, so no `icudtl.dat` file is needed.
+    ///
+    /// If ICU is not used as the Unicode implementation, this function does nothing.
     pub fn init() {
+        #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
         skia_bindings::icu::init();
     }
 
@@ -0,0 +1,243 @@
+use std::ops::Range;
+
+use skia_bindings as sb;
+
+use super::{unicode::Unicode, FontRunIterator};
+use crate::{prelude::*, Font, FontMgr, FontStyle, Typeface};
+
+/// Resolves the typefaces of a text from an ordered list of preferred families, like the CSS
+/// `font-family` property.
+///
+/// Each grapheme cluster is set in the first family that covers all of its characters. If none
+/// does, the [`FontMgr`] is asked for a fallback typeface for the cluster's first character,
+/// considering the families in order, the style and the language tags.
+///
+/// The grapheme clusters are found with the [`Unicode`] implementation selected by the cargo
+/// features. If it is not available, every character is resolved on its own.
+#[derive(Debug)]
+pub struct FontFallback {
+    font_mgr: FontMgr,
+    families: Vec<String>,
+    style: FontStyle,
+    languages: Vec<String>,
+    typefaces: Vec<Option<Typeface>>,
+    unicode: Option<Unicode>,
+}
+
+/// A range of text that is set in one typeface.
+#[derive(Clone, Debug)]
+pub struct FontFallbackRun {
+    /// The UTF-8 range of the run.
+    pub range: Range<usize>,
+    pub typeface: Typeface,
+    /// The index of the family in [`FontFallback::families()`] the typeface was matched with,
+    /// `None` if it is a fallback typeface.
+    pub family_index: Option<usize>,
+}
+
+impl FontFallback {
+    pub fn new(
+        font_mgr: impl Into<FontMgr>,
+        families: &[impl AsRef<str>],
+        style: FontStyle,
+    ) -> Self {
+        let font_mgr = font_mgr.into();
+        let families: Vec<String> = families.iter().map(|f| f.as_ref().to_string()).collect();
+        let typefaces = families
+            .iter()
+            .map(|family| font_mgr.match_family_style(family, style))
+            .collect();
+        Self {
+            font_mgr,
+            families,
+            style,
+            languages: Vec::new(),
+            typefaces,
+            unicode: Unicode::new(),
+        }
+    }
+
+    /// Sets the BCP 47 language tags that are used to select fallback typefaces, the most
+    /// preferred first.
+    pub fn set_languages(&mut self, languages: &[impl AsRef<str>]) -> &mut Self {
+        self.languages = languages.iter().map(|l| l.as_ref().to_string()).collect();
+        self
+    }
+
+    pub fn families(&self) -> &[String] {
+        &self.families
+    }
+
+    pub fn style(&self) -> FontStyle {
+        self.style
+    }
+
+    pub fn languages(&self) -> &[String] {
+        &self.languages
+    }
+
+    /// Returns the typeface of the family at `index`, `None` if the font manager does not provide
+    /// the family.
+    pub fn family_typeface(&self, index: usize) -> Option<&Typeface> {
+        self.typefaces.get(index)?.as_ref()
+    }
+
+    /// Splits `text` into runs of the typefaces that cover them.
+    ///
+    /// Characters that no typeface covers are set in the first available family so that they are
+    /// shown as missing glyphs. Returns no runs if the font manager provides no typeface at all.
+    pub fn runs(&self, text: &str) -> Vec<FontFallbackRun> {
+        let languages: Vec<&str> = self.languages.iter().map(|l| l.as_str()).collect();
+        let mut fallbacks: Vec<Typeface> = Vec::new();
+        let mut runs: Vec<FontFallbackRun> = Vec::new();
+
+        let breaks = self
+            .unicode
+            .as_ref()
+            .and_then(|unicode| unicode.grapheme_breaks(text))
+            .unwrap_or_else(|| {
+                text.char_indices()
+                    .map(|(i, _)| i)
+                    .chain(Some(text.len()))
+                    .collect()
+            });
+
+        for cluster_range in breaks.windows(2) {
+            let (start, end) = (cluster_range[0], cluster_range[1]);
+            let cluster = &text[start..end];
+            let resolved = match cluster.chars().find(|c| !is_ignorable(*c)) {
+                // Ignorable characters only, for example a line break, stay in the current run.
+                None => runs
+                    .last()
+                    .map(|run| (run.typeface.clone(), run.family_index)),
+                Some(first) => self
+                    .typefaces
+                    .iter()
+                    .enumerate()
+                    .find_map(|(i, tf)| {
+                        tf.as_ref()
+                            .filter(|tf| covers(tf, cluster))
+                            .map(|tf| (tf.clone(), Some(i)))
+                    })
+                    .or_else(|| {
+                        fallbacks
+                            .iter()
+                            .find(|tf| covers(tf, cluster))
+                            .map(|tf| (tf.clone(), None))
+                    })
+                    .or_else(|| {
+                        let tf = self
+                            .families
+                            .iter()
+                            .map(|f| f.as_str())
+                            .chain(Some(""))
+                            .find_map(|family| {
+                                self.font_mgr.match_family_style_character(
+                                    family, self.style, &languages, first as _,
+                                )
+                            })?;
+                        fallbacks.push(tf.clone());
+                        Some((tf, None))
+                    }),
+            };
+            let Some((typeface, family_index)) =
+                resolved.or_else(|| self.first_available()).or_else(|| {
+                    let tf = self.font_mgr.legacy_make_typeface(None, self.style)?;
+                    Some((tf, None))
+                })
+            else {
+                return Vec::new();
+            };
+
+            match runs.last_mut() {
+                Some(run) if run.typeface.unique_id() == typeface.unique_id() => {
+                    run.range.end = end;
+                }
+                _ => runs.push(FontFallbackRun {
+                    range: start..end,
+                    typeface,
+                    family_index,
+                }),
+            }
+        }
+        runs
+    }
+
+    /// Returns a [`FontRunIterator`] over the runs of `text` that shapes them with `font` and the
+    /// typefaces of the runs.
+    pub fn run_iterator(&self, text: &str, font: &Font) -> FontRunIterator {
+        let runs = self.runs(text);
+        let (ends, fonts): (Vec<usize>, Vec<Font>) = if runs.is_empty() {
+            (vec![text.len()], vec![font.clone()])
+        } else {
+            runs.into_iter()
+                .map(|run| {
+                    let mut font = font.clone();
+                    font.set_typeface(run.typeface);
+                    (run.range.end, font)
+                })
+                .unzip()
+        };
+        FontRunIterator::from_ptr(unsafe {
+            sb::C_SkShaper_FontRunsIterator_new(ends.as_ptr(), fonts.native().as_ptr(), ends.len())
+        })
+        .unwrap()
+    }
+
+    fn first_available(&self) -> Option<(Typeface, Option<usize>)> {
+        self.typefaces
+            .iter()
+            .enumerate()
+            .find_map(|(i, tf)| tf.clone().map(|tf| (tf, Some(i))))
+    }
+}
+
+/// Returns `true` if `typeface` has glyphs for all the characters of `cluster` that are not
+/// ignorable.
+fn covers(typeface: &Typeface, cluster: &str) -> bool {
+    cluster
+        .chars()
+        .filter(|c| !is_ignorable(*c))
+        .all(|c| typeface.unichar_to_glyph(c as _) != 0)
+}
+
+/// Characters that are not shown and that fonts don't need to have glyphs for.
+fn is_ignorable(c: char) -> bool {
+    c.is_control()
+        || matches!(c,
+            '\u{00AD}' | '\u{034F}' | '\u{200B}'..='\u{200F}' | '\u{2028}'..='\u{202E}'
+            | '\u{2060}'..='\u{206F}' | '\u{FE00}'..='\u{FE0F}' | '\u{FEFF}'
+            | '\u{E0000}'..='\u{E0FFF}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FontFallback;
+    use crate::{FontMgr, FontStyle};
+
+    #[test]
+    #[serial_test::serial]
+    fn runs_cover_the_text() {
+        let font_mgr = FontMgr::new();
+        assert!(font_mgr.count_families() > 0, "no fonts installed");
+        let family = font_mgr.family_name(0);
+        let fallback = FontFallback::new(
+            font_mgr,
+            &["Family that does not exist", family.as_str()],
+            FontStyle::normal(),
+        );
+        assert!(fallback.family_typeface(0).is_none());
+
+        // The combining acute accent must stay in the run of its base character.
+        let text = "Hello, we\u{301}rld";
+        let runs = fallback.runs(text);
+        assert!(!runs.is_empty());
+        assert_eq!(runs.first().unwrap().range.start, 0);
+        assert_eq!(runs.last().unwrap().range.end, text.len());
+        for pair in runs.windows(2) {
+            assert_eq!(pair[0].range.end, pair[1].range.start);
+        }
+        assert!(runs.iter().all(|run| run.range.start != 9));
+        assert!(runs.iter().all(|run| run.family_index != Some(0)));
+    }
+}
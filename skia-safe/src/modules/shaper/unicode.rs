@@ -1,7 +1,9 @@
-use skia_bindings as sb;
+use std::fmt;
+
+use skia_bindings::{self as sb, SkUnicode};
 
 use super::BiDiRunIterator;
-use crate::prelude::*;
+use crate::{interop::VecSink, prelude::*};
 
 pub fn bidi_run_iterator(utf8: &str, bidi_level: u8) -> Option<Borrows<BiDiRunIterator>> {
     let bytes = utf8.as_bytes();
@@ -10,3 +12,89 @@ pub fn bidi_run_iterator(utf8: &str, bidi_level: u8) -> Option<Borrows<BiDiRunIt
     })
     .map(|i| i.borrows(utf8))
 }
+
+/// The Unicode implementation used for text shaping and paragraph layout.
+///
+/// Which implementations are available is defined by the cargo features: ICU is used by default,
+/// `unicode-icu4x` selects ICU4X, and `unicode-libgrapheme` selects libgrapheme.
+pub type Unicode = RCHandle<SkUnicode>;
+
+impl NativeRefCountedBase for SkUnicode {
+    type Base = sb::SkRefCntBase;
+}
+
+impl fmt::Debug for Unicode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Unicode").finish()
+    }
+}
+
+impl Unicode {
+    /// Creates the Unicode implementation selected by the cargo features.
+    pub fn new() -> Option<Self> {
+        #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+        crate::icu::init();
+
+        Self::from_ptr(unsafe { sb::C_SkUnicode_Make() })
+    }
+
+    #[cfg(not(any(feature = "unicode-icu4x", feature = "unicode-libgrapheme")))]
+    pub fn new_icu() -> Option<Self> {
+        crate::icu::init();
+
+        Self::from_ptr(unsafe { sb::C_SkUnicodes_ICU_Make() })
+    }
+
+    #[cfg(feature = "unicode-icu4x")]
+    pub fn new_icu4x() -> Option<Self> {
+        Self::from_ptr(unsafe { sb::C_SkUnicodes_ICU4X_Make() })
+    }
+
+    #[cfg(feature = "unicode-libgrapheme")]
+    pub fn new_libgrapheme() -> Option<Self> {
+        Self::from_ptr(unsafe { sb::C_SkUnicodes_Libgrapheme_Make() })
+    }
+
+    /// Returns the UTF-8 offsets in `utf8` at which extended grapheme clusters start, followed by
+    /// the length of the text. Returns `None` if the text could not be analyzed.
+    pub fn grapheme_breaks(&self, utf8: &str) -> Option<Vec<usize>> {
+        let mut breaks = Vec::new();
+        let mut set_breaks = |b: &[usize]| breaks = b.to_vec();
+        unsafe {
+            sb::C_SkUnicode_getGraphemeBreaks(
+                self.native_mut_force(),
+                utf8.as_ptr() as _,
+                utf8.len().try_into().ok()?,
+                VecSink::new(&mut set_breaks).native_mut(),
+            )
+        }
+        .then(|| {
+            if breaks.last() != Some(&utf8.len()) {
+                breaks.push(utf8.len());
+            }
+            breaks
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Unicode;
+
+    #[test]
+    #[serial_test::serial]
+    fn default_unicode_implementation() {
+        assert!(Unicode::new().is_some());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn grapheme_breaks() {
+        let unicode = Unicode::new().unwrap();
+        let text = "ae\u{301}\u{1F469}\u{200D}\u{1F4BB}\u{1F1E9}\u{1F1EA}\r\n";
+        assert_eq!(
+            unicode.grapheme_breaks(text).unwrap(),
+            [0, 1, 4, 15, 23, 25]
+        );
+    }
+}
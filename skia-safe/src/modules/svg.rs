@@ -12,9 +12,13 @@ use skia_bindings::{SkData, SkTypeface};
 use crate::{
     interop::{MemoryStream, NativeStreamBase, RustStream},
     prelude::*,
-    Canvas, Data, FontMgr, FontStyle, Size,
+    Canvas, Data, FontMgr, FontStyle, Path, Rect, Size,
 };
 
+mod icon_font;
+
+pub use icon_font::*;
+
 pub type Dom = RCHandle<sb::SkSVGDOM>;
 require_base_type!(sb::SkSVGDOM, sb::SkRefCnt);
 unsafe_send_sync!(Dom);
@@ -213,6 +217,42 @@ impl Dom {
         unsafe { sb::C_SkSVGDOM_setContainerSize(self.native_mut(), size.native()) }
     }
 
+    /// Sets the `width` and `height` of the root element, replacing the ones in the document.
+    ///
+    /// The `viewBox` of the root element is mapped to a viewport of this size.
+    pub fn set_root_size(&mut self, size: impl Into<Size>) {
+        let size = size.into();
+        unsafe { sb::C_SkSVGDOM_setRootSize(self.native_mut(), size.native()) }
+    }
+
+    /// The `viewBox` of the root element.
+    pub fn view_box(&self) -> Option<Rect> {
+        let mut view_box = Rect::default();
+        unsafe { sb::C_SkSVGDOM_getViewBox(self.native(), view_box.native_mut()) }
+            .if_true_some(view_box)
+    }
+
+    /// The size of the root element, resolved against the container size.
+    pub fn intrinsic_size(&self) -> Size {
+        Size::construct(|size| unsafe { sb::C_SkSVGDOM_intrinsicSize(self.native(), size) })
+    }
+
+    /// Renders the document into a container of the size `viewport` and returns the area that is
+    /// covered by the fills and strokes of its shapes, in the coordinates of the container.
+    ///
+    /// The root element's `width` and `height` are resolved against the container, use
+    /// [`Self::set_root_size()`] to map the `viewBox` to the whole container.
+    ///
+    /// Shapes with `fill="none"` contribute only their stroke outlines. Colors and opacity are
+    /// ignored. Text, images, clip paths, masks and elements that are referenced by `use` are not
+    /// included.
+    pub fn to_path(&self, viewport: impl Into<Size>) -> Path {
+        let viewport = viewport.into();
+        let mut path = Path::default();
+        unsafe { sb::C_SkSVGDOM_asPath(self.native(), viewport.native(), path.native_mut()) }
+        path
+    }
+
     fn handle_load_base64(data: &str) -> Data {
         let data: Vec<_> = data.split(',').collect();
         if data.len() > 1 {
@@ -297,6 +337,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn path_of_filled_and_stroked_shapes() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="10 10 24 24">
+            <rect x="14" y="14" width="16" height="16" fill="none" stroke="black" stroke-width="2"/>
+            <circle cx="22" cy="22" r="2"/>
+            <rect x="10" y="10" width="2" height="2" fill="none"/>
+            </svg>"#;
+        let dom = Dom::from_str(svg, FontMgr::new()).unwrap();
+        let path = dom.to_path((24.0, 24.0));
+        let bounds = path.bounds();
+        assert!((bounds.left - 3.0).abs() < 0.01);
+        assert!((bounds.bottom - 21.0).abs() < 0.01);
+        // The stroke of the unfilled square.
+        assert!(path.contains((4.0, 12.0)));
+        assert!(!path.contains((8.0, 12.0)));
+        // The filled circle in its center.
+        assert!(path.contains((12.0, 12.0)));
+        // The unfilled square without a stroke.
+        assert!(!path.contains((1.0, 1.0)));
+    }
+
     #[test]
     fn decoding_base64() {
         use std::str::from_utf8;
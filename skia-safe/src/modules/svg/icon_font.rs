@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+
+use super::{Dom, LoadError};
+use crate::{
+    matrix::ScaleToFit, utils::CustomTypefaceBuilder, Font, FontMetrics, FontMgr, GlyphId, Matrix,
+    PictureRecorder, Rect, Typeface, Unichar,
+};
+
+/// How [`IconFontBuilder`] turns an SVG document into a glyph.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum IconGlyphKind {
+    /// The areas covered by the fills and strokes of the shapes become the outline of the glyph,
+    /// which is drawn in the text color. See [`Dom::to_path()`] for what is not included.
+    #[default]
+    Outline,
+    /// The document is recorded as is and drawn in its own colors.
+    Color,
+}
+
+/// Builds a typeface from SVG icons.
+///
+/// Each icon is scaled so that the height of its `viewBox` is one em, and its advance is the
+/// width of the `viewBox` at that scale. The glyph ids are assigned in the order of the code
+/// points, starting at 1.
+#[derive(Debug)]
+pub struct IconFontBuilder {
+    font_mgr: FontMgr,
+    kind: IconGlyphKind,
+    ascent: f32,
+    icons: BTreeMap<Unichar, (Dom, Rect)>,
+}
+
+impl IconFontBuilder {
+    /// Creates a builder that resolves the fonts of text in the icons with `font_mgr`.
+    pub fn new(font_mgr: impl Into<FontMgr>) -> Self {
+        Self {
+            font_mgr: font_mgr.into(),
+            kind: IconGlyphKind::default(),
+            ascent: 0.8,
+            icons: BTreeMap::new(),
+        }
+    }
+
+    pub fn set_glyph_kind(&mut self, kind: IconGlyphKind) -> &mut Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Sets the part of the em that is above the baseline, 0.8 by default.
+    pub fn set_ascent(&mut self, ascent: f32) -> &mut Self {
+        self.ascent = ascent;
+        self
+    }
+
+    /// Adds the icon `svg` for `code_point`, replacing a previous one.
+    ///
+    /// Fails if the document can not be parsed, or if it has neither a `viewBox` nor a size.
+    pub fn add_icon(&mut self, code_point: Unichar, svg: &[u8]) -> Result<&mut Self, LoadError> {
+        let mut dom = Dom::from_bytes(svg, self.font_mgr.clone())?;
+        let view_box = dom
+            .view_box()
+            .unwrap_or_else(|| Rect::from_size(dom.intrinsic_size()));
+        if view_box.is_empty() {
+            return Err(LoadError);
+        }
+        // The `viewBox` is mapped to the viewport `(0, 0, width, height)`, independent of the size
+        // of the root element in the document.
+        dom.set_root_size(view_box.size());
+        dom.set_container_size(view_box.size());
+        self.icons.insert(code_point, (dom, view_box));
+        Ok(self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.icons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.icons.is_empty()
+    }
+
+    pub fn build(&self) -> Option<IconFont> {
+        let mut builder = CustomTypefaceBuilder::new();
+        let (top, bottom) = (-self.ascent, 1.0 - self.ascent);
+        let mut max_advance: f32 = 0.0;
+        let mut total_advance = 0.0;
+
+        for (index, (dom, view_box)) in self.icons.values().enumerate() {
+            let glyph_id = (index + 1) as GlyphId;
+            let advance = view_box.width() / view_box.height();
+            let bounds = Rect::new(0.0, top, advance, bottom);
+            let viewport = Rect::from_size(view_box.size());
+            let matrix = Matrix::rect_to_rect(viewport, bounds, ScaleToFit::Fill)?;
+            match self.kind {
+                IconGlyphKind::Outline => {
+                    let path = dom.to_path(view_box.size()).with_transform(&matrix);
+                    builder.set_glyph(glyph_id, advance, &path);
+                }
+                IconGlyphKind::Color => {
+                    let mut recorder = PictureRecorder::new();
+                    let canvas = recorder.begin_recording(bounds, None);
+                    canvas.concat(&matrix);
+                    canvas.clip_rect(viewport, None, None);
+                    dom.render(canvas);
+                    let drawable = recorder.finish_recording_as_drawable()?;
+                    builder.set_glyph(glyph_id, advance, (drawable, bounds));
+                }
+            }
+            max_advance = max_advance.max(advance);
+            total_advance += advance;
+        }
+
+        let mut metrics = FontMetrics::default();
+        metrics.top = top;
+        metrics.ascent = top;
+        metrics.descent = bottom;
+        metrics.bottom = bottom;
+        metrics.cap_height = self.ascent;
+        metrics.x_height = self.ascent;
+        metrics.x_max = max_advance;
+        metrics.max_char_width = max_advance;
+        metrics.avg_char_width = if self.icons.is_empty() {
+            0.0
+        } else {
+            total_advance / self.icons.len() as f32
+        };
+        builder.set_metrics(&metrics, None);
+
+        Some(IconFont {
+            typeface: builder.detach()?,
+            code_points: self.icons.keys().copied().collect(),
+        })
+    }
+}
+
+/// A typeface built by [`IconFontBuilder`], and the code points of its glyphs.
+///
+/// Custom typefaces have no character map, so text has to be converted to glyph ids with
+/// [`IconFont::str_to_glyphs()`] before it is drawn. To use a serialized icon font, restore it
+/// with [`IconFont::from_typeface()`] and the same code points.
+#[derive(Clone, Debug)]
+pub struct IconFont {
+    typeface: Typeface,
+    code_points: Vec<Unichar>,
+}
+
+impl IconFont {
+    /// Combines a deserialized icon font typeface with the code points it was built from.
+    pub fn from_typeface(
+        typeface: Typeface,
+        code_points: impl IntoIterator<Item = Unichar>,
+    ) -> Self {
+        let mut code_points: Vec<Unichar> = code_points.into_iter().collect();
+        code_points.sort_unstable();
+        code_points.dedup();
+        Self {
+            typeface,
+            code_points,
+        }
+    }
+
+    pub fn typeface(&self) -> &Typeface {
+        &self.typeface
+    }
+
+    /// The code points of the icons, in the order of their glyph ids.
+    pub fn code_points(&self) -> &[Unichar] {
+        &self.code_points
+    }
+
+    pub fn glyph_id(&self, code_point: Unichar) -> Option<GlyphId> {
+        let index = self.code_points.binary_search(&code_point).ok()?;
+        Some((index + 1) as GlyphId)
+    }
+
+    /// Returns the glyph ids of the characters of `text`, 0 for characters without an icon.
+    pub fn str_to_glyphs(&self, text: impl AsRef<str>) -> Vec<GlyphId> {
+        text.as_ref()
+            .chars()
+            .map(|c| self.glyph_id(c as Unichar).unwrap_or_default())
+            .collect()
+    }
+
+    /// The advance of the icon of `code_point` in ems.
+    pub fn advance(&self, code_point: Unichar) -> Option<f32> {
+        let glyph_id = self.glyph_id(code_point)?;
+        let font = Font::from_typeface(self.typeface.clone(), 1.0);
+        let mut widths = [0.0];
+        font.get_widths(&[glyph_id], &mut widths);
+        Some(widths[0])
+    }
+}
+
+impl From<IconFont> for Typeface {
+    fn from(icon_font: IconFont) -> Self {
+        icon_font.typeface
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{IconFont, IconFontBuilder, IconGlyphKind};
+    use crate::{
+        surfaces, typeface::SerializeBehavior, Color, Font, FontMgr, Paint, Point, Typeface,
+    };
+
+    const SQUARE: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="2" y="2" width="20" height="20" fill="red"/></svg>"#;
+    const WIDE: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 48 24"><circle cx="24" cy="12" r="10"/></svg>"#;
+
+    #[test]
+    fn build_and_serialize_icon_fonts() {
+        for kind in [IconGlyphKind::Outline, IconGlyphKind::Color] {
+            let mut builder = IconFontBuilder::new(FontMgr::new());
+            builder.set_glyph_kind(kind);
+            builder.add_icon(0xe001, WIDE.as_bytes()).unwrap();
+            builder.add_icon(0xe000, SQUARE.as_bytes()).unwrap();
+            assert!(builder.add_icon(0xe002, b"not svg").is_err());
+            let font = builder.build().unwrap();
+
+            assert_eq!(font.code_points(), [0xe000, 0xe001]);
+            assert_eq!(font.str_to_glyphs("\u{e001}\u{e000}x"), [2, 1, 0]);
+            assert!((font.advance(0xe000).unwrap() - 1.0).abs() < 0.01);
+            assert!((font.advance(0xe001).unwrap() - 2.0).abs() < 0.01);
+
+            let data = font.typeface().serialize(SerializeBehavior::DoIncludeData);
+            let typeface = Typeface::make_deserialize(Cursor::new(data.as_bytes()), None).unwrap();
+            let restored = IconFont::from_typeface(typeface, [0xe001, 0xe000]);
+            assert_eq!(restored.glyph_id(0xe001), Some(2));
+            assert!((restored.advance(0xe001).unwrap() - 2.0).abs() < 0.01);
+        }
+    }
+
+    /// The view box starts at 10, 10 and the root element is twice as large as the view box.
+    const OFFSET: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="48" height="48" viewBox="10 10 24 24"><rect x="12" y="12" width="20" height="20" fill="red"/></svg>"#;
+
+    #[test]
+    fn view_box_origin_and_root_size_are_ignored() {
+        // At 24 points one unit of the view box is one pixel, and the glyph covers 0..24 with the
+        // baseline at 19.2.
+        let font_size = 24.0;
+        let baseline = 0.8 * font_size;
+
+        let mut builder = IconFontBuilder::new(FontMgr::new());
+        builder.add_icon(0xe000, OFFSET.as_bytes()).unwrap();
+        let font = builder.build().unwrap();
+        let font = Font::from_typeface(font.typeface().clone(), font_size);
+        let bounds = font
+            .get_path(1)
+            .unwrap()
+            .bounds()
+            .with_offset((0.0, baseline));
+        assert!((bounds.left - 2.0).abs() < 0.1, "{bounds:?}");
+        assert!((bounds.top - 2.0).abs() < 0.1, "{bounds:?}");
+        assert!((bounds.right - 22.0).abs() < 0.1, "{bounds:?}");
+        assert!((bounds.bottom - 22.0).abs() < 0.1, "{bounds:?}");
+
+        let mut builder = IconFontBuilder::new(FontMgr::new());
+        builder.set_glyph_kind(IconGlyphKind::Color);
+        builder.add_icon(0xe000, OFFSET.as_bytes()).unwrap();
+        let font = builder.build().unwrap();
+        let font = Font::from_typeface(font.typeface().clone(), font_size);
+        let mut surface = surfaces::raster_n32_premul((24, 24)).unwrap();
+        surface.canvas().draw_glyphs_at(
+            &[1],
+            [Point::new(0.0, baseline)].as_slice(),
+            Point::default(),
+            &font,
+            &Paint::default(),
+        );
+        let pixmap = surface.peek_pixels().unwrap();
+        assert_eq!(pixmap.get_color((1, 1)), Color::TRANSPARENT);
+        assert_eq!(pixmap.get_color((3, 3)), Color::RED);
+        assert_eq!(pixmap.get_color((20, 20)), Color::RED);
+        assert_eq!(pixmap.get_color((23, 23)), Color::TRANSPARENT);
+    }
+}
@@ -1,5 +1,6 @@
 mod camera;
 mod custom_typeface;
+pub mod glyph_atlas;
 mod null_canvas;
 mod ordered_font_mgr;
 pub mod parse_path;
@@ -0,0 +1,381 @@
+//! Rasterizes glyphs of a [`Font`] and packs them into one or more atlas pages.
+//!
+//! This is meant for renderers that draw text with their own pipeline and use Skia only to
+//! prepare the glyph bitmaps.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    font::Edging, scalar, AlphaType, Bitmap, Canvas, Color, ColorType, Font, GlyphId, IPoint,
+    IRect, ISize, ImageInfo, Paint, PixelGeometry, Point, Rect, RoundOut, SurfaceProps,
+    SurfacePropsFlags,
+};
+
+/// The pixel format of the rasterized glyphs.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum GlyphFormat {
+    /// Anti-aliased coverage, one byte per pixel ([`ColorType::Alpha8`]).
+    A8,
+    /// Horizontal RGB subpixel coverage in the color channels of [`ColorType::N32`] pixels.
+    LCD,
+    /// Color glyphs (for example emoji) as premultiplied [`ColorType::N32`] pixels.
+    Color,
+}
+
+impl GlyphFormat {
+    fn color_type(self) -> ColorType {
+        match self {
+            GlyphFormat::A8 => ColorType::Alpha8,
+            GlyphFormat::LCD | GlyphFormat::Color => ColorType::N32,
+        }
+    }
+
+    fn edging(self) -> Edging {
+        match self {
+            GlyphFormat::A8 | GlyphFormat::Color => Edging::AntiAlias,
+            GlyphFormat::LCD => Edging::SubpixelAntiAlias,
+        }
+    }
+}
+
+/// A glyph that was placed in the atlas.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AtlasGlyph {
+    pub glyph_id: GlyphId,
+    /// The index of the page the glyph was rasterized into.
+    pub page: usize,
+    /// The pixel rectangle of the glyph in its page. Empty for glyphs without any pixels, for
+    /// example spaces.
+    pub rect: IRect,
+    /// [`Self::rect`] normalized to the page dimensions.
+    pub uv_rect: Rect,
+    /// The offset from the glyph's origin on the baseline to the top left corner of
+    /// [`Self::rect`].
+    pub bearing: IPoint,
+    /// The horizontal advance of the glyph.
+    pub advance: scalar,
+}
+
+/// The pages and glyph locations created by a [`GlyphAtlasBuilder`].
+#[derive(Debug)]
+pub struct GlyphAtlas {
+    format: GlyphFormat,
+    pages: Vec<Bitmap>,
+    glyphs: HashMap<GlyphId, AtlasGlyph>,
+}
+
+impl GlyphAtlas {
+    pub fn format(&self) -> GlyphFormat {
+        self.format
+    }
+
+    /// The atlas pages. Use [`Bitmap::pixmap()`] to access their pixels.
+    pub fn pages(&self) -> &[Bitmap] {
+        &self.pages
+    }
+
+    /// Returns the location of `glyph_id` in the atlas, `None` if the glyph was not requested or
+    /// did not fit into a page.
+    pub fn glyph(&self, glyph_id: GlyphId) -> Option<&AtlasGlyph> {
+        self.glyphs.get(&glyph_id)
+    }
+
+    pub fn glyphs(&self) -> impl Iterator<Item = &AtlasGlyph> {
+        self.glyphs.values()
+    }
+}
+
+/// Collects glyphs and builds a [`GlyphAtlas`].
+#[derive(Debug)]
+pub struct GlyphAtlasBuilder {
+    font: Font,
+    format: GlyphFormat,
+    page_size: ISize,
+    padding: i32,
+    glyphs: BTreeSet<GlyphId>,
+}
+
+impl GlyphAtlasBuilder {
+    /// Creates a builder that rasterizes glyphs with `font` into pages of `page_size` pixels.
+    ///
+    /// Returns `None` if the width or the height of `page_size` is not positive.
+    pub fn new(font: &Font, format: GlyphFormat, page_size: impl Into<ISize>) -> Option<Self> {
+        let page_size = page_size.into();
+        if page_size.is_empty() {
+            return None;
+        }
+        let mut font = font.clone();
+        font.set_edging(format.edging());
+        if format == GlyphFormat::Color {
+            font.set_embedded_bitmaps(true);
+        }
+        Some(Self {
+            font,
+            format,
+            page_size,
+            padding: 1,
+            glyphs: BTreeSet::new(),
+        })
+    }
+
+    /// Sets the number of empty pixels around every glyph, `1` by default.
+    pub fn set_padding(&mut self, padding: i32) -> &mut Self {
+        self.padding = padding.max(0);
+        self
+    }
+
+    pub fn add_glyphs(&mut self, glyphs: &[GlyphId]) -> &mut Self {
+        self.glyphs.extend(glyphs);
+        self
+    }
+
+    /// Adds the glyphs the font's typeface maps the characters of `str` to.
+    pub fn add_str(&mut self, str: impl AsRef<str>) -> &mut Self {
+        let glyphs = self.font.str_to_glyphs_vec(str);
+        self.add_glyphs(&glyphs)
+    }
+
+    /// Rasterizes and packs all added glyphs.
+    ///
+    /// Glyphs that do not fit into an empty page are left out. Returns `None` if the pixels of a
+    /// page can not be allocated.
+    pub fn build(&self) -> Option<GlyphAtlas> {
+        let glyphs: Vec<GlyphId> = self.glyphs.iter().copied().collect();
+        let mut widths = vec![0.0; glyphs.len()];
+        let mut bounds = vec![Rect::default(); glyphs.len()];
+        self.font
+            .get_widths_bounds(&glyphs, Some(&mut widths), Some(&mut bounds), None);
+
+        // LCD glyphs are filtered horizontally and spread into their neighbour pixels.
+        let lcd_outset = if self.format == GlyphFormat::LCD {
+            1
+        } else {
+            0
+        };
+
+        let mut entries: Vec<(GlyphId, IRect, scalar)> = glyphs
+            .iter()
+            .zip(bounds.iter().zip(widths.iter()))
+            .map(|(&glyph_id, (bounds, &advance))| {
+                let rect: IRect = bounds.round_out();
+                let rect = if rect.is_empty() {
+                    IRect::default()
+                } else {
+                    rect.with_outset((lcd_outset + 1, 1))
+                };
+                (glyph_id, rect, advance)
+            })
+            .collect();
+
+        // Packing the tallest glyphs first reduces the space wasted on the shelves.
+        entries.sort_by(|(_, a, _), (_, b, _)| b.height().cmp(&a.height()));
+
+        let mut packer = ShelfPacker::new(self.page_size, self.padding);
+        let mut placed = HashMap::with_capacity(entries.len());
+        let mut page_glyphs: Vec<Vec<(GlyphId, IPoint)>> = Vec::new();
+
+        for (glyph_id, glyph_rect, advance) in entries {
+            let Some((page, location)) = packer.place(glyph_rect.size()) else {
+                continue;
+            };
+            if page_glyphs.len() <= page {
+                page_glyphs.resize_with(page + 1, Vec::new);
+            }
+            let rect = IRect::from_pt_size(location, glyph_rect.size());
+            let bearing = IPoint::new(glyph_rect.left, glyph_rect.top);
+            if !rect.is_empty() {
+                // The origin of the glyph in page coordinates.
+                page_glyphs[page].push((glyph_id, location - bearing));
+            }
+            let (page_width, page_height) = (
+                self.page_size.width as scalar,
+                self.page_size.height as scalar,
+            );
+            placed.insert(
+                glyph_id,
+                AtlasGlyph {
+                    glyph_id,
+                    page,
+                    rect,
+                    uv_rect: Rect::new(
+                        rect.left as scalar / page_width,
+                        rect.top as scalar / page_height,
+                        rect.right as scalar / page_width,
+                        rect.bottom as scalar / page_height,
+                    ),
+                    bearing,
+                    advance,
+                },
+            );
+        }
+
+        let pages = page_glyphs
+            .iter()
+            .map(|glyphs| self.rasterize_page(glyphs))
+            .collect::<Option<_>>()?;
+
+        Some(GlyphAtlas {
+            format: self.format,
+            pages,
+            glyphs: placed,
+        })
+    }
+
+    fn rasterize_page(&self, glyphs: &[(GlyphId, IPoint)]) -> Option<Bitmap> {
+        let alpha_type = match self.format {
+            GlyphFormat::A8 | GlyphFormat::Color => AlphaType::Premul,
+            GlyphFormat::LCD => AlphaType::Opaque,
+        };
+        let info = ImageInfo::new(self.page_size, self.format.color_type(), alpha_type, None);
+        let mut bitmap = Bitmap::new();
+        if !bitmap.try_alloc_pixels_flags(&info) {
+            return None;
+        }
+
+        // LCD text requires an opaque destination and a known pixel geometry, so white glyphs are
+        // drawn on black and the color channels contain the coverage.
+        let (background, foreground) = match self.format {
+            GlyphFormat::A8 | GlyphFormat::Color => (Color::TRANSPARENT, Color::BLACK),
+            GlyphFormat::LCD => (Color::BLACK, Color::WHITE),
+        };
+        let props = SurfaceProps::new(SurfacePropsFlags::default(), PixelGeometry::RGBH);
+
+        {
+            let canvas = Canvas::from_bitmap(&bitmap, Some(&props))?;
+            canvas.clear(background);
+            let mut paint = Paint::default();
+            paint.set_color(foreground).set_anti_alias(true);
+            let (ids, positions): (Vec<GlyphId>, Vec<Point>) = glyphs
+                .iter()
+                .map(|(glyph_id, origin)| (*glyph_id, Point::from(*origin)))
+                .unzip();
+            canvas.draw_glyphs_at(
+                &ids,
+                positions.as_slice(),
+                Point::default(),
+                &self.font,
+                &paint,
+            );
+        }
+
+        bitmap.set_immutable();
+        Some(bitmap)
+    }
+}
+
+/// Places rectangles row by row into pages of a fixed size.
+#[derive(Debug)]
+struct ShelfPacker {
+    page_size: ISize,
+    padding: i32,
+    page: usize,
+    // The top, the height, and the horizontal fill of the current shelf.
+    shelf_top: i32,
+    shelf_height: i32,
+    shelf_x: i32,
+}
+
+impl ShelfPacker {
+    fn new(page_size: ISize, padding: i32) -> Self {
+        Self {
+            page_size,
+            padding,
+            page: 0,
+            shelf_top: padding,
+            shelf_height: 0,
+            shelf_x: padding,
+        }
+    }
+
+    /// Returns the page and the location of the top left corner for a rectangle of `size`.
+    fn place(&mut self, size: ISize) -> Option<(usize, IPoint)> {
+        if size.is_empty() {
+            return Some((self.page, IPoint::default()));
+        }
+        let (width, height) = (size.width + self.padding, size.height + self.padding);
+        if width + self.padding > self.page_size.width
+            || height + self.padding > self.page_size.height
+        {
+            return None;
+        }
+
+        if self.shelf_x + width > self.page_size.width {
+            self.shelf_top += self.shelf_height;
+            self.shelf_height = 0;
+            self.shelf_x = self.padding;
+        }
+        if self.shelf_top + height > self.page_size.height {
+            self.page += 1;
+            self.shelf_top = self.padding;
+            self.shelf_height = 0;
+            self.shelf_x = self.padding;
+        }
+
+        let location = IPoint::new(self.shelf_x, self.shelf_top);
+        self.shelf_x += width;
+        self.shelf_height = self.shelf_height.max(height);
+        Some((self.page, location))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GlyphAtlasBuilder, GlyphFormat};
+    use crate::{AlphaType, Color, ColorType, Contains, Font, FontMgr, FontStyle};
+
+    #[test]
+    fn build_a8_atlas() {
+        let typeface = FontMgr::new()
+            .legacy_make_typeface(None, FontStyle::normal())
+            .unwrap();
+        let font = Font::new(typeface, 24.0);
+        let atlas = GlyphAtlasBuilder::new(&font, GlyphFormat::A8, (64, 64))
+            .unwrap()
+            .add_str("The quick brown fox jumps over the lazy dog")
+            .build()
+            .unwrap();
+
+        assert!(atlas.pages().len() > 1);
+        let space = atlas.glyph(font.unichar_to_glyph(' ' as _)).unwrap();
+        assert!(space.rect.is_empty());
+        assert!(space.advance > 0.0);
+        for glyph in atlas.glyphs() {
+            let page = &atlas.pages()[glyph.page];
+            assert!(page.bounds().contains(glyph.rect));
+        }
+    }
+
+    #[test]
+    fn build_lcd_atlas() {
+        let typeface = FontMgr::new()
+            .legacy_make_typeface(None, FontStyle::normal())
+            .unwrap();
+        let font = Font::new(typeface, 24.0);
+        let atlas = GlyphAtlasBuilder::new(&font, GlyphFormat::LCD, (256, 256))
+            .unwrap()
+            .add_str("H")
+            .build()
+            .unwrap();
+
+        assert_eq!(atlas.pages().len(), 1);
+        let page = &atlas.pages()[0];
+        assert_eq!(page.color_type(), ColorType::N32);
+        assert_eq!(page.alpha_type(), AlphaType::Opaque);
+        // The background is black, the coverage is in the color channels of the glyph pixels.
+        assert_eq!(page.get_color((0, 0)), Color::BLACK);
+        let glyph = atlas.glyph(font.unichar_to_glyph('H' as _)).unwrap();
+        let covered = (glyph.rect.top..glyph.rect.bottom)
+            .flat_map(|y| (glyph.rect.left..glyph.rect.right).map(move |x| (x, y)))
+            .map(|p| page.get_color(p))
+            .filter(|color| color.r() > 0 || color.g() > 0 || color.b() > 0)
+            .count();
+        assert!(covered > 0);
+    }
+
+    #[test]
+    fn reject_empty_pages() {
+        let font = Font::default();
+        assert!(GlyphAtlasBuilder::new(&font, GlyphFormat::Color, (0, 64)).is_none());
+        assert!(GlyphAtlasBuilder::new(&font, GlyphFormat::Color, (64, -1)).is_none());
+    }
+}
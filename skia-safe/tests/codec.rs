@@ -1,4 +1,4 @@
-//! Tests for the various image encoder and decoders skia-safe supports by default.
+//! Tests for the various image encoder and decoders skia-safe supports.
 use std::io;
 
 use skia_safe::{
@@ -6,22 +6,14 @@ use skia_safe::{
     Bitmap, Codec, Data, EncodedImageFormat,
 };
 
-/// The supported encoders.
-const STANDARD_ENCODERS: &[EncodedImageFormat] =
-    &[EncodedImageFormat::JPEG, EncodedImageFormat::PNG];
-
-/// The supported decoders.
-const STANDARD_DECODERS: &[EncodedImageFormat] = &[
-    EncodedImageFormat::BMP,
-    EncodedImageFormat::GIF,
-    EncodedImageFormat::ICO,
-    EncodedImageFormat::JPEG,
-    EncodedImageFormat::PNG,
-    EncodedImageFormat::WBMP,
-];
-
 fn supported_encoders() -> Vec<EncodedImageFormat> {
-    let mut r = STANDARD_ENCODERS.to_vec();
+    let mut r = Vec::new();
+    if cfg!(feature = "jpeg-encode") {
+        r.push(EncodedImageFormat::JPEG);
+    }
+    if cfg!(feature = "png-encode") {
+        r.push(EncodedImageFormat::PNG);
+    }
     if cfg!(feature = "webp-encode") {
         r.push(EncodedImageFormat::WEBP);
     }
@@ -29,7 +21,25 @@ fn supported_encoders() -> Vec<EncodedImageFormat> {
 }
 
 fn supported_decoders() -> Vec<EncodedImageFormat> {
-    let mut r = STANDARD_DECODERS.to_vec();
+    let mut r = Vec::new();
+    if cfg!(feature = "bmp") {
+        r.push(EncodedImageFormat::BMP);
+    }
+    if cfg!(feature = "gif") {
+        r.push(EncodedImageFormat::GIF);
+    }
+    if cfg!(feature = "ico") {
+        r.push(EncodedImageFormat::ICO);
+    }
+    if cfg!(feature = "jpeg-decode") {
+        r.push(EncodedImageFormat::JPEG);
+    }
+    if cfg!(feature = "png-decode") {
+        r.push(EncodedImageFormat::PNG);
+    }
+    if cfg!(feature = "wbmp") {
+        r.push(EncodedImageFormat::WBMP);
+    }
     if cfg!(feature = "webp-decode") {
         r.push(EncodedImageFormat::WEBP);
     }
@@ -95,31 +105,37 @@ type DecoderTest = (EncodedImageFormat, fn() -> Decoder, &'static [u8]);
 
 // image files copied from skia/resources/images
 const DECODER_TESTS: &[DecoderTest] = &[
+    #[cfg(feature = "bmp")]
     (
         EncodedImageFormat::BMP,
         codec::bmp_decoder::decoder,
         include_bytes!("images/randPixels.bmp"),
     ),
+    #[cfg(feature = "gif")]
     (
         EncodedImageFormat::GIF,
         codec::gif_decoder::decoder,
         include_bytes!("images/box.gif"),
     ),
+    #[cfg(feature = "ico")]
     (
         EncodedImageFormat::ICO,
         codec::ico_decoder::decoder,
         include_bytes!("images/color_wheel.ico"),
     ),
+    #[cfg(feature = "jpeg-decode")]
     (
         EncodedImageFormat::JPEG,
         codec::jpeg_decoder::decoder,
         include_bytes!("images/color_wheel.jpg"),
     ),
+    #[cfg(feature = "png-decode")]
     (
         EncodedImageFormat::PNG,
         codec::png_decoder::decoder,
         include_bytes!("images/mandrill_16.png"),
     ),
+    #[cfg(feature = "wbmp")]
     (
         EncodedImageFormat::WBMP,
         codec::wbmp_decoder::decoder,
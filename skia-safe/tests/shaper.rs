@@ -47,7 +47,7 @@ impl RunHandler for DebugRunHandler {
 #[cfg(test)]
 mod tests {
     use crate::DebugRunHandler;
-    use skia_safe::{shapers, Font, Shaper};
+    use skia_safe::{shaper::Feature, shapers, Font, FourByteTag, Shaper};
 
     #[test]
     #[serial_test::serial]
@@ -62,6 +62,93 @@ mod tests {
         );
     }
 
+    #[test]
+    #[serial_test::serial]
+    fn test_shape_to_runs() {
+        let text = "Hello العربية";
+        let lines = Shaper::new(None).shape_to_runs(text, &Font::default(), true, 10000.0);
+        assert_eq!(lines.len(), 1);
+        let runs = &lines[0].runs;
+        assert!(runs.len() >= 2);
+        assert!(runs.iter().any(|run| run.is_rtl()));
+        for run in runs {
+            assert_eq!(run.glyphs.len(), run.positions.len());
+            assert_eq!(run.glyphs.len(), run.advances.len());
+            assert_eq!(run.glyphs.len(), run.clusters.len());
+            for cluster in &run.clusters {
+                assert!(run.utf8_range.contains(&(*cluster as usize)));
+            }
+            let advance: f32 = run.advances.iter().map(|a| a.x).sum();
+            assert!((advance - run.advance.x).abs() < 0.01);
+        }
+        let width: f32 = runs.iter().map(|run| run.advance.x).sum();
+        assert!((width - lines[0].width).abs() < 0.01);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_shape_to_runs_with_iterators_and_features() {
+        let text = "office";
+        let font = Font::default();
+        let mut font_runs = Shaper::new_trivial_font_run_iterator(&font, text.len());
+        let mut bidi_runs = shapers::primitive::trivial_bidi_run_iterator(0, text.len());
+        let mut script_runs =
+            Shaper::new_script_run_iterator(text, FourByteTag::from_chars('Z', 'y', 'y', 'y'));
+        let mut language_runs = Shaper::new_trivial_language_run_iterator("en", text.len());
+        // Disables ligatures, so every character is shaped to its own glyph.
+        let features = [Feature {
+            tag: *FourByteTag::from_chars('l', 'i', 'g', 'a'),
+            value: 0,
+            start: 0,
+            end: text.len(),
+        }];
+        let lines = Shaper::new(None).shape_to_runs_with_iterators_and_features(
+            text,
+            &mut font_runs,
+            &mut bidi_runs,
+            &mut script_runs,
+            &mut language_runs,
+            &features,
+            10000.0,
+        );
+        assert_eq!(lines.len(), 1);
+        let glyphs: usize = lines[0].runs.iter().map(|run| run.glyphs.len()).sum();
+        assert_eq!(glyphs, text.len());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_shape_to_runs_reports_the_scripts_of_the_script_run_iterator() {
+        let text = "office";
+        let font = Font::default();
+        let shaper = Shaper::new(None);
+
+        let lines = shaper.shape_to_runs(text, &font, true, 10000.0);
+        let latin = FourByteTag::from_chars('L', 'a', 't', 'n');
+        assert!(lines[0].runs.iter().all(|run| run.script == latin));
+
+        // The trivial script run iterator reports the tag 0 for the whole text.
+        let mut font_runs = Shaper::new_trivial_font_run_iterator(&font, text.len());
+        let mut bidi_runs = shapers::primitive::trivial_bidi_run_iterator(0, text.len());
+        let mut script_runs = shapers::primitive::trivial_script_run_iterator(0, text.len());
+        let mut language_runs = Shaper::new_trivial_language_run_iterator("en", text.len());
+        let lines = shaper.shape_to_runs_with_iterators_and_features(
+            text,
+            &mut font_runs,
+            &mut bidi_runs,
+            &mut script_runs,
+            &mut language_runs,
+            &[],
+            10000.0,
+        );
+        assert_eq!(lines.len(), 1);
+        assert!(!lines[0].runs.is_empty());
+        assert!(lines[0]
+            .runs
+            .iter()
+            .all(|run| run.script == FourByteTag::new(0)));
+    }
+
     #[test]
     #[serial_test::serial]
     fn test_skunicode_parameterized_shaper() {
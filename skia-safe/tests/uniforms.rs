@@ -0,0 +1,97 @@
+#![cfg(feature = "derive")]
+use skia_safe::{
+    runtime_effect::{BuilderError, Uniforms},
+    Color4f, Matrix, Point, RuntimeEffect, RuntimeEffectBuilder,
+};
+
+const SKSL: &str = r#"
+    uniform float2 center;
+    uniform float u_time;
+    layout(color) uniform half4 tint;
+    uniform float weights[3];
+    uniform float3x3 transform;
+
+    half4 main(float2 p) {
+        float3 q = transform * float3(p - center, 1);
+        float w = weights[0] + weights[1] + weights[2];
+        return tint * half(sin(length(q.xy) * w - u_time));
+    }
+"#;
+
+#[derive(Uniforms)]
+#[repr(C)]
+struct Ripple {
+    center: Point,
+    #[uniform(name = "u_time")]
+    time: f32,
+    tint: Color4f,
+    #[uniform(array)]
+    weights: [f32; 3],
+    transform: Matrix,
+}
+
+#[allow(dead_code)]
+#[derive(Uniforms)]
+struct Reordered {
+    #[uniform(name = "u_time")]
+    time: f32,
+    center: Point,
+}
+
+#[derive(Uniforms)]
+struct Incomplete {
+    center: Point,
+}
+
+#[test]
+fn derived_uniforms_match_the_effect() {
+    let effect = RuntimeEffect::make_for_shader(SKSL, None).unwrap();
+    Ripple::check(&effect).unwrap();
+
+    let ripple = Ripple {
+        center: Point::new(1.0, 2.0),
+        time: 3.0,
+        tint: Color4f::new(0.0, 0.5, 1.0, 1.0),
+        weights: [4.0, 5.0, 6.0],
+        transform: Matrix::translate((7.0, 8.0)),
+    };
+    let data = ripple.to_data();
+    assert_eq!(data.size(), effect.uniform_size());
+
+    let read = |name: &str, i: usize| {
+        let offset = effect.find_uniform(name).unwrap().offset() + i * 4;
+        f32::from_ne_bytes(data.as_bytes()[offset..offset + 4].try_into().unwrap())
+    };
+    assert_eq!(read("center", 1), 2.0);
+    assert_eq!(read("u_time", 0), 3.0);
+    assert_eq!(read("tint", 1), 0.5);
+    assert_eq!(read("weights", 2), 6.0);
+    assert_eq!(read("transform", 7), 8.0);
+
+    let mut builder = RuntimeEffectBuilder::new(effect);
+    builder.set_uniforms(&ripple).unwrap();
+    assert_eq!(builder.uniforms(), data.as_bytes());
+    assert!(builder.make_shader(None).is_some());
+}
+
+#[test]
+fn mismatches_are_reported() {
+    let effect = RuntimeEffect::make_for_shader(SKSL, None).unwrap();
+    assert_eq!(
+        Reordered::check(&effect).unwrap_err(),
+        BuilderError::UniformOrder {
+            name: "u_time".into(),
+            expected: 1,
+            actual: 0
+        }
+    );
+    assert_eq!(
+        Incomplete::check(&effect).unwrap_err(),
+        BuilderError::MissingUniform("u_time".into())
+    );
+    assert!(RuntimeEffectBuilder::new(effect)
+        .set_uniforms(&Incomplete {
+            center: Point::default()
+        })
+        .is_err());
+}